@@ -0,0 +1,35 @@
+use using::{using, using_build, Setters, UsingBuilder};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Setters)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+    #[setter(with)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, UsingBuilder)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+    #[builder(default)]
+    pub threads: u8,
+    #[setter(strip_option)]
+    pub proxy: Option<String>,
+}
+
+pub fn named_and_move() -> Point {
+    using!(point @ Point::default() => {
+        .x = 1;
+        .y += point.x;
+        move .with_label(Some("a".to_string()));
+    })
+}
+
+pub fn builder() -> Result<Server, ServerBuilderError> {
+    using_build!(Server::builder() => {
+        .host("localhost".to_string());
+        .port(80);
+        .proxy("proxy".to_string());
+    })
+}
@@ -0,0 +1,16 @@
+//! Uses the macros of `using` in a crate with pedantic lints, so that lints triggered by the
+//! generated code are caught by `cargo clippy --workspace`.
+
+#![deny(clippy::all, clippy::pedantic, clippy::nursery)]
+#![allow(
+    clippy::missing_const_for_fn,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::must_use_candidate
+)]
+
+#[cfg(feature = "derive")]
+pub mod derives;
+pub mod statements;
+#[cfg(feature = "std")]
+pub mod std_macros;
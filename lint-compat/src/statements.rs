@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::num::ParseIntError;
+use std::sync::Mutex;
+
+use using::{
+    assert_using, cascade, using, using_all, using_cell, using_clone, using_cow, using_default,
+    using_do, using_each, using_errors, using_fn, using_in, using_keep, using_lazy, using_lock,
+    using_map, using_new, using_ok, using_pipe, using_record, using_set, using_some, using_take,
+    using_validated, using_vec, using_with, with, Validate,
+};
+
+pub struct Logger {
+    pub level: u8,
+}
+
+impl Logger {
+    pub const fn set_level(&mut self, level: u8) {
+        self.level = level;
+    }
+}
+
+#[derive(Default)]
+pub struct Config {
+    pub logger: Option<Logger>,
+    pub values: Vec<i32>,
+}
+
+fn add(values: &mut Vec<i32>, value: i32) {
+    values.push(value);
+}
+
+fn check(values: &[i32]) -> Result<usize, String> {
+    if values.is_empty() {
+        return Err("empty".to_string());
+    }
+    Ok(values.len())
+}
+
+pub fn statements(flag: bool) -> Vec<i32> {
+    using!(Vec::new() => {
+        .push(1);
+        .push(2);
+        .push(3);
+        .push(4);
+        .push(5);
+        let len = .len();
+        if flag {
+            .push(6);
+        } else if len > 3 {
+            .pop();
+        } else {
+            .clear();
+        }
+        match len {
+            0 => .push(0),
+            _ => {
+                .push(1);
+            }
+        }
+        for value in 0..2 {
+            .push(value);
+        }
+        let mut count = .len();
+        while count < 10 {
+            .push(0);
+            count += 1;
+        }
+        while let Some(value) = .pop() {
+            if value == 0 {
+                break;
+            }
+        }
+        loop {
+            .push(1);
+            break;
+        }
+        _ .len();
+        .>add(7);
+    })
+}
+
+pub fn trailing() -> usize {
+    using!(Vec::new() => {
+        .push(1);
+        .len()
+    })
+}
+
+pub fn question_mark() -> Result<Vec<i32>, String> {
+    Ok(using!(Vec::new() => {
+        .push(1);
+        let len = .>check()?;
+        .push(i32::try_from(len).map_err(|err| err.to_string())?);
+    }))
+}
+
+pub fn optional_chaining() -> Config {
+    using!(Config::default() => {
+        .logger = Some(Logger { level: 0 });
+        .logger?.set_level(1);
+        .values.push(1);
+    })
+}
+
+pub fn checkpoint(values: &mut Vec<i32>) -> Result<(), String> {
+    using_in!(values => {
+        checkpoint auto;
+        .push(1);
+        .>check()?;
+        checkpoint;
+        .push(2);
+        rollback;
+    });
+    Ok(())
+}
+
+pub fn ref_mut(point: &mut Point) {
+    using!(ref mut *point => {
+        .x = 1;
+        .y *= 2;
+    });
+}
+
+pub fn builder_errors() -> Result<Vec<i32>, Vec<String>> {
+    using_errors!(Vec::new() => {
+        .push(1);
+        .>check()?;
+    })
+}
+
+#[derive(Clone, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+pub fn cascades() -> (Vec<i32>, Point) {
+    let vec = cascade! {
+        Vec::new();
+        ..push(1);
+        ..push(2);
+    };
+    let point = with! {
+        point = Point::default();
+        point.x = 1;
+    };
+    (vec, point)
+}
+
+pub fn variants(point: &Point, cell: &RefCell<Vec<i32>>, mutex: &Mutex<Vec<i32>>) -> Vec<i32> {
+    let clone = using_clone!(point => {
+        .x = 2;
+    });
+    let default = using_default!(Vec<i32> => {
+        .push(clone.x);
+    });
+    let new = using_new!(HashMap<i32, i32> => {
+        .insert(1, 2);
+    });
+    using_cell!(cell => {
+        .push(1);
+    });
+    using_lock!(mutex => {
+        .push(new.len().try_into().unwrap_or_default());
+    });
+    let some = using_some!(Some(Vec::new()) => {
+        .push(1);
+    });
+    let ok = using_ok!(Ok::<_, ()>(Vec::new()) => {
+        .push(1);
+    });
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    using_all!(a, b => {
+        .push(1);
+    });
+    let map: HashMap<_, _> = using_map! { 1 => 2 };
+    let vec = using_vec![1, 2];
+    [default, some.unwrap_or_default(), ok.unwrap_or_default(), a, b, vec, map.into_keys().collect()].concat()
+}
+
+pub struct Client {
+    pub connection: Option<Vec<&'static str>>,
+}
+
+#[derive(Default)]
+pub struct Range {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Validate for Range {
+    type Error = &'static str;
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.start <= self.end { Ok(()) } else { Err("start must not be after end") }
+    }
+}
+
+using_fn! {
+    /// Returns a vector containing `1` and `2`.
+    pub fn one_two(Vec::new()) -> Vec<i32> {
+        .push(1);
+        .push(2);
+    }
+
+    pub fn extended(vec @ base, base: Vec<i32>, value: i32) -> usize {
+        .push(value);
+        vec.len()
+    }
+}
+
+pub fn more_variants(client: &mut Client, input: &str, path: &str) -> Result<usize, ParseIntError> {
+    let len = using_take!(client.connection => {
+        .push("ping");
+        .len()
+    } else {
+        0
+    });
+    let _ = using_take!(move client.connection => {
+        .push("close");
+    });
+    let vecs: Vec<_> = using_each!(vec in [vec![1], vec![2, 3]] => {
+        .push(0);
+    });
+    let (_, with_len) = using_with!(Vec::new() => {
+        .push(1);
+        .len()
+    });
+    let mut out = String::new();
+    using_do!(Vec::new() => {
+        .push("a");
+        .push("b");
+        let joined = .join(", ");
+        out.push_str(&joined);
+    });
+    let kept = using_keep!(vec @ Vec::new() => {
+        .push(1);
+        assert_eq!(vec.len(), 1);
+    });
+    let configure = using_lazy!(Vec<usize> => {
+        .push(with_len);
+    });
+    let mut lazy = Vec::new();
+    configure(&mut lazy);
+    let parsed = using_pipe!(input => {
+        .trim();
+        .parse::<usize>()?;
+        @ * 2
+    });
+    let set: HashSet<_> = using_set![
+        1,
+        for i in 0..4 {
+            i * i,
+        }
+    ];
+    let range = using_validated!(Range::default() => {
+        .start = 1;
+        .end = 2;
+    });
+    let (recorded, records): (_, Vec<_>) = using_record!(Vec::new() => {
+        .push(1);
+    });
+    assert_using!(recorded => {
+        .len() == 1;
+    });
+    let cow = using_cow!(Cow::Borrowed(path) => {
+        let slash = .ends_with('/');
+        if !slash {
+            mut .push('/');
+        }
+    });
+    Ok(len + out.len() + vecs.len() + kept.len() + lazy.len() + parsed + set.len() + records.len()
+        + cow.len() + usize::from(range.is_ok()) + extended(one_two(), 3))
+}
@@ -0,0 +1,48 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use using::{using_catch, using_global, using_ptr, using_string, using_tls};
+
+thread_local! {
+    static VALUES: RefCell<Vec<i32>> = const { RefCell::new(Vec::new()) };
+    static COUNTER: Cell<u32> = const { Cell::new(0) };
+}
+
+static GLOBAL: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+
+pub fn globals() -> usize {
+    using_tls!(VALUES => {
+        .push(1);
+    });
+    using_global!(GLOBAL => {
+        .push(1);
+        .len()
+    })
+}
+
+pub fn std_variants() -> usize {
+    let query = using_string!(query @ {
+        "SELECT ";
+        for (i, column) in [ "id", "name" ].iter().enumerate() {
+            if i > 0 {
+                ", ";
+            }
+            += column;
+        }
+        if query.len() > 80 {
+            '\n';
+        }
+    });
+    let rc = using_ptr!(Rc::new(Vec::new()) => {
+        .push(1);
+    });
+    let count = using_tls!(ref COUNTER => {
+        .set(1);
+        .get()
+    });
+    let caught = using_catch!(Vec::new() => {
+        .push(1);
+    });
+    query.len() + rc.len() + usize::try_from(count).unwrap_or_default() + caught.map_or(0, |vec| vec.len())
+}
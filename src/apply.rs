@@ -0,0 +1,227 @@
+/// An extension trait for cascading with closures instead of macros.
+///
+/// This trait is implemented for all types. [`apply`](Apply::apply) calls a closure with a mutable
+/// reference to `self` and returns `self`, which works like
+/// [`using`](crate::using) in contexts where macros cannot or should not be used. The variants
+/// [`apply_if`](Apply::apply_if) and [`apply_if_some`](Apply::apply_if_some) only call the closure
+/// under a condition, so optional changes can be chained instead of nested in `if` statements, and
+/// [`apply_all`](Apply::apply_all) calls it for each item of an iterator, e.g.:
+///
+/// ```
+/// use using::Apply;
+///
+/// let extra = Some(3);
+/// let vec = Vec::new()
+///     .apply(|vec| {
+///         vec.push(1);
+///         vec.push(2);
+///     })
+///     .apply_if(false, |vec| vec.clear())
+///     .apply_if_some(extra, Vec::push)
+///     .apply_all(4..=5, Vec::push);
+/// assert_eq!(vec, [ 1, 2, 3, 4, 5 ]);
+/// ```
+pub trait Apply {
+    /// Calls `f` with a mutable reference to `self` and returns `self`.
+    fn apply(mut self, f: impl FnOnce(&mut Self)) -> Self where Self: Sized {
+        f(&mut self);
+        self
+    }
+
+    /// Calls `f` with a mutable reference to `self` if `cond` is `true`, and returns `self`.
+    fn apply_if(self, cond: bool, f: impl FnOnce(&mut Self)) -> Self where Self: Sized {
+        if cond { self.apply(f) } else { self }
+    }
+
+    /// Calls `f` with a mutable reference to `self` and the value of `opt` if it is `Some`, and
+    /// returns `self`.
+    fn apply_if_some<T>(mut self, opt: Option<T>, f: impl FnOnce(&mut Self, T)) -> Self where Self: Sized {
+        if let Some(value) = opt {
+            f(&mut self, value);
+        }
+        self
+    }
+
+    /// Calls `f` with a mutable reference to `self` for each item of `iter`, and returns `self`.
+    fn apply_all<I: IntoIterator>(mut self, iter: I, mut f: impl FnMut(&mut Self, I::Item)) -> Self where Self: Sized {
+        for item in iter {
+            f(&mut self, item);
+        }
+        self
+    }
+
+    /// Calls `f` with `self` and returns `self`, for cascades on a mutable reference.
+    fn apply_ref(&mut self, f: impl FnOnce(&mut Self)) -> &mut Self {
+        f(self);
+        self
+    }
+}
+
+impl<T: ?Sized> Apply for T { }
+
+/// An extension trait for fallible cascades with closures instead of macros.
+///
+/// This trait is implemented for all types and is the fallible variant of [`Apply`]: The closure
+/// returns a `Result`, so errors can be propagated with `?` inside the closure like in a
+/// [`using`](crate::using) block, e.g.:
+///
+/// ```
+/// use using::TryApply;
+///
+/// fn parse_all(input: &str) -> Result<Vec<i32>, std::num::ParseIntError> {
+///     Vec::new().try_apply(|vec| {
+///         for s in input.split(',') {
+///             vec.push(s.trim().parse()?);
+///         }
+///         Ok(())
+///     })
+/// }
+///
+/// assert_eq!(parse_all("1, 2"), Ok(vec![ 1, 2 ]));
+/// assert!(parse_all("1, a").is_err());
+/// ```
+pub trait TryApply {
+    /// Calls `f` with a mutable reference to `self`, and returns `self` if `f` succeeds or the
+    /// error of `f` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f`.
+    fn try_apply<E>(mut self, f: impl FnOnce(&mut Self) -> Result<(), E>) -> Result<Self, E> where Self: Sized {
+        f(&mut self)?;
+        Ok(self)
+    }
+
+    /// Calls `f` with `self`, and returns `self` if `f` succeeds or the error of `f` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f`.
+    fn try_apply_ref<E>(&mut self, f: impl FnOnce(&mut Self) -> Result<(), E>) -> Result<&mut Self, E> {
+        f(self)?;
+        Ok(self)
+    }
+}
+
+impl<T: ?Sized> TryApply for T { }
+
+/// An extension trait for creating a value with `Default::default` and changing it with a closure.
+///
+/// This trait is implemented for all types implementing `Default` and is the closure-based
+/// equivalent of [`using_default`](crate::using_default), e.g.:
+///
+/// ```
+/// use using::WithDefault;
+///
+/// #[derive(Default)]
+/// struct HttpConfig {
+///     host: &'static str,
+///     port: u16,
+/// }
+///
+/// let config = HttpConfig::with(|config| {
+///     config.host = "localhost";
+///     config.port = 80;
+/// });
+/// assert_eq!((config.host, config.port), ("localhost", 80));
+/// ```
+pub trait WithDefault: Default {
+    /// Creates a value with `Default::default`, calls `f` with a mutable reference to it and
+    /// returns it.
+    fn with(f: impl FnOnce(&mut Self)) -> Self {
+        Self::default().apply(f)
+    }
+}
+
+impl<T: Default> WithDefault for T { }
+
+/// An extension trait for cascading on the value inside an `Option` with closures.
+///
+/// This trait is implemented for `Option<T>` and is the closure-based counterpart of
+/// [`using_some`](crate::using_some), which is useful for optional sub-builders:
+/// [`get_or_insert_using`](ApplyOption::get_or_insert_using) inserts `Default::default()` if the
+/// option is `None` and calls the closure with the contained value, and
+/// [`map_using`](ApplyOption::map_using) only calls the closure if the option is `Some`, e.g.:
+///
+/// ```
+/// use using::ApplyOption;
+///
+/// #[derive(Default)]
+/// struct Config {
+///     tls: Option<Vec<&'static str>>,
+///     proxy: Option<Vec<&'static str>>,
+/// }
+///
+/// let mut config = Config::default();
+/// config.tls.get_or_insert_using(|tls| tls.push("cert.pem"));
+/// config.tls.get_or_insert_using(|tls| tls.push("key.pem"));
+/// let proxy = config.proxy.take().map_using(|proxy| proxy.push("unused"));
+/// assert_eq!(config.tls, Some(vec![ "cert.pem", "key.pem" ]));
+/// assert_eq!(proxy, None);
+/// ```
+pub trait ApplyOption<T> {
+    /// Inserts `Default::default()` if `self` is `None`, calls `f` with a mutable reference to the
+    /// contained value and returns the reference.
+    fn get_or_insert_using(&mut self, f: impl FnOnce(&mut T)) -> &mut T where T: Default;
+
+    /// Calls `f` with a mutable reference to the contained value if `self` is `Some`, and returns
+    /// `self`.
+    fn map_using(self, f: impl FnOnce(&mut T)) -> Self;
+}
+
+impl<T> ApplyOption<T> for Option<T> {
+    fn get_or_insert_using(&mut self, f: impl FnOnce(&mut T)) -> &mut T where T: Default {
+        self.get_or_insert_with(T::default).apply_ref(f)
+    }
+
+    fn map_using(self, f: impl FnOnce(&mut T)) -> Self {
+        self.map(|value| value.apply(f))
+    }
+}
+
+/// An extension trait for cascading on the value inside a `Result` with closures.
+///
+/// This trait is implemented for `Result<T, E>` and is the closure-based counterpart of
+/// [`using_ok`](crate::using_ok), e.g. for builders received at API boundaries:
+/// [`map_using`](ApplyResult::map_using) calls the closure with the `Ok` value, and
+/// [`and_then_using`](ApplyResult::and_then_using) additionally allows the closure to fail. An
+/// `Err` is returned unchanged without calling the closure, e.g.:
+///
+/// ```
+/// use using::ApplyResult;
+///
+/// fn push(vec: &mut Vec<i32>, x: i32) -> Result<(), &'static str> {
+///     if x < 0 { Err("negative") } else { vec.push(x); Ok(()) }
+/// }
+///
+/// let vec = Ok(vec![ 1 ])
+///     .map_using(|vec| vec.push(2))
+///     .and_then_using(|vec| push(vec, 3));
+/// assert_eq!(vec, Ok(vec![ 1, 2, 3 ]));
+///
+/// let vec = Ok(vec![ 1 ]).and_then_using(|vec| push(vec, -1));
+/// assert_eq!(vec, Err("negative"));
+/// ```
+pub trait ApplyResult<T, E> {
+    /// Calls `f` with a mutable reference to the contained value if `self` is `Ok`, and returns
+    /// `self`.
+    fn map_using(self, f: impl FnOnce(&mut T)) -> Self;
+
+    /// Calls `f` with a mutable reference to the contained value if `self` is `Ok`, and returns
+    /// `self` if `f` succeeds or the error of `f` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `self` or `f`.
+    fn and_then_using(self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Self;
+}
+
+impl<T, E> ApplyResult<T, E> for Result<T, E> {
+    fn map_using(self, f: impl FnOnce(&mut T)) -> Self {
+        self.map(|value| value.apply(f))
+    }
+
+    fn and_then_using(self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Self {
+        self.and_then(|value| value.try_apply(f))
+    }
+}
@@ -0,0 +1,38 @@
+/// A trait for builders that produce an object when they are finished.
+///
+/// This trait is used by [`using_build`](crate::using_build) to automatically call `build` at the
+/// end of the block. Builders whose `build` method takes `&mut self` can implement this trait by
+/// simply forwarding to that method. The builders generated by `#[derive(UsingBuilder)]` implement
+/// this trait automatically.
+///
+/// The trait can also be used to write helpers that are generic over builders, e.g.:
+///
+/// ```
+/// use using::UsingBuild;
+///
+/// fn make<B: UsingBuild + Default>(configure: impl FnOnce(&mut B)) -> B::Output {
+///     let mut builder = B::default();
+///     configure(&mut builder);
+///     builder.build()
+/// }
+///
+/// #[derive(Default)]
+/// struct SumBuilder(Vec<i32>);
+///
+/// impl UsingBuild for SumBuilder {
+///     type Output = i32;
+///
+///     fn build(self) -> i32 {
+///         self.0.iter().sum()
+///     }
+/// }
+///
+/// assert_eq!(make(|builder: &mut SumBuilder| builder.0.extend([ 1, 2, 3 ])), 6);
+/// ```
+pub trait UsingBuild {
+    /// The type of the object produced by the builder.
+    type Output;
+
+    /// Consumes the builder and produces the object.
+    fn build(self) -> Self::Output;
+}
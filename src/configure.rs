@@ -0,0 +1,98 @@
+/// A trait for configuration callbacks that are applied once to a target of type `T`.
+///
+/// This trait is the convention for functions accepting configuration callbacks, e.g.
+/// `fn request(url: &str, configure: impl Configure<RequestBuilder>)`, which call
+/// [`configure`](Configure::configure) with the target. It is implemented for all closures and
+/// functions implementing `FnOnce(&mut T)`, so users can pass plain closures or the closures
+/// created by [`using_lazy`](crate::using_lazy). See [`DynConfigure`] for configuration steps
+/// stored as trait objects.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{using, using_lazy, Configure};
+///
+/// #[derive(Default)]
+/// pub struct RequestBuilder {
+///     url: String,
+///     headers: Vec<(&'static str, String)>,
+/// }
+///
+/// pub fn request(url: &str, configure: impl Configure<RequestBuilder>) -> RequestBuilder {
+///     using!(builder @ RequestBuilder::default() => {
+///         .url = url.to_string();
+///         configure.configure(&mut builder);
+///     })
+/// }
+///
+/// let token = "secret".to_string();
+/// let a = request("/a", |builder: &mut RequestBuilder| builder.headers.push(("auth", token)));
+/// let b = request("/b", using_lazy!(RequestBuilder => {
+///     .headers.push(("accept", "text/plain".to_string()));
+/// }));
+/// assert_eq!(a.headers, [ ("auth", "secret".to_string()) ]);
+/// assert_eq!(b.url, "/b");
+/// ```
+pub trait Configure<T: ?Sized> {
+    /// Applies the configuration to `target`.
+    fn configure(self, target: &mut T);
+}
+
+impl<T: ?Sized, F: FnOnce(&mut T)> Configure<T> for F {
+    fn configure(self, target: &mut T) {
+        self(target)
+    }
+}
+
+/// A dyn-compatible trait for configuration steps that can be applied to a target of type `T`.
+///
+/// The trait has a single method taking `&self` and no generic methods, so configuration steps can
+/// be stored as trait objects, e.g. `Box<dyn DynConfigure<Builder>>`, and applied multiple times.
+/// It is implemented for all closures and functions implementing `Fn(&mut T)`. Add `Send` or
+/// `Sync` to the trait object if the steps are shared between threads, e.g.
+/// `Box<dyn DynConfigure<Builder> + Send + Sync>`.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{using, DynConfigure};
+///
+/// #[derive(Default)]
+/// struct ServerBuilder {
+///     port: u16,
+///     routes: Vec<&'static str>,
+/// }
+///
+/// struct AddRoute(&'static str);
+///
+/// impl DynConfigure<ServerBuilder> for AddRoute {
+///     fn configure(&self, builder: &mut ServerBuilder) {
+///         builder.routes.push(self.0);
+///     }
+/// }
+///
+/// // e.g. registered by plugins
+/// let steps: Vec<Box<dyn DynConfigure<ServerBuilder>>> = vec![
+///     Box::new(AddRoute("/")),
+///     Box::new(|builder: &mut ServerBuilder| builder.port = 8080),
+/// ];
+///
+/// let builder = using!(builder @ ServerBuilder::default() => {
+///     .port = 80;
+///     for step in &steps {
+///         step.configure(&mut builder);
+///     }
+/// });
+/// assert_eq!(builder.port, 8080);
+/// assert_eq!(builder.routes, [ "/" ]);
+/// ```
+pub trait DynConfigure<T: ?Sized> {
+    /// Applies the configuration step to `target`.
+    fn configure(&self, target: &mut T);
+}
+
+impl<T: ?Sized, F: Fn(&mut T) + ?Sized> DynConfigure<T> for F {
+    fn configure(&self, target: &mut T) {
+        self(target)
+    }
+}
@@ -220,9 +220,202 @@
 //! simple enough, this could even make defining a builder obsolete. Also, the `build` method can
 //! now take both `self` or `&mut self` without breaking method chaining, which is usually a
 //! drawback of defining builders taking `&mut self`.
+//!
+//! # Consuming builders
+//!
+//! Setters taking `&mut self` are used like any other method, and fallible setters and `build`
+//! methods returning a `Result` can be used with `?`. Setters taking `self` (e.g. of type-state
+//! builders, where a setter can return a builder of a different type) can be used by prefixing the
+//! statement with `move`, which rebinds the target to the result for the rest of the block. This
+//! is only supported at the top level of the block, since a `move` statement in the body of an if
+//! expression, a match expression or a loop would only rebind the target within that body (which
+//! is reported as an error). This covers the builders generated by other crates without any glue
+//! code: the setters of `derive_builder` take `&mut self` and its `build` method returns a
+//! `Result`, while the type-state builders of `typed-builder` and `bon` have consuming setters.
+//! For example, with a hand-written type-state builder:
+//!
+//! ```
+//! # use using::using;
+//! struct Client {
+//!     host: String,
+//!     retries: u32,
+//! }
+//!
+//! struct ClientBuilder<H> {
+//!     host: H,
+//!     retries: u32,
+//! }
+//!
+//! impl ClientBuilder<()> {
+//!     fn new() -> Self {
+//!         ClientBuilder { host: (), retries: 5 }
+//!     }
+//!
+//!     fn host(self, host: &str) -> ClientBuilder<String> {
+//!         ClientBuilder { host: host.to_string(), retries: self.retries }
+//!     }
+//! }
+//!
+//! impl<H> ClientBuilder<H> {
+//!     fn retries(self, retries: u32) -> Self {
+//!         ClientBuilder { retries, ..self }
+//!     }
+//! }
+//!
+//! impl ClientBuilder<String> {
+//!     fn build(self) -> Client {
+//!         Client { host: self.host, retries: self.retries }
+//!     }
+//! }
+//!
+//! let client = using!(ClientBuilder::new() => {
+//!     move .retries(2);
+//!     move .host("localhost");
+//!     .build()
+//! });
+//! assert_eq!(client.host, "localhost");
+//! assert_eq!(client.retries, 2);
+//! ```
+//!
+//! # Features
+//!
+//! The crate is `no_std` and has no dependencies by default. The following features can be
+//! enabled:
+//!
+//! * `alloc`: Implements [`TargetMut`] for `Box<T>`, `Rc<T>` and `Arc<T>` and enables
+//!   `using_string`.
+//! * `std`: Enables `alloc`, the extension traits for types of the standard library in
+//!   `std_ext`, [`Tap::tap_dbg`], `using_catch`, `using_tls` and `using_global`.
+//! * `futures`: Enables `for await` loops over streams implementing `Stream` of the
+//!   `futures-core` crate in [`using`] blocks, e.g. in [`async_using`]. [`async_using`] itself is
+//!   always available and needs no dependencies.
+//! * `derive`: Enables the `Setters` and `UsingBuilder` derive macros. This compiles the
+//!   `using-macros` crate, but not the procedural macros of the `macros` feature.
+//! * `macros`: Enables `derive` and the remaining procedural macros: `block` and the
+//!   `#[using(...)]` attribute in `attr`, which have no positional restrictions for target
+//!   expressions, and `using_expand`, which returns the code generated by `block` as a string. Also improves the errors of [`using`] for invalid target expressions, which then
+//!   point to the target expression and suggest a `let` binding, and for bindings that shadow a
+//!   named target, which then point to the binding.
+//! * `log`: Logs every target expression statement evaluated in a [`using`] block with the `log`
+//!   crate at trace level with the target `"using"`, and enables `Tap::tap_log`.
+//! * `serde`: Enables `#[builder(deserialize)]` of the `UsingBuilder` derive macro, which
+//!   implements `Deserialize` of the `serde` crate for the builder.
+//! * `tracing`: Emits a trace event for every target expression statement evaluated in a
+//!   [`using`] block with the `tracing` crate with the target `"using"`.
 
 #![cfg_attr(not(test), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+// allows the derive macros to refer to this crate as `::using` inside this crate
+#[cfg(feature = "derive")]
+extern crate self as using;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::string::String;
+    #[cfg(feature = "log")]
+    pub use log;
+    #[cfg(feature = "macros")]
+    pub use using_macros::diagnostic;
+    #[cfg(feature = "serde")]
+    pub use serde;
+    #[cfg(feature = "std")]
+    pub use std::panic::{catch_unwind, AssertUnwindSafe};
+    #[cfg(feature = "std")]
+    pub use std::sync::{Mutex, RwLock};
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+
+    // detects trailing target expressions of type `()` with autoref specialization: the method
+    // of `TrailingUnit` takes precedence, but is only implemented for `()`; this constrains the
+    // type, so it is only used for methods that are known to return a concrete type
+    pub struct Trailing<'a, T>(pub &'a T);
+
+    pub trait TrailingUnit {
+        #[deprecated(note = "the trailing target expression returns `()` instead of the target, add a `;` to return the target")]
+        fn check(&self) {}
+    }
+
+    impl TrailingUnit for Trailing<'_, ()> {}
+
+    pub trait TrailingValue {
+        fn check(&self) {}
+    }
+
+    impl<T> TrailingValue for &Trailing<'_, T> {}
+
+    // holds a value taken out of an `Option` and puts it back when it is dropped, so it is also put
+    // back if the block of `using_take` exits early (e.g. with `?`) or panics
+    pub struct PutBack<'a, T> {
+        place: &'a mut Option<T>,
+        value: Option<T>,
+    }
+
+    impl<'a, T> PutBack<'a, T> {
+        pub fn new(place: &'a mut Option<T>, value: T) -> Self {
+            PutBack { place, value: Some(value) }
+        }
+
+        pub fn get(&mut self) -> &mut T {
+            match &mut self.value {
+                Some(value) => value,
+                None => unreachable!(),
+            }
+        }
+    }
+
+    impl<T> Drop for PutBack<'_, T> {
+        fn drop(&mut self) {
+            *self.place = self.value.take();
+        }
+    }
+
+    // returns the next item of a pinned stream, like `StreamExt::next` of `futures`
+    #[cfg(feature = "futures")]
+    pub fn next<S: futures_core::Stream + ?Sized>(
+        mut stream: core::pin::Pin<&mut S>,
+    ) -> impl core::future::Future<Output = Option<S::Item>> + '_ {
+        core::future::poll_fn(move |cx| stream.as_mut().poll_next(cx))
+    }
+}
+
+mod apply;
+mod build;
+mod configure;
+mod ptr;
+mod tap;
+mod validate;
+
+#[cfg(feature = "std")]
+pub mod std_ext;
+pub mod typestate;
+
+pub use apply::{Apply, ApplyOption, ApplyResult, TryApply, WithDefault};
+pub use build::UsingBuild;
+pub use configure::{Configure, DynConfigure};
+pub use ptr::TargetMut;
+pub use tap::Tap;
+pub use validate::Validate;
+
+#[cfg(feature = "macros")]
+pub use using_macros::{block, using_expand};
+#[cfg(feature = "derive")]
+pub use using_macros::{Setters, UsingBuilder};
+
+/// Attribute macros.
+///
+/// This module is only available with the `macros` feature.
+#[cfg(feature = "macros")]
+pub mod attr {
+    pub use using_macros::{adapter, cascade_api, using};
+}
+
 /// A macro that provides method cascading for an object.
 ///
 /// # Usage
@@ -231,20 +424,129 @@
 /// using!(expression => { ... })
 ///
 /// using!(identifier @ expression => { ... })
+///
+/// using!(ref mut place => { ... })
+///
+/// using!(ref mut identifier @ place => { ... })
+/// ```
+///
+/// The macro can also be invoked with braces, e.g. `using! { expression => { ... } }`, which
+/// expands exactly like the invocation with parentheses. In statement position, the braces make
+/// the trailing semicolon optional if the macro evaluates to `()`, e.g. with `ref mut` or a
+/// trailing expression of type `()`:
+///
+/// ```
+/// # use using::using;
+/// let mut values = vec![ 1 ];
+/// using! { ref mut values => {
+///     .push(2);
+///     .push(3);
+/// } }
+/// assert_eq!(values, [ 1, 2, 3 ]);
 /// ```
 ///
 /// Binds `expression` to a mutable variable (called "target") that can be manipulated inside the
 /// block with expressions starting with a dot (called "target expressions"). The target variable
-/// can be explicitly named with an @-binding. If the block does not contain a trailing expression,
-/// the target is returned instead.
+/// can be explicitly named with an @-binding, which must not be shadowed by a binding of a pattern
+/// in the block (of a `let` statement, a `for` loop, an `if let` or `while let` condition or a
+/// `match` arm, including destructuring patterns). Closure parameters are not checked, since target
+/// expressions cannot be used in closures. If the block does not contain a trailing expression, the
+/// target is returned instead.
+/// A trailing call of a method that returns `()` (e.g. `.push(1)` instead of `.push(1);`) is
+/// reported with a deprecation warning, but only for the following method names of the standard
+/// library: `append`, `clear`, `dedup`, `extend`, `extend_from_slice`, `fill`, `insert`, `push`,
+/// `push_back`, `push_front`, `push_str`, `reserve`, `resize`, `retain`, `reverse`, `sort`,
+/// `sort_by`, `sort_by_key`, `sort_unstable`, `swap` and `truncate`. The warning is only emitted if
+/// the call actually returns `()` (e.g. not for `BTreeSet::insert`). Other trailing expressions of
+/// type `()`, including calls of setters of your own types (e.g. `.set_port(80)`), are not
+/// checked, since the check would constrain inferred types (e.g. of `.iter().sum()`) to `()`.
+///
+/// With `ref mut`, the target is a mutable borrow of `place` (e.g. a field, an indexed element or
+/// a dereferenced reference) instead of a moved value, so the place can be modified without moving
+/// out of it. In this case, nothing is returned if the block does not contain a trailing
+/// expression. For targets that already are mutable references, see [`using_in`].
+///
+/// Target expressions that consume the target and return a new one (e.g. builder methods taking
+/// `self` and returning `Self`) can be used with a statement prefixed with `move`, e.g. `move
+/// .x(1);`, which rebinds the target to the result of the target expression. This also works if
+/// the type of the result differs from the type of the target, e.g. for type-state builders.
+/// Since the rebinding only affects the rest of the current block, `move` statements can only be
+/// used at the top level of the block of a macro that returns its target, and are rejected in the
+/// bodies of if expressions, match expressions, and loops.
+///
+/// The statement `drop;` at the top level of the block drops the target, e.g. to release a
+/// resource held by it before the rest of the block is executed. Afterwards, the target cannot be
+/// used anymore and nothing is returned if the block does not contain a trailing expression.
+///
+/// The statement `checkpoint;` stores a clone of the target (which must implement `Clone`), and
+/// `rollback;` restores the target to the last checkpoint before it, e.g. to undo a speculative
+/// configuration that turned out to be invalid. With `checkpoint auto;`, the target is also rolled
+/// back before a question mark operator in a target expression returns an error, so a target
+/// borrowed with `ref mut` or [`using_in`] is not left half-modified. Checkpoints follow the
+/// scoping of the block, i.e. a checkpoint in the body of an if expression is only restored within
+/// that body, and `rollback;` without a checkpoint in the same or an enclosing block is reported
+/// with a dedicated error. The statements can also be used in the macros based on [`using`] (e.g.
+/// [`using_dbg`], [`using_record`] or [`using_errors`]), where `checkpoint auto;` only affects
+/// question mark operators that return an error.
+///
+/// Target expressions with a value that must be used (e.g. method calls returning a `Result` or
+/// `#[must_use]` methods of builders returning `&mut Self`) are reported with a warning if they are
+/// used as statements. To discard the value intentionally, prefix the statement with `_`, e.g. `_
+/// .x(1);`, which is equivalent to `let _ = .x(1);`.
+///
+/// Free functions taking the target as their first argument can be called with `.>`, e.g.
+/// `.>apply_defaults();` is equivalent to `apply_defaults(&mut target);` and `.>set_port(443)?;`
+/// to `set_port(&mut target, 443)?;`, so shared helpers can be used without naming the target. The
+/// function can be a path (e.g. `.>config::apply_defaults();`), and the call can be continued like
+/// a method call, e.g. `.>add_layer(x).enable();`.
+///
+/// Statements on a field of type `Option` can use optional chaining, e.g.
+/// `.logger?.set_level(Level::Debug);`, which is equivalent to
+/// `if let Some(value) = target.logger.as_mut() { value.set_level(Level::Debug); }`, so optional
+/// sub-components can be configured without matching on them. This only applies to a field
+/// followed by `?.` in a statement ending with `;`, while `?` after method calls is still the
+/// question mark operator.
+///
+/// Target expression are a sequence of field accessess (e.g. `.x`), method calls (e.g.
+/// `.push(10)`) and question mark operators (e.g. `.try_reserve(10)?`) and can only be used in
+/// blocks, let statements, bodies of if expressions, match expressions, and loops. They cannot be
+/// used in the conditional expressions (except as the scrutinee of a `while let` loop, e.g.
+/// `while let Some(item) = .pop() { ... }`), which is reported with a dedicated error, and also not
+/// in compound expressions, e.g. `.last().unwrap() + 1` is not valid. For details see below.
+///
+/// Method calls keep the location of the statement they are written in, so a panic in a
+/// `#[track_caller]` method (e.g. a `build()` method unwrapping a missing field) reports the line
+/// of the statement in the block instead of the line of the macro invocation:
+///
+/// ```
+/// # use using::using;
+/// # use std::panic::Location;
+/// struct Lines(Vec<u32>);
+///
+/// impl Lines {
+///     #[track_caller]
+///     fn mark(&mut self) -> &mut Self {
+///         self.0.push(Location::caller().line());
+///         self
+///     }
+/// }
 ///
-/// Target expression are a sequence of field accessess (e.g. `.x`) and method calls (e.g.
-/// `.push(10)`) and can only be used in blocks, let statements, bodies of if expressions, match
-/// expressions, and loops. They cannot be used in the conditional expressions and also not in
-/// compound expressions, e.g. `.last().unwrap() + 1` is not valid. For details see below.
+/// let first = line!() + 2;
+/// let lines = using!(Lines(Vec::new()) => {
+///     .mark();
+///     .mark().mark();
+///     let _ = .mark();
+///     if true {
+///         .mark();
+///     }
+/// });
+/// assert_eq!(lines.0, [ first, first + 1, first + 1, first + 2, first + 4 ]);
+/// ```
 ///
 /// Besides the target expressions, every statement and expression can be used inside the block,
-/// which also allows nesting [`using`] macros.
+/// which also allows nesting [`using`] macros. The block can also be forwarded from other
+/// declarative macros, including fragments they have already captured (e.g. `$e:expr` as a
+/// statement or argument, or `$s:stmt` as a statement).
 ///
 /// # Examples:
 ///
@@ -283,19 +585,169 @@
 /// assert_eq!(&vec[..], [ 0, 2, 4, 6, 8, 20 ]);
 /// ```
 ///
+/// Target expressions in conditions are rejected with an error suggesting a `let` binding:
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(Vec::new() => {
+///     if .is_empty() {
+///         .push(1);
+///     }
+/// });
+/// ```
+///
+/// Instead, bind the value first:
+///
+/// ```
+/// # use using::using;
+/// let vec = using!(Vec::new() => {
+///     let empty = .is_empty();
+///     if empty {
+///         .push(1);
+///     }
+/// });
+/// assert_eq!(vec, [ 1 ]);
+/// ```
+///
+/// Only fields can be assigned in target expressions, so assigning to the result of a method call
+/// (e.g. `*.last_mut().unwrap() = 1;`) is reported with a dedicated error. Again, bind the result
+/// first:
+///
+/// ```
+/// # use using::using;
+/// let vec = using!(vec![1, 2, 3] => {
+///     let last = .last_mut().unwrap();
+///     *last = 4;
+/// });
+/// assert_eq!(vec, [ 1, 2, 4 ]);
+/// ```
+///
+/// A `let` statement that shadows a target named with an @-binding is rejected, also if the name is
+/// bound by a destructuring pattern:
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(vec @ Vec::<i32>::new() => {
+///     let (vec, len) = (vec![1], 1);
+///     .push(len);
+/// });
+/// ```
+///
+/// The same applies to the patterns of `for` loops, `if let` and `while let` conditions and
+/// `match` arms, in which the target expressions would modify the new binding instead of the
+/// target:
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(vec @ Vec::<i32>::new() => {
+///     for mut vec in [vec![1]] {
+///         .push(2);
+///     }
+/// });
+/// ```
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(vec @ Vec::<i32>::new() => {
+///     if let Some(mut vec) = Some(vec![1]) {
+///         .push(2);
+///     }
+/// });
+/// ```
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(vec @ Vec::<i32>::new() => {
+///     match Some(vec![1]) {
+///         Some(mut vec) => { .push(2); }
+///         None => { }
+///     }
+/// });
+/// ```
+///
+/// An explicit name is checked even if it is spelled like the default name:
+///
+/// ```compile_fail
+/// # use using::using;
+/// let vec = using!(target @ Vec::<i32>::new() => {
+///     let mut target = vec![1];
+///     .push(2);
+/// });
+/// ```
+///
+/// A `move` statement in a nested block is rejected, since the rebinding would be lost at the end
+/// of the block:
+///
+/// ```compile_fail
+/// # use using::using;
+/// #[derive(Clone, Copy)]
+/// struct Counter(i32);
+///
+/// impl Counter {
+///     fn inc(self) -> Self {
+///         Counter(self.0 + 1)
+///     }
+/// }
+///
+/// let counter = using!(Counter(0) => {
+///     if true {
+///         move .inc();
+///     }
+/// });
+/// ```
+///
+/// Dropping the target before computing the result:
+///
+/// ```
+/// # use using::using;
+/// # use std::cell::RefCell;
+/// let cell = RefCell::new(vec![1]);
+/// let sum: i32 = using!(cell.borrow_mut() => {
+///     .push(2);
+///     drop;
+///     cell.borrow().iter().sum()
+/// });
+/// assert_eq!(sum, 3);
+/// ```
+///
+/// Modifying a place without moving out of it:
+///
+/// ```
+/// # use using::using;
+/// let mut vecs = vec![vec![1, 2], vec![3]];
+/// using!(ref mut vecs[1] => {
+///     .push(4);
+///     .push(5);
+/// });
+/// assert_eq!(vecs, [ vec![1, 2], vec![3, 4, 5] ]);
+///
+/// // Generated code:
+/// //
+/// // {
+/// //     let target = &mut vecs[1];
+/// //     target.push(4);
+/// //     target.push(5);
+/// // };
+/// ```
+///
 /// # Syntax:
 ///
 /// This section explains the syntax in a BNF-like form to clarify the details and where target
 /// expressions can be used. The symbols `IDENTIFIER`, `Statement`, `Expression`,
-/// `BlockExpression`, `Pattern`, `GenericArgs`, `CallParams`, and `Type` are defined in [The Rust
-/// Reference](https://doc.rust-lang.org/stable/reference/). The syntax of the macro is defined by:
+/// `BlockExpression`, `Pattern`, `GenericArgs`, `CallParams`, `PathExpression`, and `Type` are
+/// defined in [The Rust Reference](https://doc.rust-lang.org/stable/reference/). The syntax of the
+/// macro is defined by:
 ///
 /// ```plain
 /// "using" "!" "(" Expression "=>" UsingBlock ")"
 ///
 /// "using" "!" "(" IDENTIFIER "@" Expression "=>" UsingBlock ")"
+///
+/// "using" "!" "(" "ref" "mut" ( IDENTIFIER "@" )? Expression "=>" UsingBlock ")"
 /// ```
 ///
+/// where the parentheses can also be replaced by braces.
+///
 /// A `UsingBlock` is an extension of Rusts `BlockExpression`: it is a block surrounded by curly
 /// braces, containing a sequence of `UsingStatement`s followed by an optional `UsingExpression`.
 ///
@@ -305,6 +757,29 @@
 /// UsingExpression ";"
 ///
 /// "let" IDENTIFIER ( ":" Type )? = UsingExpression ";"
+///
+/// // Assignments to fields of the target, where ASSIGN is either "=" or a compound assignment
+/// // operator, e.g. "+="
+/// ( "." IDENTIFIER | "." IDENTIFIER ( "::" GenericArgs )? "(" CallParams? ")"
+/// | "." ">" PathExpression "(" CallParams? ")" | "?" )*
+/// "." IDENTIFIER ASSIGN Expression ";"
+///
+/// // Rebinding the target to the result of a target expression
+/// "move" UsingExpression ";"
+///
+/// // Dropping the target (only at the top level of the block)
+/// "drop" ";"
+///
+/// // Storing a clone of the target, and restoring it
+/// "checkpoint" "auto"? ";"
+/// "rollback" ";"
+///
+/// // Discarding the value of a target expression
+/// "_" UsingExpression ";"
+///
+/// // Optional chaining, where the statement after the "?" is only executed if the field is
+/// // `Some`, and the field can be preceded by a target expression, e.g. ".a.b?.c();"
+/// "." IDENTIFIER "?" UsingStatement
 /// ```
 ///
 /// A `UsingExpression` is either an `Expression` or one of the following:
@@ -312,8 +787,13 @@
 /// ```plain
 /// UsingBlock
 ///
-/// // This defines the "target expressions"
-/// ( "." IDENTIFIER | "." IDENTIFIER ( "::" GenericArgs )? "(" CallParams? ")" )+
+/// // This defines the "target expressions", where calls of free functions (".>") take a mutable
+/// // borrow of the preceding expression as their first argument, and a leading "&" (only in
+/// // `using_cow!`) borrows the result of reading the value
+/// "&"? ( "." IDENTIFIER | "." IDENTIFIER ( "::" GenericArgs )? "(" CallParams? ")"
+/// | "." ">" PathExpression "(" CallParams? ")" )
+/// ( "." IDENTIFIER | "." IDENTIFIER ( "::" GenericArgs )? "(" CallParams? ")"
+/// | "." ">" PathExpression "(" CallParams? ")" | "?" )*
 ///
 /// "if" Expression UsingBlock ( "else" "if" Expression UsingBlock )* ( "else" UsingBlock )?
 ///
@@ -321,659 +801,7748 @@
 ///
 /// "loop" UsingBlock
 ///
-/// "while" Pattern "in" Expression UsingBlock
+/// "while" Expression UsingBlock
+///
+/// "while" "let" Pattern "=" UsingExpression UsingBlock
 ///
 /// "for" Pattern "in" Expression UsingBlock
+///
+/// // Loops over streams (requires the `futures` feature and an async context)
+/// "for" "await" Pattern "in" Expression UsingBlock
+/// ```
+///
+/// # Recursion limit:
+///
+/// The block is processed one token at a time by a recursive macro, so very large blocks can
+/// exceed the default recursion limit of the compiler, which is reported as `recursion limit
+/// reached while expanding $crate::using_impl!`. Consecutive simple method calls (e.g.
+/// `.push(1);`) are expanded four at a time, so about 500 of them fit into the default limit,
+/// while other statements need two to five levels each (longer conditions and initialisers are
+/// consumed four tokens per level), so blocks with a few dozen statements of any kind are fine.
+/// If the limit is reached, either split the block into several [`using`] invocations or helper
+/// functions, or raise the limit of your crate:
+///
+/// ```
+/// #![recursion_limit = "256"]
+/// # fn main() {}
 /// ```
 #[macro_export]
 macro_rules! using {
+    (ref mut $target:expr => { $( $t:tt )* }) => {
+        {
+            let target = &mut $target;
+            { $crate::using_impl! { @named [default] (&mut *target) () block empty { $($t)* } } }
+        }
+    };
+    (ref mut $([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            let $id = &mut $target;
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+        }
+    };
     ($target:expr => { $( $t:tt )* }) => {
         {
             #[allow(unused_mut)]
             let mut target = $target;
-            $crate::using_impl!(target root empty { $($t)* })
+            { $crate::using_impl! { @named [default] target () root empty { $($t)* } } }
         }
     };
-    ($id:ident @ $target:expr => { $( $t:tt )* }) => {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
         {
             #[allow(unused_mut)]
             let mut $id = $target;
-            $crate::using_impl!($id root empty { $($t)* })
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
         }
     };
+    ($( $t:tt )*) => {
+        $crate::using_impl! { @header "using" () $($t)* }
+    };
 }
 
-#[doc(hidden)]
+/// A macro that provides method cascading for the default value of a type.
+///
+/// # Usage
+///
+/// ```plain
+/// using_default!(type => { ... })
+///
+/// using_default!(identifier @ type => { ... })
+/// ```
+///
+/// A shorthand for `using!(<type>::default() => { ... })`, i.e. the target is created with
+/// `Default::default`. See [`using_new`] for types that are created with a `new` function.
+///
+/// Note that [`using`] cannot accept a type instead of an expression, since a type can also be a
+/// valid expression, e.g. a unit struct or a variable.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_default;
+/// #[derive(Default)]
+/// struct Builder {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let builder = using_default!(Builder => {
+///     .x = 1;
+///     .y = 2;
+/// });
+/// assert_eq!((builder.x, builder.y), (1, 2));
+///
+/// // Generated code:
+/// //
+/// // let builder = {
+/// //     let mut target = <Builder as Default>::default();
+/// //     target.x = 1;
+/// //     target.y = 2;
+/// //     target
+/// // };
+/// ```
 #[macro_export]
-macro_rules! using_impl {
-    ($target:ident $scope:ident maybe_trailing_exp ($id:ident) { }) => {
-        $id
-    };
-
-    ($target:ident $scope:ident maybe_trailing_exp ($id:ident) { ; $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope empty { $($rest)* })
-    };
-
-    ($target:ident $scope:ident maybe_trailing_exp ($id:ident) { $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope empty { $($rest)* })
-    };
-
-
-
-    ($target:ident root empty { }) => {
-        $target
-    };
-
-    ($target:ident block empty { }) => {
-        #[allow(unreachable_code)]
-        ()
-    };
-
-    ($target:ident $scope:ident empty { ; $($rest:tt)* }) => {
+macro_rules! using_default {
+    ($([$default:ident])? $id:ident @ $ty:ty => { $( $t:tt )* }) => {
         {
-            ;
-            $crate::using_impl!($target $scope empty { $($rest)* })
+            #[allow(unused_mut)]
+            let mut $id = <$ty as ::core::default::Default>::default();
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
         }
     };
-
-
-
-    ($target:ident $scope:ident empty { . $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_exp ($target) { . $($rest)* })
-    };
-
-    ($target:ident $scope:ident in_exp ($exp:expr) { . $name:ident $( ::<$($ty:ty),* $(,)?> )? ( $($args:expr),* $(,)? ) $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_exp ($exp.$name$(::<$($ty),*>)*($($args),*)) { $($rest)* })
-    };
-
-    ($target:ident $scope:ident in_exp ($exp:expr) { . $name:ident $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_exp ($exp.$name) { $($rest)* })
-    };
-
-    ($target:ident $scope:ident in_exp ($exp:expr) { }) => {
-        $exp
-    };
-
-    ($target:ident $scope:ident in_exp ($exp:expr) { ; $($rest:tt)* }) => {
-        {
-            $exp;
-            $crate::using_impl!($target $scope empty { $($rest)* })
-        }
+    ($ty:ty => { $( $t:tt )* }) => {
+        $crate::using_default!([default] target @ $ty => { $($t)* })
     };
+}
 
-    ($target:ident $scope:ident in_exp ($exp:expr) { . $name:ident = $value:expr; $($rest:tt)* }) => {
+/// A macro that provides method cascading for a value created with `new`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_new!(type => { ... })
+///
+/// using_new!(identifier @ type => { ... })
+/// ```
+///
+/// A shorthand for `using!(<type>::new() => { ... })`, i.e. the target is created with an
+/// associated function `new` without arguments. See [`using_default`] for types implementing
+/// `Default`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_new;
+/// # use std::collections::HashMap;
+/// let map = using_new!(HashMap<&str, i32> => {
+///     .insert("a", 1);
+///     .insert("b", 2);
+/// });
+/// assert_eq!(map.len(), 2);
+///
+/// // Generated code:
+/// //
+/// // let map = {
+/// //     let mut target = <HashMap<&str, i32>>::new();
+/// //     target.insert("a", 1);
+/// //     target.insert("b", 2);
+/// //     target
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_new {
+    ($([$default:ident])? $id:ident @ $ty:ty => { $( $t:tt )* }) => {
         {
-            $exp.$name = $value;
-            $crate::using_impl!($target $scope empty { $($rest)* })
+            #[allow(unused_mut)]
+            let mut $id = <$ty>::new();
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
         }
     };
-
-
-
-    ($target:ident $scope:ident empty { { $($block:tt)* } }) => {
-        $crate::using_impl!($target block empty { $($block)* })
-    };
-
-    ($target:ident $scope:ident empty { { $($block:tt)* } $($rest:tt)* }) => {
-        {
-            $crate::using_impl!($target block empty { $($block)* });
-            $crate::using_impl!($target $scope empty { $($rest)* })
-        }
+    ($ty:ty => { $( $t:tt )* }) => {
+        $crate::using_new!([default] target @ $ty => { $($t)* })
     };
+}
 
+/// A macro that provides method cascading for an existing object behind a mutable reference.
+///
+/// # Usage
+///
+/// ```plain
+/// using_in!(expression => { ... })
+///
+/// using_in!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to a mutable reference (e.g.
+/// `&mut existing`), which is used as the target. The referenced object is modified in place and
+/// is never moved or rebound, therefore the macro evaluates to `()` if the block does not contain
+/// a trailing expression.
+///
+/// The reference is reborrowed for every target expression, so it also works for unsized targets
+/// like trait objects (e.g. `&mut dyn Write`), even if a method takes the reference by value
+/// (e.g. `Read::take`, which is implemented for `&mut R`).
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_in;
+/// let mut vec = vec![1, 2];
+/// using_in!(&mut vec => {
+///     .push(3);
+///     .push(4);
+/// });
+/// assert_eq!(vec, [ 1, 2, 3, 4 ]);
+///
+/// let last = using_in!(v @ &mut vec => {
+///     .retain(|x| x % 2 == 0);
+///     *v.last().unwrap()
+/// });
+/// assert_eq!(last, 4);
+/// ```
+///
+/// With a trait object as target:
+///
+/// ```
+/// # use std::io::{self, Read, Write};
+/// # use using::using_in;
+/// fn copy_header(input: &mut dyn Read, output: &mut dyn Write) -> io::Result<()> {
+///     let mut header = Vec::new();
+///     using_in!(input => {
+///         .take(2).read_to_end(&mut header)?;
+///         .read_exact(&mut [ 0; 1 ])?;
+///     });
+///     using_in!(output => {
+///         .write_all(&header)?;
+///         .flush()?;
+///     });
+///     Ok(())
+/// }
+///
+/// let mut output = Vec::new();
+/// copy_header(&mut &b"ab|c"[..], &mut output).unwrap();
+/// assert_eq!(output, b"ab");
+/// ```
+#[macro_export]
+macro_rules! using_in {
+    ($target:expr => { $( $t:tt )* }) => {
+        {
+            let target: &mut _ = $target;
+            { $crate::using_impl! { @named [default] (&mut *target) () block empty { $($t)* } } }
+        }
+    };
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            let $id: &mut _ = $target;
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+        }
+    };
+}
+
+/// A macro that provides method cascading for several existing objects with the same block.
+///
+/// # Usage
+///
+/// ```plain
+/// using_all!(place, ... => { ... })
+///
+/// using_all!(identifier @ place, ... => { ... })
+/// ```
+///
+/// The block is evaluated once for each `place` (e.g. a variable or a field), in the given order,
+/// with a mutable reference to the place as the target (like in [`using_in`]). The block is
+/// expanded separately for each target, therefore the targets can have different types. The macro
+/// evaluates to a tuple containing the results of the blocks.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_all;
+/// let mut primary = vec![1];
+/// let mut replica = vec![2];
+/// let mut canary = vec![3];
+/// let (a, b, c) = using_all!(primary, replica, canary => {
+///     .push(10);
+///     .len()
+/// });
+/// assert_eq!((a, b, c), (2, 2, 2));
+/// assert_eq!(primary, [ 1, 10 ]);
+/// assert_eq!(replica, [ 2, 10 ]);
+/// assert_eq!(canary, [ 3, 10 ]);
+///
+/// // Generated code:
+/// //
+/// // let (a, b, c) = (
+/// //     {
+/// //         let target = &mut primary;
+/// //         target.push(10);
+/// //         target.len()
+/// //     },
+/// //     {
+/// //         let target = &mut replica;
+/// //         target.push(10);
+/// //         target.len()
+/// //     },
+/// //     {
+/// //         let target = &mut canary;
+/// //         target.push(10);
+/// //         target.len()
+/// //     },
+/// // );
+/// ```
+#[macro_export]
+macro_rules! using_all {
+    ([$default:ident] $id:ident @ $( $target:expr ),+ $(,)? => $block:tt) => {
+        (
+            $(
+                {
+                    let $id = &mut $target;
+                    { $crate::using_impl! { @named [$default] (&mut *$id) () block empty $block } }
+                },
+            )+
+        )
+    };
+    ($id:ident @ $( $target:expr ),+ $(,)? => $block:tt) => {
+        (
+            $(
+                {
+                    let $id = &mut $target;
+                    { $crate::using_impl! { @named (&mut *$id) () block empty $block } }
+                },
+            )+
+        )
+    };
+    ($( $target:expr ),+ $(,)? => $block:tt) => {
+        $crate::using_all!([default] target @ $($target),+ => $block)
+    };
+}
+
+/// A macro that provides method cascading for a clone of an object.
+///
+/// # Usage
+///
+/// ```plain
+/// using_clone!(expression => { ... })
+///
+/// using_clone!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the target is a clone of `expression`, which can be either a
+/// value or a reference. The original object is left untouched, which is useful for creating
+/// modified copies of existing objects, e.g. configurations.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_clone;
+/// let base = vec![1, 2, 3];
+/// let modified = using_clone!(&base => {
+///     .push(4);
+///     .retain(|x| x % 2 == 0);
+/// });
+/// assert_eq!(base, [ 1, 2, 3 ]);
+/// assert_eq!(modified, [ 2, 4 ]);
+///
+/// // Generated code:
+/// //
+/// // let modified = {
+/// //     let mut target = (&base).clone();
+/// //     target.push(4);
+/// //     target.retain(|x| x % 2 == 0);
+/// //     target
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_clone {
+    ($target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_imports)]
+            use ::core::clone::Clone as _;
+            #[allow(unused_mut)]
+            let mut target = ($target).clone();
+            { $crate::using_impl! { @named [default] target () root empty { $($t)* } } }
+        }
+    };
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_imports)]
+            use ::core::clone::Clone as _;
+            #[allow(unused_mut)]
+            let mut $id = ($target).clone();
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
+        }
+    };
+}
+
+/// A macro that provides method cascading for the value protected by a lock.
+///
+/// # Usage
+///
+/// ```plain
+/// using_lock!(expression => { ... })
+///
+/// using_lock!(identifier @ expression => { ... })
+///
+/// using_lock!(write expression => { ... })
+///
+/// using_lock!(identifier @ write expression => { ... })
+/// ```
+///
+/// Acquires the lock once by calling `expression.lock()` (e.g. for a `Mutex`) or, if prefixed with
+/// `write`, `expression.write()` (e.g. for a `RwLock`), and uses the locked value as the target of
+/// the block. The guard is released at the end of the block. Like [`using_in`], the macro
+/// evaluates to `()` if the block does not contain a trailing expression.
+///
+/// # Panics
+///
+/// Panics if the lock is poisoned.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::sync::{Mutex, RwLock};
+/// # use using::using_lock;
+/// let mutex = Mutex::new(Vec::new());
+/// using_lock!(mutex => {
+///     .push(1);
+///     .push(2);
+/// });
+/// assert_eq!(*mutex.lock().unwrap(), [ 1, 2 ]);
+///
+/// let rwlock = RwLock::new(Vec::new());
+/// let len = using_lock!(write rwlock => {
+///     .push(1);
+///     .len()
+/// });
+/// assert_eq!(len, 1);
+///
+/// // Generated code:
+/// //
+/// // let len = match rwlock.write().unwrap() {
+/// //     mut guard => {
+/// //         let target = &mut *guard;
+/// //         target.push(1);
+/// //         target.len()
+/// //     }
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_lock {
+    (write $lock:expr => { $( $t:tt )* }) => {
+        $crate::using_lock!([default] target @ write $lock => { $($t)* })
+    };
+    // the guard is bound by a `match` instead of a `let`, which is equivalent, but does not trigger
+    // `clippy::significant_drop_tightening` in the crate of the caller, since the guard is held for
+    // the whole block on purpose
+    ($([$default:ident])? $id:ident @ write $lock:expr => { $( $t:tt )* }) => {
+        match ($lock).write().unwrap() {
+            mut guard => {
+                let $id = &mut *guard;
+                { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+            }
+        }
+    };
+    ($([$default:ident])? $id:ident @ $lock:expr => { $( $t:tt )* }) => {
+        match ($lock).lock().unwrap() {
+            mut guard => {
+                let $id = &mut *guard;
+                { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+            }
+        }
+    };
+    ($lock:expr => { $( $t:tt )* }) => {
+        $crate::using_lock!([default] target @ $lock => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the value inside a `RefCell`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_cell!(expression => { ... })
+///
+/// using_cell!(identifier @ expression => { ... })
+///
+/// using_cell!(per_statement expression => { ... })
+/// ```
+///
+/// Mutably borrows the `RefCell` (or anything that dereferences to a `RefCell`, e.g.
+/// `Rc<RefCell<T>>`) once for the whole block and uses the borrowed value as the target. With
+/// `per_statement`, the `RefCell` is instead borrowed separately for each target expression, which
+/// keeps the borrows as short as possible, e.g. when the block calls code that accesses the same
+/// `RefCell`. Like [`using_in`], the macro evaluates to `()` if the block does not contain a
+/// trailing expression.
+///
+/// # Panics
+///
+/// Panics if the `RefCell` is already borrowed when the borrow is taken.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::cell::RefCell;
+/// # use std::rc::Rc;
+/// # use using::using_cell;
+/// let shared = Rc::new(RefCell::new(Vec::new()));
+/// using_cell!(shared => {
+///     .push(1);
+///     .push(2);
+/// });
+///
+/// let other = Rc::clone(&shared);
+/// let len = using_cell!(per_statement shared => {
+///     .push(3);
+///     other.borrow_mut().push(4);
+///     .len()
+/// });
+/// assert_eq!(len, 4);
+///
+/// // Generated code:
+/// //
+/// // let len = {
+/// //     let cell: &RefCell<_> = &shared;
+/// //     RefCell::borrow_mut(cell).push(3);
+/// //     other.borrow_mut().push(4);
+/// //     RefCell::borrow_mut(cell).len()
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_cell {
+    (per_statement $cell:expr => { $( $t:tt )* }) => {
+        {
+            let cell: &::core::cell::RefCell<_> = &$cell;
+            { $crate::using_impl! { (::core::cell::RefCell::borrow_mut(cell)) () block empty { $($t)* } } }
+        }
+    };
+    ($([$default:ident])? $id:ident @ $cell:expr => { $( $t:tt )* }) => {
+        {
+            let mut borrow = ::core::cell::RefCell::borrow_mut(&$cell);
+            let $id = &mut *borrow;
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+        }
+    };
+    ($cell:expr => { $( $t:tt )* }) => {
+        $crate::using_cell!([default] target @ $cell => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the value of a thread local.
+///
+/// This macro is only available with the `std` feature.
+///
+/// # Usage
+///
+/// ```plain
+/// using_tls!(key => { ... })
+///
+/// using_tls!(identifier @ key => { ... })
+///
+/// using_tls!(ref ( identifier @ )? key => { ... })
+/// ```
+///
+/// Accesses the value of the thread local `key` (declared with [`thread_local`]) with
+/// [`LocalKey::with`](std::thread::LocalKey::with) and, like [`using_cell`], mutably borrows the
+/// `RefCell` it contains for the whole block and uses the borrowed value as the target. With
+/// `ref`, the value is not required to be a `RefCell`, and the target is a shared reference to it
+/// instead, e.g. for values with other forms of interior mutability. The macro evaluates to the
+/// result of the block, which is `()` if the block does not contain a trailing expression.
+///
+/// The block is evaluated inside a closure, so `return` and question mark operators refer to the
+/// closure instead of the enclosing function.
+///
+/// # Panics
+///
+/// Panics if the `RefCell` is already borrowed, or if the thread local has been destroyed.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::cell::{Cell, RefCell};
+/// # use std::collections::HashMap;
+/// # use using::using_tls;
+/// thread_local! {
+///     static REGISTRY: RefCell<HashMap<&'static str, u32>> = RefCell::new(HashMap::new());
+///     static COUNTER: Cell<u32> = Cell::new(0);
+/// }
+///
+/// using_tls!(REGISTRY => {
+///     .insert("a", 1);
+///     .insert("b", 2);
+/// });
+/// assert_eq!(REGISTRY.with_borrow(|registry| registry.len()), 2);
+///
+/// let count = using_tls!(ref COUNTER => {
+///     .set(5);
+///     .get()
+/// });
+/// assert_eq!(count, 5);
+///
+/// // Generated code:
+/// //
+/// // REGISTRY.with(|cell| {
+/// //     let mut borrow = RefCell::borrow_mut(cell);
+/// //     let target = &mut *borrow;
+/// //     target.insert("a", 1);
+/// //     target.insert("b", 2);
+/// // });
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! using_tls {
+    (ref $([$default:ident])? $id:ident @ $key:expr => { $( $t:tt )* }) => {
+        ($key).with(|$id| { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } })
+    };
+    (ref $key:expr => { $( $t:tt )* }) => {
+        $crate::using_tls!(ref [default] target @ $key => { $($t)* })
+    };
+    ($([$default:ident])? $id:ident @ $key:expr => { $( $t:tt )* }) => {
+        ($key).with(|cell| {
+            let mut borrow = ::core::cell::RefCell::borrow_mut(cell);
+            let $id = &mut *borrow;
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+        })
+    };
+    ($key:expr => { $( $t:tt )* }) => {
+        $crate::using_tls!([default] target @ $key => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the value of a global protected by a lock.
+///
+/// This macro is only available with the `std` feature.
+///
+/// # Usage
+///
+/// ```plain
+/// using_global!(path => { ... })
+///
+/// using_global!(path = expression => { ... })
+///
+/// using_global!(identifier @ path ( = expression )? => { ... })
+///
+/// using_global!(( identifier @ )? write path ( = expression )? => { ... })
+/// ```
+///
+/// Works like [`using_lock`] for the static `path`, which is either a `Mutex` or dereferences to
+/// one (e.g. a `LazyLock<Mutex<T>>`). With `= expression`, `path` is a `OnceLock<Mutex<T>>`
+/// instead, which is initialized with a `Mutex` containing `expression` if it is not yet
+/// initialized, so the global is initialized or accessed and configured in one expression. With
+/// `write`, `Mutex` is replaced by `RwLock`, and the lock is acquired for writing. Like
+/// [`using_lock`], the macro evaluates to `()` if the block does not contain a trailing
+/// expression.
+///
+/// # Panics
+///
+/// Panics if the lock is poisoned.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::sync::{LazyLock, Mutex, OnceLock, RwLock};
+/// # use using::using_global;
+/// static NAMES: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+/// static PORTS: LazyLock<Mutex<Vec<u16>>> = LazyLock::new(|| Mutex::new(vec![ 80 ]));
+/// static CONFIG: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+///
+/// using_global!(NAMES => {
+///     .push("a");
+///     .push("b");
+/// });
+/// assert_eq!(*NAMES.lock().unwrap(), [ "a", "b" ]);
+///
+/// let len = using_global!(PORTS => {
+///     .push(443);
+///     .len()
+/// });
+/// assert_eq!(len, 2);
+///
+/// using_global!(write CONFIG = vec![ "default".to_string() ] => {
+///     .push("custom".to_string());
+/// });
+/// assert_eq!(*CONFIG.get().unwrap().read().unwrap(), [ "default", "custom" ]);
+///
+/// // Generated code:
+/// //
+/// // match CONFIG.get_or_init(|| RwLock::new(vec!["default".to_string()])).write().unwrap() {
+/// //     mut guard => {
+/// //         let target = &mut *guard;
+/// //         target.push("custom".to_string());
+/// //     }
+/// // };
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! using_global {
+    ($([$default:ident])? $id:ident @ write $key:path = $init:expr => { $( $t:tt )* }) => {
+        $crate::using_lock!($id @ write $key.get_or_init(|| $crate::__private::RwLock::new($init)) => { $($t)* })
+    };
+    ($([$default:ident])? $id:ident @ write $key:path => { $( $t:tt )* }) => {
+        $crate::using_lock!($id @ write $key => { $($t)* })
+    };
+    ($([$default:ident])? $id:ident @ $key:path = $init:expr => { $( $t:tt )* }) => {
+        $crate::using_lock!($id @ $key.get_or_init(|| $crate::__private::Mutex::new($init)) => { $($t)* })
+    };
+    ($([$default:ident])? $id:ident @ $key:path => { $( $t:tt )* }) => {
+        $crate::using_lock!($id @ $key => { $($t)* })
+    };
+    (write $key:path $( = $init:expr )? => { $( $t:tt )* }) => {
+        $crate::using_global!([default] target @ write $key $( = $init )? => { $($t)* })
+    };
+    ($key:path $( = $init:expr )? => { $( $t:tt )* }) => {
+        $crate::using_global!([default] target @ $key $( = $init )? => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the value behind a smart pointer.
+///
+/// # Usage
+///
+/// ```plain
+/// using_ptr!(expression => { ... })
+///
+/// using_ptr!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to a pointer implementing
+/// [`TargetMut`] (e.g. `Box<T>`, `Rc<T>` or `Arc<T>` with the `alloc` feature) and the target is
+/// the value the pointer points to. If the block does not contain a trailing expression, the
+/// pointer is returned.
+///
+/// # Panics
+///
+/// Panics if the pointee cannot be accessed mutably, e.g. if an `Rc<T>` is shared. To handle this
+/// case, use [`TargetMut::try_target_mut`] together with [`using_in`] instead.
+///
+/// # Examples:
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use std::rc::Rc;
+/// # use using::using_ptr;
+/// let rc = using_ptr!(Rc::new(Vec::new()) => {
+///     .push(1);
+///     .push(2);
+/// });
+/// assert_eq!(*rc, [ 1, 2 ]);
+///
+/// // Generated code:
+/// //
+/// // let rc = {
+/// //     let mut ptr = Rc::new(Vec::new());
+/// //     let target = TargetMut::target_mut(&mut ptr);
+/// //     target.push(1);
+/// //     target.push(2);
+/// //     ptr
+/// // };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! using_ptr {
+    ($([$default:ident])? $id:ident @ $ptr:expr => { $( $t:tt )* }) => {
+        {
+            let mut ptr = $ptr;
+            let $id = $crate::TargetMut::target_mut(&mut ptr);
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () (root ptr) empty { $($t)* } } }
+        }
+    };
+    ($ptr:expr => { $( $t:tt )* }) => {
+        $crate::using_ptr!([default] target @ $ptr => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the owned value of a `Cow`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_cow!(expression => { ... })
+///
+/// using_cow!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to a `Cow` and target expressions
+/// are evaluated on the dereferenced `Cow`, so reading the value does not clone it. Target
+/// expressions that need the owned value start with `mut` (e.g. `mut .push(1);` or
+/// `let last = mut .pop();`) and go through `Cow::to_mut`, i.e. a borrowed value is only cloned
+/// once the first of them is evaluated. The @-binding names the `Cow` itself. If the block does not
+/// contain a trailing expression, the `Cow` is returned.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::borrow::Cow;
+/// # use using::using_cow;
+/// fn normalize(path: &str) -> Cow<str> {
+///     using_cow!(path @ Cow::Borrowed(path) => {
+///         let slash = .ends_with('/');
+///         if !slash {
+///             mut .push('/');
+///         }
+///     })
+/// }
+///
+/// assert!(matches!(normalize("/usr/"), Cow::Borrowed("/usr/")));
+/// assert!(matches!(normalize("/usr"), Cow::Owned(path) if path == "/usr/"));
+///
+/// // Generated code:
+/// //
+/// // {
+/// //     let mut path = Cow::Borrowed(path);
+/// //     let slash = (&*path).ends_with('/');
+/// //     if !slash {
+/// //         path.to_mut().push('/');
+/// //     }
+/// //     path
+/// // }
+/// ```
+#[macro_export]
+macro_rules! using_cow {
+    ($([$default:ident])? $id:ident @ $cow:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $cow;
+            { $crate::using_impl! { @named $([$default])? (&*$id) () (root $id) empty { $($t)* } } }
+        }
+    };
+    ($cow:expr => { $( $t:tt )* }) => {
+        $crate::using_cow!([default] cow @ $cow => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for the value inside an `Option`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_some!(expression => { ... })
+///
+/// using_some!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to an `Option` and the block is
+/// only evaluated if it is `Some`, with the contained value as the target. The macro evaluates to
+/// `Some` containing the result of the block, or to `None` if `expression` is `None`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_some;
+/// let mut vecs = vec![vec![1]];
+/// let len = using_some!(vecs.last_mut() => {
+///     .push(2);
+///     .len()
+/// });
+/// assert_eq!(len, Some(2));
+///
+/// vecs.clear();
+/// let len = using_some!(vecs.last_mut() => {
+///     .push(2);
+///     .len()
+/// });
+/// assert_eq!(len, None);
+///
+/// // Generated code:
+/// //
+/// // let len = match vecs.last_mut() {
+/// //     Some(mut target) => Some({
+/// //         target.push(2);
+/// //         target.len()
+/// //     }),
+/// //     None => None,
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_some {
+    ($([$default:ident])? $id:ident @ $option:expr => { $( $t:tt )* }) => {
+        match $option {
+            #[allow(unused_mut)]
+            ::core::option::Option::Some(mut $id) => {
+                ::core::option::Option::Some({ $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } })
+            }
+            ::core::option::Option::None => ::core::option::Option::None,
+        }
+    };
+    ($option:expr => { $( $t:tt )* }) => {
+        $crate::using_some!([default] target @ $option => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for a value taken out of an `Option`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_take!(place => { ... })
+///
+/// using_take!(place => { ... } else { ... })
+///
+/// using_take!(move place => { ... })
+///
+/// using_take!(move place => { ... } else { ... })
+/// ```
+///
+/// All forms also accept `identifier @ place` instead of `place` to name the target.
+///
+/// `place` must be an `Option` that can be borrowed mutably (e.g. a field). Its value is taken out
+/// with `Option::take`, leaving `None` in its place, and the block is evaluated with the taken
+/// value as the target. After the block, the target is put back into `place`, also if the block
+/// exits early (e.g. with `return`, `break` or `?`) or panics, and the macro evaluates to `Some`
+/// containing the result of the block. With `move`, the target is not put back, instead, the block
+/// works like [`using`], i.e. the macro evaluates to `Some` containing the target or the trailing
+/// expression of the block.
+///
+/// If `place` is `None`, the block is not evaluated and the macro evaluates to `None`. If an
+/// `else` block is given, it is evaluated instead and the macro evaluates to the result of the
+/// respective block without wrapping it in `Some`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_take;
+/// struct Connection {
+///     log: Vec<&'static str>,
+/// }
+///
+/// struct Client {
+///     connection: Option<Connection>,
+/// }
+///
+/// let mut client = Client { connection: Some(Connection { log: Vec::new() }) };
+/// let len = using_take!(client.connection => {
+///     .log.push("ping");
+///     .log.len()
+/// } else {
+///     0
+/// });
+/// assert_eq!(len, 1);
+/// assert_eq!(client.connection.as_ref().unwrap().log, [ "ping" ]);
+///
+/// let connection = using_take!(move client.connection => {
+///     .log.push("close");
+/// });
+/// assert!(client.connection.is_none());
+/// assert_eq!(connection.unwrap().log, [ "ping", "close" ]);
+///
+/// // Generated code:
+/// //
+/// // let len = {
+/// //     let place = &mut client.connection;
+/// //     match Option::take(place) {
+/// //         Some(value) => {
+/// //             // puts the value back into `place` when it is dropped
+/// //             let mut guard = PutBack::new(place, value);
+/// //             let target = guard.get();
+/// //             target.log.push("ping");
+/// //             target.log.len()
+/// //         }
+/// //         None => {
+/// //             0
+/// //         }
+/// //     }
+/// // };
+/// //
+/// // let connection = match Option::take(&mut client.connection) {
+/// //     Some(mut target) => Some({
+/// //         target.log.push("close");
+/// //         target
+/// //     }),
+/// //     None => None,
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_take {
+    (move $([$default:ident])? $id:ident @ $place:expr => { $( $t:tt )* } else { $( $e:tt )* }) => {
+        match ::core::option::Option::take(&mut $place) {
+            #[allow(unused_mut)]
+            ::core::option::Option::Some(mut $id) => {
+                { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
+            }
+            ::core::option::Option::None => {
+                $($e)*
+            }
+        }
+    };
+    (move $([$default:ident])? $id:ident @ $place:expr => { $( $t:tt )* }) => {
+        match ::core::option::Option::take(&mut $place) {
+            #[allow(unused_mut)]
+            ::core::option::Option::Some(mut $id) => {
+                ::core::option::Option::Some({ $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } })
+            }
+            ::core::option::Option::None => ::core::option::Option::None,
+        }
+    };
+    (move $place:expr => { $( $t:tt )* } $( else { $( $e:tt )* } )?) => {
+        $crate::using_take!(move [default] target @ $place => { $($t)* } $( else { $($e)* } )?)
+    };
+    ($([$default:ident])? $id:ident @ $place:expr => { $( $t:tt )* } else { $( $e:tt )* }) => {
+        {
+            let place = &mut $place;
+            match ::core::option::Option::take(place) {
+                ::core::option::Option::Some(value) => {
+                    let mut guard = $crate::__private::PutBack::new(place, value);
+                    let $id = guard.get();
+                    { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+                }
+                ::core::option::Option::None => {
+                    $($e)*
+                }
+            }
+        }
+    };
+    ($([$default:ident])? $id:ident @ $place:expr => { $( $t:tt )* }) => {
+        {
+            let place = &mut $place;
+            match ::core::option::Option::take(place) {
+                ::core::option::Option::Some(value) => {
+                    let mut guard = $crate::__private::PutBack::new(place, value);
+                    let $id = guard.get();
+                    ::core::option::Option::Some({ $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } })
+                }
+                ::core::option::Option::None => ::core::option::Option::None,
+            }
+        }
+    };
+    ($place:expr => { $( $t:tt )* } $( else { $( $e:tt )* } )?) => {
+        $crate::using_take!([default] target @ $place => { $($t)* } $( else { $($e)* } )?)
+    };
+}
+
+/// A macro that provides method cascading for the value inside a `Result`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_ok!(expression => { ... })
+///
+/// using_ok!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using_some`], but for `Result`: the block is only evaluated if `expression` is
+/// `Ok`, with the contained value as the target. The macro evaluates to `Ok` containing the result
+/// of the block, or to the unchanged `Err` if `expression` is `Err`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_ok;
+/// let vec = using_ok!("1,2".split(',').map(str::parse::<i32>).collect::<Result<Vec<_>, _>>() => {
+///     .push(3);
+/// });
+/// assert_eq!(vec, Ok(vec![1, 2, 3]));
+///
+/// // Generated code:
+/// //
+/// // let vec = match "1,2".split(',').map(str::parse::<i32>).collect::<Result<Vec<_>, _>>() {
+/// //     Ok(mut target) => Ok({
+/// //         target.push(3);
+/// //         target
+/// //     }),
+/// //     Err(err) => Err(err),
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_ok {
+    ($([$default:ident])? $id:ident @ $result:expr => { $( $t:tt )* }) => {
+        match $result {
+            #[allow(unused_mut)]
+            ::core::result::Result::Ok(mut $id) => {
+                ::core::result::Result::Ok({ $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } })
+            }
+            ::core::result::Result::Err(err) => ::core::result::Result::Err(err),
+        }
+    };
+    ($result:expr => { $( $t:tt )* }) => {
+        $crate::using_ok!([default] target @ $result => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for each element of an iterator.
+///
+/// # Usage
+///
+/// ```plain
+/// using_each!(expression => { ... })
+///
+/// using_each!(identifier in expression => { ... })
+/// ```
+///
+/// Evaluates the block once for each element of `expression`, which can be anything that
+/// implements `IntoIterator`, with the element as the target (optionally named with `identifier`).
+/// The results of the block are collected into a collection that implements `FromIterator` (e.g.
+/// `Vec` or `Box<[T]>`), which is inferred from the context. Like in [`using`], the result of the
+/// block is the target itself if the block does not contain a trailing expression.
+///
+/// The block is the body of a closure passed to `Iterator::map`, therefore `?` and `return` leave
+/// the block of the current element instead of the surrounding function. If the block evaluates to
+/// a `Result`, the results can be collected into a `Result` (e.g. `Result<Vec<T>, E>`), which
+/// stops at the first error.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_each;
+/// let vecs: Vec<_> = using_each!(vec in vec![vec![1], vec![2, 3]] => {
+///     .push(0);
+/// });
+/// assert_eq!(vecs, [ vec![1, 0], vec![2, 3, 0] ]);
+///
+/// let numbers: Result<Vec<i32>, _> = using_each!(value in [ " 1", "2 ", "x" ] => {
+///     .trim().parse::<i32>()
+/// });
+/// assert!(numbers.is_err());
+///
+/// // Generated code:
+/// //
+/// // let vecs: Vec<_> = vec![vec![1], vec![2, 3]]
+/// //     .into_iter()
+/// //     .map(|mut vec| {
+/// //         vec.push(0);
+/// //         vec
+/// //     })
+/// //     .collect();
+/// ```
+#[macro_export]
+macro_rules! using_each {
+    ($([$default:ident])? $id:ident in $iter:expr => { $( $t:tt )* }) => {
+        ::core::iter::Iterator::collect(::core::iter::Iterator::map(
+            ::core::iter::IntoIterator::into_iter($iter),
+            |#[allow(unused_mut)] mut $id| { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } },
+        ))
+    };
+    ($iter:expr => { $( $t:tt )* }) => {
+        $crate::using_each!([default] target in $iter => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and returns both the object and the
+/// result of the block.
+///
+/// # Usage
+///
+/// ```plain
+/// using_with!(expression => { ... })
+///
+/// using_with!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the macro evaluates to a tuple containing the target and the
+/// trailing expression of the block (or `()` if the block does not contain a trailing
+/// expression).
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_with;
+/// let (vec, len) = using_with!(Vec::new() => {
+///     .push(1);
+///     .push(2);
+///     .len()
+/// });
+/// assert_eq!(vec, [ 1, 2 ]);
+/// assert_eq!(len, 2);
+///
+/// // Generated code:
+/// //
+/// // let (vec, len) = {
+/// //     let mut target = Vec::new();
+/// //     let result = {
+/// //         target.push(1);
+/// //         target.push(2);
+/// //         target.len()
+/// //     };
+/// //     (target, result)
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_with {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            let result = { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } };
+            ($id, result)
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_with!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object purely for its side effects.
+///
+/// # Usage
+///
+/// ```plain
+/// using_do!(expression => { ... })
+///
+/// using_do!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the macro always evaluates to `()`: the target is dropped at
+/// the end of the block and the trailing expression, if any, is discarded. This avoids unused
+/// value warnings when the macro is used in statement position.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_do;
+/// let mut out = String::new();
+/// using_do!(Vec::new() => {
+///     .push("Hello");
+///     .push("World!");
+///     let joined = .join(", ");
+///     out.push_str(&joined);
+/// });
+/// assert_eq!(out, "Hello, World!");
+///
+/// // Generated code:
+/// //
+/// // {
+/// //     let mut target = Vec::new();
+/// //     let _ = {
+/// //         target.push("Hello");
+/// //         target.push("World!");
+/// //         let joined = target.join(", ");
+/// //         out.push_str(&joined);
+/// //     };
+/// // }
+/// ```
+#[macro_export]
+macro_rules! using_do {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            let _ = { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } };
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_do!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and always returns the object.
+///
+/// # Usage
+///
+/// ```plain
+/// using_keep!(expression => { ... })
+///
+/// using_keep!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the target is returned even if the block contains a trailing
+/// expression, which is only evaluated for its side effects, e.g. assertions or logging.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_keep;
+/// let vec = using_keep!(vec @ Vec::new() => {
+///     .push(1);
+///     .push(2);
+///     assert_eq!(vec.len(), 2)
+/// });
+/// assert_eq!(vec, [ 1, 2 ]);
+///
+/// // Generated code:
+/// //
+/// // let vec = {
+/// //     let mut vec = Vec::new();
+/// //     let _ = {
+/// //         vec.push(1);
+/// //         vec.push(2);
+/// //         assert_eq!(vec.len(), 2)
+/// //     };
+/// //     vec
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_keep {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            let _ = { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } };
+            $id
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_keep!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that passes the result of each statement to the next statement.
+///
+/// # Usage
+///
+/// ```plain
+/// using_pipe!(expression => { ... })
+/// ```
+///
+/// Instead of modifying a target in place, each statement of the block is a stage of a pipeline:
+/// the current value is available as `@` (e.g. `parse(@)`) or as the target of a target
+/// expression (e.g. `.trim()`), and the result of the statement becomes the value of the next
+/// statement. The value of the first statement is `expression`. The macro evaluates to the
+/// trailing expression of the block, or to the result of the last statement if the block does not
+/// contain a trailing expression. Since every statement is a stage, statements that do not use
+/// the value (e.g. `println!("...");`) replace the value with their result. A `@` following an
+/// identifier is left as is (e.g. `n @ 1..=5` in a pattern), as are the blocks of nested
+/// cascade macros (e.g. `using!(v @ Vec::with_capacity(@) => { ... })`), so only the target
+/// expression of a nested macro refers to the current value.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_pipe;
+/// # use std::num::ParseIntError;
+/// fn double(input: &str) -> Result<i32, ParseIntError> {
+///     using_pipe!(input => {
+///         .trim();
+///         .parse::<i32>()?;
+///         Ok(@ * 2)
+///     })
+/// }
+///
+/// assert_eq!(double(" 21 "), Ok(42));
+/// assert!(double("x").is_err());
+///
+/// // Generated code:
+/// //
+/// // fn double(input: &str) -> Result<i32, ParseIntError> {
+/// //     let target = input;
+/// //     let target = target.trim();
+/// //     let target = target.parse::<i32>()?;
+/// //     Ok(target * 2)
+/// // }
+/// ```
+#[macro_export]
+macro_rules! using_pipe {
+    ($target:expr => { $( $t:tt )* }) => {
+        {
+            let target = $target;
+            $crate::using_pipe_impl!(target [] () { $($t)* })
+        }
+    };
+}
+
+/// A macro that creates a closure providing method cascading for a mutable reference.
+///
+/// # Usage
+///
+/// ```plain
+/// using_lazy!(type => { ... })
+///
+/// using_lazy!(identifier @ type => { ... })
+/// ```
+///
+/// Instead of evaluating the block immediately, this macro evaluates to a `move` closure taking a
+/// `&mut type`, which is used as the target of the block when the closure is called (like in
+/// [`using_in`]). This allows defining reusable configurations and passing them to functions
+/// accepting callbacks, e.g. functions taking an `impl` [`Configure`].
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_lazy;
+/// let offset = 10;
+/// let configure = using_lazy!(Vec<i32> => {
+///     .push(offset + 1);
+///     .push(offset + 2);
+/// });
+///
+/// let mut a = Vec::new();
+/// let mut b = vec![0];
+/// configure(&mut a);
+/// configure(&mut b);
+/// assert_eq!(a, [ 11, 12 ]);
+/// assert_eq!(b, [ 0, 11, 12 ]);
+///
+/// // Generated code:
+/// //
+/// // let configure = move |target: &mut Vec<i32>| {
+/// //     target.push(offset + 1);
+/// //     target.push(offset + 2);
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_lazy {
+    ($([$default:ident])? $id:ident @ $ty:ty => { $( $t:tt )* }) => {
+        move |$id: &mut $ty| {
+            { $crate::using_impl! { @named $([$default])? (&mut *$id) () block empty { $($t)* } } }
+        }
+    };
+    ($ty:ty => { $( $t:tt )* }) => {
+        $crate::using_lazy!([default] target @ $ty => { $($t)* })
+    };
+}
+
+/// A macro that defines functions whose bodies provide method cascading for an object.
+///
+/// # Usage
+///
+/// ```plain
+/// using_fn! {
+///     fn name(expression, parameter: type, ...) -> type { ... }
+///
+///     fn name(identifier @ expression, parameter: type, ...) -> type { ... }
+///
+///     fn name<generics>(expression, pattern: type, ...) -> type where ... { ... }
+///
+///     ...
+/// }
+/// ```
+///
+/// Defines one or more functions, each with the given attributes, visibility, name, generics,
+/// return type and where clause. The first item in the parentheses is not a parameter, but the
+/// target of the body, which works like the block of [`using`]. The remaining items are the
+/// parameters of the function, which can be patterns (e.g. `mut name: type` or
+/// `(a, b): (type, type)`), and which can be used in the target expression (e.g. to pass in an
+/// existing target) and in the body. If the return type is omitted, the function returns `()`, the
+/// target is dropped at the end of the function, and the body must not contain a trailing
+/// expression.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_fn;
+/// using_fn! {
+///     /// Returns a vector containing `1` and `2`.
+///     pub fn one_two(Vec::new()) -> Vec<i32> {
+///         .push(1);
+///         .push(2);
+///     }
+///
+///     fn extended(vec @ base, base: Vec<i32>, value: i32) -> usize {
+///         .push(value);
+///         vec.len()
+///     }
+/// }
+///
+/// assert_eq!(one_two(), [ 1, 2 ]);
+/// assert_eq!(extended(one_two(), 3), 3);
+///
+/// // Generated code:
+/// //
+/// // /// Returns a vector containing `1` and `2`.
+/// // pub fn one_two() -> Vec<i32> {
+/// //     let mut target = Vec::new();
+/// //     target.push(1);
+/// //     target.push(2);
+/// //     target
+/// // }
+/// //
+/// // fn extended(base: Vec<i32>, value: i32) -> usize {
+/// //     let mut vec = base;
+/// //     vec.push(value);
+/// //     vec.len()
+/// // }
+/// ```
+#[macro_export]
+macro_rules! using_fn {
+    () => { };
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($([$default:ident])? $id:ident @ $target:expr $(, $param:ident: $ty:ty)* $(,)?) -> $ret:ty
+        { $( $t:tt )* }
+        $( $rest:tt )*
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($param: $ty),*) -> $ret {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
+        }
+
+        $crate::using_fn! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($([$default:ident])? $id:ident @ $target:expr $(, $param:ident: $ty:ty)* $(,)?)
+        { $( $t:tt )* }
+        $( $rest:tt )*
+    ) => {
+        $(#[$attr])*
+        $vis fn $name($($param: $ty),*) {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } }
+        }
+
+        $crate::using_fn! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident($target:expr $(, $param:ident: $ty:ty)* $(,)?) $(-> $ret:ty)?
+        { $( $t:tt )* }
+        $( $rest:tt )*
+    ) => {
+        $crate::using_fn! {
+            $(#[$attr])*
+            $vis fn $name([default] target @ $target $(, $param: $ty)*) $(-> $ret)? { $($t)* }
+            $($rest)*
+        }
+    };
+
+    // functions with generics, a where clause or parameters with patterns are parsed token by
+    // token, starting with the generics, where `$depth` contains a `<` for each unclosed `<`
+    (
+        $(#[$attr:meta])*
+        $vis:vis fn $name:ident $( $rest:tt )*
+    ) => {
+        $crate::using_fn! { @generics ($(#[$attr])* $vis fn $name) () () { $($rest)* } }
+    };
+
+    (@generics $head:tt () () { < $($rest:tt)* }) => {
+        $crate::using_fn! { @generics $head (<) (<) { $($rest)* } }
+    };
+
+    (@generics $head:tt $gen:tt () { ( $($params:tt)* ) $($rest:tt)* }) => {
+        $crate::using_fn! { @target $head $gen ( $($params)* ) { $($rest)* } }
+    };
+
+    (@generics $head:tt ($($gen:tt)*) ($($depth:tt)*) { < $($rest:tt)* }) => {
+        $crate::using_fn! { @generics $head ($($gen)* <) (< $($depth)*) { $($rest)* } }
+    };
+
+    (@generics $head:tt ($($gen:tt)*) (< $($depth:tt)*) { > $($rest:tt)* }) => {
+        $crate::using_fn! { @generics $head ($($gen)* >) ($($depth)*) { $($rest)* } }
+    };
+
+    (@generics $head:tt ($($gen:tt)*) (< < $($depth:tt)*) { >> $($rest:tt)* }) => {
+        $crate::using_fn! { @generics $head ($($gen)* >>) ($($depth)*) { $($rest)* } }
+    };
+
+    (@generics $head:tt ($($gen:tt)*) $depth:tt { $t:tt $($rest:tt)* }) => {
+        $crate::using_fn! { @generics $head ($($gen)* $t) $depth { $($rest)* } }
+    };
+
+    // the target, followed by the parameters
+    (@target $head:tt $gen:tt ( $([$default:ident])? $id:ident @ $target:expr $(, $($params:tt)*)? ) $rest:tt) => {
+        $crate::using_fn! { @params $head $gen ($([$default])? $id $target) () () { $($($params)*)? } $rest }
+    };
+
+    (@target $head:tt $gen:tt ( $target:expr $(, $($params:tt)*)? ) $rest:tt) => {
+        $crate::using_fn! { @params $head $gen ([default] target $target) () () { $($($params)*)? } $rest }
+    };
+
+    // the pattern of a parameter is accumulated until the `:` preceding its type
+    (@params $head:tt $gen:tt $target:tt ($($done:tt)*) ($($pat:tt)+) { : $ty:ty $(, $($params:tt)*)? } $rest:tt) => {
+        $crate::using_fn! { @params $head $gen $target ($($done)* [$($pat)+: $ty]) () { $($($params)*)? } $rest }
+    };
+
+    (@params $head:tt $gen:tt $target:tt $done:tt () { } $rest:tt) => {
+        $crate::using_fn! { @sig $head $gen $target $done $rest }
+    };
+
+    (@params $head:tt $gen:tt $target:tt $done:tt ($($pat:tt)*) { $t:tt $($params:tt)* } $rest:tt) => {
+        $crate::using_fn! { @params $head $gen $target $done ($($pat)* $t) { $($params)* } $rest }
+    };
+
+    // the return type and the where clause, which is accumulated until the body
+    (@sig $head:tt $gen:tt $target:tt $params:tt { -> $ret:ty { $($t:tt)* } $($rest:tt)* }) => {
+        $crate::using_fn! { @fn $head $gen $target $params (-> $ret) () { $($t)* } { $($rest)* } }
+    };
+
+    (@sig $head:tt $gen:tt $target:tt $params:tt { -> $ret:ty where $($rest:tt)* }) => {
+        $crate::using_fn! { @where $head $gen $target $params (-> $ret) (where) { $($rest)* } }
+    };
+
+    (@sig $head:tt $gen:tt $target:tt $params:tt { { $($t:tt)* } $($rest:tt)* }) => {
+        $crate::using_fn! { @fn $head $gen $target $params () () { $($t)* } { $($rest)* } }
+    };
+
+    (@sig $head:tt $gen:tt $target:tt $params:tt { where $($rest:tt)* }) => {
+        $crate::using_fn! { @where $head $gen $target $params () (where) { $($rest)* } }
+    };
+
+    (@where $head:tt $gen:tt $target:tt $params:tt $ret:tt $where:tt { { $($t:tt)* } $($rest:tt)* }) => {
+        $crate::using_fn! { @fn $head $gen $target $params $ret $where { $($t)* } { $($rest)* } }
+    };
+
+    (@where $head:tt $gen:tt $target:tt $params:tt $ret:tt ($($where:tt)*) { $w:tt $($rest:tt)* }) => {
+        $crate::using_fn! { @where $head $gen $target $params $ret ($($where)* $w) { $($rest)* } }
+    };
+
+    (
+        @fn ($($head:tt)*) ($($gen:tt)*) ($([$default:ident])? $id:ident $target:expr) ($([$($param:tt)*])*)
+        (-> $ret:ty) ($($where:tt)*) { $( $t:tt )* } { $( $rest:tt )* }
+    ) => {
+        $($head)* $($gen)* ($($($param)*),*) -> $ret $($where)* {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id () root empty { $($t)* } } }
+        }
+
+        $crate::using_fn! { $($rest)* }
+    };
+
+    (
+        @fn ($($head:tt)*) ($($gen:tt)*) ($([$default:ident])? $id:ident $target:expr) ($([$($param:tt)*])*)
+        () ($($where:tt)*) { $( $t:tt )* } { $( $rest:tt )* }
+    ) => {
+        $($head)* $($gen)* ($($($param)*),*) $($where)* {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } }
+        }
+
+        $crate::using_fn! { $($rest)* }
+    };
+}
+
+/// A macro that provides method cascading for a builder and builds the result.
+///
+/// # Usage
+///
+/// ```plain
+/// using_build!(expression => { ... })
+///
+/// using_build!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to a type implementing
+/// [`UsingBuild`], on which [`UsingBuild::build`] is called after the block has been evaluated.
+/// The result of `build` is returned, therefore the block must not contain a trailing expression.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::{using_build, UsingBuild};
+/// #[derive(Default)]
+/// struct SumBuilder {
+///     values: Vec<i32>,
+/// }
+///
+/// impl SumBuilder {
+///     fn add(&mut self, value: i32) {
+///         self.values.push(value);
+///     }
+/// }
+///
+/// impl UsingBuild for SumBuilder {
+///     type Output = i32;
+///
+///     fn build(self) -> i32 {
+///         self.values.iter().sum()
+///     }
+/// }
+///
+/// let sum = using_build!(SumBuilder::default() => {
+///     .add(1);
+///     .add(2);
+/// });
+/// assert_eq!(sum, 3);
+///
+/// // Generated code:
+/// //
+/// // let sum = {
+/// //     let mut target = SumBuilder::default();
+/// //     let () = {
+/// //         target.add(1);
+/// //         target.add(2);
+/// //     };
+/// //     UsingBuild::build(target)
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_build {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            let () = { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } };
+            $crate::UsingBuild::build($id)
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_build!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and validates it afterwards.
+///
+/// # Usage
+///
+/// ```plain
+/// using_validated!(expression => { ... })
+///
+/// using_validated!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that `expression` must evaluate to a type implementing
+/// [`Validate`], which is used to check the target after the block has been evaluated. The macro
+/// evaluates to `Ok` containing the target if it is valid and to the error returned by
+/// [`Validate::validate`] otherwise, therefore the block must not contain a trailing expression.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::{using_validated, Validate};
+/// #[derive(Debug, Default)]
+/// struct Range {
+///     start: i32,
+///     end: i32,
+/// }
+///
+/// impl Range {
+///     fn start(&mut self, start: i32) {
+///         self.start = start;
+///     }
+///
+///     fn end(&mut self, end: i32) {
+///         self.end = end;
+///     }
+/// }
+///
+/// impl Validate for Range {
+///     type Error = &'static str;
+///
+///     fn validate(&self) -> Result<(), &'static str> {
+///         if self.start <= self.end { Ok(()) } else { Err("start must not be after end") }
+///     }
+/// }
+///
+/// let range = using_validated!(Range::default() => {
+///     .start(10);
+///     .end(5);
+/// });
+/// assert_eq!(range.unwrap_err(), "start must not be after end");
+///
+/// // Generated code:
+/// //
+/// // let range = {
+/// //     let mut target = Range::default();
+/// //     let () = {
+/// //         target.start(10);
+/// //         target.end(5);
+/// //     };
+/// //     match Validate::validate(&target) {
+/// //         Ok(()) => Ok(target),
+/// //         Err(err) => Err(err),
+/// //     }
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_validated {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            let () = { $crate::using_impl! { @named $([$default])? $id () block empty { $($t)* } } };
+            match $crate::Validate::validate(&$id) {
+                ::core::result::Result::Ok(()) => ::core::result::Result::Ok($id),
+                ::core::result::Result::Err(err) => ::core::result::Result::Err(err),
+            }
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_validated!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and collects all errors of fallible
+/// target expressions.
+///
+/// # Usage
+///
+/// ```plain
+/// using_errors!(expression => { ... })
+///
+/// using_errors!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that target expression statements ending with a question mark
+/// operator (e.g. `.port(value)?;`) do not return early: instead, the error is added to a
+/// collection and the evaluation of the block continues. The collection must implement `Default`
+/// and `Extend` (e.g. `Vec`) and is inferred from the context. The macro evaluates to `Ok`
+/// containing the target if no error occurred and to `Err` containing all errors otherwise,
+/// therefore the block must not contain a trailing expression.
+///
+/// All collected errors must have the same type. Question mark operators in other positions, e.g.
+/// in let statements, behave as usual.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_errors;
+/// #[derive(Debug, Default)]
+/// struct Config {
+///     port: u16,
+///     threads: u8,
+/// }
+///
+/// impl Config {
+///     fn port(&mut self, port: &str) -> Result<(), String> {
+///         self.port = port.parse().map_err(|_| format!("invalid port: {port}"))?;
+///         Ok(())
+///     }
+///
+///     fn threads(&mut self, threads: &str) -> Result<(), String> {
+///         self.threads = threads.parse().map_err(|_| format!("invalid threads: {threads}"))?;
+///         Ok(())
+///     }
+/// }
+///
+/// let config: Result<Config, Vec<String>> = using_errors!(Config::default() => {
+///     .port("http")?;
+///     .threads("1000")?;
+/// });
+/// assert_eq!(config.unwrap_err(), [ "invalid port: http", "invalid threads: 1000" ]);
+///
+/// // Generated code:
+/// //
+/// // let config: Result<Config, Vec<String>> = {
+/// //     let mut target = Config::default();
+/// //     let mut errors = Default::default();
+/// //     let mut failed = false;
+/// //     let () = {
+/// //         if let Err(err) = target.port("http") {
+/// //             Extend::extend(&mut errors, Some(err));
+/// //             failed = true;
+/// //         }
+/// //         if let Err(err) = target.threads("1000") {
+/// //             Extend::extend(&mut errors, Some(err));
+/// //             failed = true;
+/// //         }
+/// //     };
+/// //     if failed { Err(errors) } else { Ok(target) }
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_errors {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            #[allow(unused_mut)]
+            let mut errors = ::core::default::Default::default();
+            #[allow(unused_mut)]
+            let mut failed = false;
+            let () = { $crate::using_impl! { @named $([$default])? $id (collect errors failed) block empty { $($t)* } } };
+            if failed {
+                ::core::result::Result::Err(errors)
+            } else {
+                ::core::result::Result::Ok($id)
+            }
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_errors!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and catches panics in the block.
+///
+/// This macro is only available with the `std` feature.
+///
+/// # Usage
+///
+/// ```plain
+/// using_catch!(expression => { ... })
+///
+/// using_catch!(identifier @ expression => { ... })
+///
+/// using_catch!(ref mut ( identifier @ )? place => { ... })
+/// ```
+///
+/// Works like [`using`], except that the target expression and the block are evaluated with
+/// [`std::panic::catch_unwind`]. The macro evaluates to `Ok` containing the result of [`using`] or
+/// to `Err` containing the payload of the panic, i.e. `Result<T, Box<dyn Any + Send>>`. Note that
+/// the panic hook is still called, which prints the panic message by default.
+///
+/// The block is evaluated inside a closure, so `return` and question mark operators refer to the
+/// closure instead of the enclosing function. The closure is wrapped in
+/// [`AssertUnwindSafe`](std::panic::AssertUnwindSafe), therefore values that are borrowed mutably
+/// by the block (e.g. with `ref mut`) may be left in an inconsistent state after a panic.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_catch;
+/// #[derive(Debug, Default)]
+/// struct Config {
+///     threads: u8,
+/// }
+///
+/// impl Config {
+///     fn threads(&mut self, threads: u32) {
+///         self.threads = threads.try_into().expect("too many threads");
+///     }
+/// }
+///
+/// let config = using_catch!(Config::default() => {
+///     .threads(4);
+/// });
+/// assert_eq!(config.unwrap().threads, 4);
+///
+/// let config = using_catch!(Config::default() => {
+///     .threads(1000);
+/// });
+/// assert!(config.is_err());
+///
+/// // Generated code:
+/// //
+/// // let config = std::panic::catch_unwind(AssertUnwindSafe(|| {
+/// //     let mut target = Config::default();
+/// //     target.threads(1000);
+/// //     target
+/// // }));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! using_catch {
+    ($( $t:tt )*) => {
+        $crate::__private::catch_unwind($crate::__private::AssertUnwindSafe(|| $crate::using!($($t)*)))
+    };
+}
+
+/// A macro that provides method cascading for an object in an async block.
+///
+/// # Usage
+///
+/// ```plain
+/// async_using!(expression => { ... })
+///
+/// async_using!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the target expression and the block are evaluated in an
+/// `async move` block, so the macro evaluates to a future of the result of [`using`]. Target
+/// expressions can be awaited (e.g. `.next().await`), also as the scrutinee of a `while let` loop.
+/// With the `futures` feature, `for await` loops iterate over streams implementing `Stream` of the
+/// `futures-core` crate.
+///
+/// As in any async block, `return` and question mark operators refer to the async block instead of
+/// the enclosing function.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::async_using;
+/// use futures::executor::block_on;
+/// use futures::stream::{self, StreamExt};
+///
+/// let sum = async_using!(stream::iter(1..=3) => {
+///     let mut sum = 0;
+///     while let Some(value) = .next().await {
+///         sum += value;
+///     }
+///     sum
+/// });
+/// assert_eq!(block_on(sum), 6);
+///
+/// # #[cfg(feature = "futures")] {
+/// let batches = stream::iter([ vec![ 1, 2 ], vec![ 3 ] ]);
+/// let values = async_using!(Vec::new() => {
+///     for await batch in batches {
+///         .extend(batch);
+///     }
+/// });
+/// assert_eq!(block_on(values), [ 1, 2, 3 ]);
+/// # }
+///
+/// // Generated code:
+/// //
+/// // let values = async move {
+/// //     let mut target = Vec::new();
+/// //     {
+/// //         let mut stream = pin!(batches);
+/// //         while let Some(batch) = next(stream.as_mut()).await {
+/// //             target.extend(batch);
+/// //         }
+/// //     }
+/// //     target
+/// // };
+/// ```
+#[macro_export]
+macro_rules! async_using {
+    ($( $t:tt )*) => {
+        async move { $crate::using!($($t)*) }
+    };
+}
+
+/// A macro that provides Dart-style method cascading, compatible with the `cascade` crate.
+///
+/// # Usage
+///
+/// ```plain
+/// cascade! { expression; ... }
+///
+/// cascade! { let identifier ( : type )? = expression; ... }
+/// ```
+///
+/// Works like [`using`], except that the target is given by the first statement and the remaining
+/// statements make up the block. Besides the target expressions of [`using`], target expressions
+/// can also start with `..` (e.g. `..push(1);` or `..x += 1;`), and statements can be prefixed
+/// with `|`, which is ignored. If the block does not contain a trailing expression, the target is
+/// returned.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::cascade;
+/// struct Counter {
+///     count: u32,
+///     history: Vec<u32>,
+/// }
+///
+/// let counter = cascade! {
+///     let c = Counter { count: 0, history: Vec::new() };
+///     ..count += 1;
+///     ..history.push(c.count);
+///     ..count = 10;
+///     | c.history.push(c.count);
+/// };
+/// assert_eq!(counter.history, [ 1, 10 ]);
+///
+/// // Generated code:
+/// //
+/// // let counter = {
+/// //     let mut c = Counter { count: 0, history: Vec::new() };
+/// //     c.count += 1;
+/// //     c.history.push(c.count);
+/// //     c.count = 10;
+/// //     c.history.push(c.count);
+/// //     c
+/// // };
+/// ```
+#[macro_export]
+macro_rules! cascade {
+    (let _ : $ty:ty = $target:expr; $( $t:tt )*) => {
+        $crate::cascade!(let [default] target: $ty = $target; $($t)*)
+    };
+    (let $([$default:ident])? $id:ident : $ty:ty = $target:expr; $( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id: $ty = $target;
+            { $crate::using_impl! { @named $([$default])? $id (cascade) root empty { $($t)* } } }
+        }
+    };
+    (let $([$default:ident])? $id:ident = $target:expr; $( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id (cascade) root empty { $($t)* } } }
+        }
+    };
+    ($target:expr; $( $t:tt )*) => {
+        $crate::cascade!(let [default] target = $target; $($t)*)
+    };
+}
+
+/// A macro that provides method cascading for an explicitly named object.
+///
+/// # Usage
+///
+/// ```plain
+/// with! { identifier ( : type )? = expression; ... }
+/// ```
+///
+/// An alternative syntax for [`using`] for code that prefers to always refer to the target by
+/// name: the first statement binds `expression` to a mutable variable named `identifier` and the
+/// remaining statements make up the block. Target expressions starting with a dot can still be
+/// used. If the block does not contain a trailing expression, the target is returned.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::with;
+/// let vec = with! {
+///     vec = Vec::new();
+///     vec.push(1);
+///     for i in 2..4 {
+///         vec.push(i);
+///     }
+/// };
+/// assert_eq!(vec, [ 1, 2, 3 ]);
+///
+/// // Generated code:
+/// //
+/// // let vec = {
+/// //     let mut vec = Vec::new();
+/// //     vec.push(1);
+/// //     for i in 2..4 {
+/// //         vec.push(i);
+/// //     }
+/// //     vec
+/// // };
+/// ```
+#[macro_export]
+macro_rules! with {
+    ($id:ident : $ty:ty = $target:expr; $( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id: $ty = $target;
+            { $crate::using_impl! { @named $id () root empty { $($t)* } } }
+        }
+    };
+    ($id:ident = $target:expr; $( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $id () root empty { $($t)* } } }
+        }
+    };
+}
+
+/// A macro that provides method cascading for an object and prints the target after each target
+/// expression statement.
+///
+/// # Usage
+///
+/// ```plain
+/// using_dbg!(expression => { ... })
+///
+/// using_dbg!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], but after each statement consisting of a target expression (e.g.
+/// `.push(1);` or `.x = 1;`), the statement and the target are printed to stderr, similar to
+/// `dbg!`. Therefore, the target must implement `Debug` and the macro requires `std`. This allows
+/// temporarily replacing [`using`] with `using_dbg` to debug the construction of an object.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_dbg;
+/// let vec = using_dbg!(vec @ Vec::new() => {
+///     .push(1);
+///     .push(2);
+/// });
+/// assert_eq!(vec, [ 1, 2 ]);
+///
+/// // Prints:
+/// //
+/// // [src/main.rs:2] vec.push(1); vec = [
+/// //     1,
+/// // ]
+/// // [src/main.rs:2] vec.push(2); vec = [
+/// //     1,
+/// //     2,
+/// // ]
+/// ```
+#[macro_export]
+macro_rules! using_dbg {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            { $crate::using_impl! { @named $([$default])? $id (dbg) root empty { $($t)* } } }
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_dbg!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that provides method cascading for an object and records the evaluated statements.
+///
+/// # Usage
+///
+/// ```plain
+/// using_record!(expression => { ... })
+///
+/// using_record!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], but additionally records each evaluated statement consisting of a target
+/// expression (e.g. `.push(1);` or `.x = 1;`) and each taken branch of `if` and `match`
+/// expressions as a `&'static str`. The records are collected into a collection that implements
+/// `Default` and `Extend` (e.g. `Vec`), which is inferred from the context, and the macro
+/// evaluates to a tuple containing the result of [`using`] and the records. This is useful to
+/// check the construction logic of objects in tests.
+///
+/// The recorded strings are created with `stringify!` from the generated code, therefore they
+/// contain the name of the target, and the formatting might differ from the original source.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_record;
+/// let (vec, records): (_, Vec<_>) = using_record!(vec @ Vec::new() => {
+///     for i in 0..2 {
+///         if i == 0 {
+///             .push(i);
+///         } else {
+///             .push(i * 10);
+///         }
+///     }
+/// });
+/// assert_eq!(vec, [ 0, 10 ]);
+/// assert_eq!(records, [ "if i == 0", "vec.push(i)", "else", "vec.push(i * 10)" ]);
+/// ```
+#[macro_export]
+macro_rules! using_record {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $target;
+            #[allow(unused_mut)]
+            let mut records = ::core::default::Default::default();
+            let result = { $crate::using_impl! { @named $([$default])? $id (record records) root empty { $($t)* } } };
+            (result, records)
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::using_record!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that asserts target expressions of an object.
+///
+/// # Usage
+///
+/// ```plain
+/// assert_using!(expression => { ... })
+///
+/// assert_using!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`], except that the target is a shared reference to `expression` and every
+/// statement (and the trailing expression) starting with a target expression is a boolean
+/// condition that is asserted with `assert!`. Unlike in [`using`], such conditions may contain
+/// operators after the target expression, e.g. `.len() == 2;`. Other statements, including let
+/// statements, are evaluated as usual.
+///
+/// # Panics
+///
+/// Panics if one of the conditions evaluates to `false`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::assert_using;
+/// let vec = vec![1, 2, 3];
+/// assert_using!(vec => {
+///     .len() == 3;
+///     .contains(&2);
+///     let sum: i32 = .iter().sum();
+///     assert_eq!(sum, 6);
+///     .iter().all(|x| *x > 0)
+/// });
+///
+/// // Generated code:
+/// //
+/// // {
+/// //     let target = &vec;
+/// //     assert!(target.len() == 3);
+/// //     assert!(target.contains(&2));
+/// //     let sum: i32 = target.iter().sum();
+/// //     assert_eq!(sum, 6);
+/// //     assert!(target.iter().all(|x| *x > 0))
+/// // }
+/// ```
+#[macro_export]
+macro_rules! assert_using {
+    ($([$default:ident])? $id:ident @ $target:expr => { $( $t:tt )* }) => {
+        {
+            let $id = &$target;
+            { $crate::using_impl! { @named $([$default])? $id (assert) block empty { $($t)* } } }
+        }
+    };
+    ($target:expr => { $( $t:tt )* }) => {
+        $crate::assert_using!([default] target @ $target => { $($t)* })
+    };
+}
+
+/// A macro that creates a map from a list of entries.
+///
+/// # Usage
+///
+/// ```plain
+/// using_map! { key => value, ... }
+/// ```
+///
+/// Creates a map (or any other collection that implements `Default` and `Extend<(K, V)>`, e.g.
+/// `HashMap` or `BTreeMap`), which is inferred from the context, and inserts the given entries.
+/// Like in [`using`], the entries can be interleaved with statements and `for`, `if`, `while` and
+/// `loop` expressions, whose bodies can contain entries as well. Entries must be separated by
+/// commas, and values that start with one of these keywords must be wrapped in parentheses.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use using::using_map;
+/// let map: BTreeMap<_, _> = using_map! {
+///     "zero" => 0,
+///     for (i, name) in [ "one", "two" ].into_iter().enumerate() {
+///         name => i + 1,
+///     }
+///     if false {
+///         "three" => 3,
+///     }
+/// };
+/// assert_eq!(map.len(), 3);
+/// assert_eq!(map["two"], 2);
+///
+/// // Generated code:
+/// //
+/// // let map: BTreeMap<_, _> = {
+/// //     let mut target = Default::default();
+/// //     Extend::extend(&mut target, Some(("zero", 0)));
+/// //     for (i, name) in [ "one", "two" ].into_iter().enumerate() {
+/// //         Extend::extend(&mut target, Some((name, i + 1)));
+/// //     }
+/// //     if false {
+/// //         Extend::extend(&mut target, Some(("three", 3)));
+/// //     }
+/// //     target
+/// // };
+/// ```
+#[macro_export]
+macro_rules! using_map {
+    ($( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut target = ::core::default::Default::default();
+            $crate::using_entries!(@entries (map target [default]) [] () { $($t)* })
+        }
+    };
+}
+
+/// A macro that creates a set from a list of values.
+///
+/// # Usage
+///
+/// ```plain
+/// using_set![value, ...]
+/// ```
+///
+/// Works like [`using_map`], but creates a set (or any other collection that implements `Default`
+/// and `Extend<T>`, e.g. `HashSet` or `BTreeSet`) from the given values.
+///
+/// # Examples:
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use using::using_set;
+/// let set: HashSet<_> = using_set![
+///     1,
+///     for i in 0..4 {
+///         i * i,
+///     }
+/// ];
+/// assert_eq!(set.len(), 4);
+/// ```
+#[macro_export]
+macro_rules! using_set {
+    ($( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut target = ::core::default::Default::default();
+            $crate::using_entries!(@entries (value target [default]) [] () { $($t)* })
+        }
+    };
+}
+
+/// A macro that creates a vector from a list of values.
+///
+/// # Usage
+///
+/// ```plain
+/// using_vec![value, ...]
+/// ```
+///
+/// Works like [`using_map`], but creates a vector (or any other collection that implements
+/// `Default` and `Extend<T>`, e.g. `VecDeque` or `String`) from the given values.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using_vec;
+/// let vec: Vec<_> = using_vec![
+///     0,
+///     for i in 1..10 {
+///         if i % 3 == 0 {
+///             i,
+///         }
+///     }
+///     10,
+/// ];
+/// assert_eq!(vec, [ 0, 3, 6, 9, 10 ]);
+/// ```
+#[macro_export]
+macro_rules! using_vec {
+    ($( $t:tt )*) => {
+        {
+            #[allow(unused_mut)]
+            let mut target = ::core::default::Default::default();
+            $crate::using_entries!(@entries (value target [default]) [] () { $($t)* })
+        }
+    };
+}
+
+/// A macro that builds a `String`.
+///
+/// # Usage
+///
+/// ```plain
+/// using_string! { ... }
+///
+/// using_string!(identifier @ { ... })
+/// ```
+///
+/// Creates an empty `String` and uses it as the target of the block, which supports the following
+/// statements in addition to the ones supported by [`using`]:
+///
+/// * Literals (e.g. `"Hello";`, `'!';` or `42;`), which are appended to the string.
+/// * `+= expression;`, which appends a `&str` to the string.
+/// * `write!(...);` and `writeln!(...);` with the arguments of `format!`, which append a formatted
+///   string.
+///
+/// Like in [`using_map`], these statements can also be used in the bodies of `for`, `if`, `while`
+/// and `loop` expressions. The macro evaluates to the built string. This macro requires the
+/// `alloc` feature.
+///
+/// # Examples:
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use using::using_string;
+/// let columns = [ "id", "name" ];
+/// let query = using_string!(query @ {
+///     "SELECT ";
+///     for (i, column) in columns.iter().enumerate() {
+///         if i > 0 {
+///             ", ";
+///         }
+///         += column;
+///     }
+///     write!(" FROM {}", "users");
+///     if query.len() > 80 {
+///         '\n';
+///     }
+/// });
+/// assert_eq!(query, "SELECT id, name FROM users");
+/// # }
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! using_string {
+    ($([$default:ident])? $id:ident @ { $( $t:tt )* }) => {
+        {
+            #[allow(unused_mut)]
+            let mut $id = $crate::__private::String::new();
+            $crate::using_entries!(@entries (string $id $([$default])?) [] () { $($t)* })
+        }
+    };
+    ($( $t:tt )*) => {
+        $crate::using_string!([default] target @ { $($t)* })
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_pipe_impl {
+    // the macros of this crate with their own target, whose `@` is not replaced in a step, e.g.
+    // `using!(@ => { ... })`; this must be the same list as `NESTED_MACROS` in
+    // `using-macros/src/rewrite.rs`, which is checked by `using-macros/tests/nested_macros.rs`
+    (@call using $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using $($x)*) };
+    (@call using_default $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_default $($x)*) };
+    (@call using_new $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_new $($x)*) };
+    (@call using_in $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_in $($x)*) };
+    (@call using_all $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_all $($x)*) };
+    (@call using_clone $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_clone $($x)*) };
+    (@call using_lock $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_lock $($x)*) };
+    (@call using_cell $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_cell $($x)*) };
+    (@call using_tls $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_tls $($x)*) };
+    (@call using_global $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_global $($x)*) };
+    (@call using_ptr $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_ptr $($x)*) };
+    (@call using_cow $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_cow $($x)*) };
+    (@call using_some $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_some $($x)*) };
+    (@call using_take $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_take $($x)*) };
+    (@call using_ok $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_ok $($x)*) };
+    (@call using_each $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_each $($x)*) };
+    (@call using_with $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_with $($x)*) };
+    (@call using_do $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_do $($x)*) };
+    (@call using_keep $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_keep $($x)*) };
+    (@call using_pipe $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_pipe $($x)*) };
+    (@call using_lazy $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_lazy $($x)*) };
+    (@call using_build $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_build $($x)*) };
+    (@call using_validated $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_validated $($x)*) };
+    (@call using_errors $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_errors $($x)*) };
+    (@call using_catch $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_catch $($x)*) };
+    (@call async_using $($x:tt)*) => { $crate::using_pipe_impl!(@cascade async_using $($x)*) };
+    (@call cascade $($x:tt)*) => { $crate::using_pipe_impl!(@cascade cascade $($x)*) };
+    (@call with $($x:tt)*) => { $crate::using_pipe_impl!(@cascade with $($x)*) };
+    (@call using_dbg $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_dbg $($x)*) };
+    (@call using_record $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_record $($x)*) };
+    (@call assert_using $($x:tt)*) => { $crate::using_pipe_impl!(@cascade assert_using $($x)*) };
+    (@call using_map $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_map $($x)*) };
+    (@call using_set $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_set $($x)*) };
+    (@call using_vec $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_vec $($x)*) };
+    (@call using_string $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_string $($x)*) };
+    (@call block $($x:tt)*) => { $crate::using_pipe_impl!(@cascade block $($x)*) };
+    (@call using_expand $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_expand $($x)*) };
+    (@call using_fn $($x:tt)*) => { $crate::using_pipe_impl!(@cascade using_fn $($x)*) };
+
+    (@call $m:ident $delim:ident $target:ident [ $($stack:tt)* ] ($($out:tt)*) ($($inner:tt)*) ($($rest:tt)*)) => {
+        $crate::using_pipe_impl!($target [ ($delim ($($out)* $m !) ($($rest)*)) $($stack)* ] () { $($inner)* })
+    };
+
+    (@cascade $m:ident $delim:ident $target:ident [ $($stack:tt)* ] ($($out:tt)*) ($($inner:tt)*) ($($rest:tt)*)) => {
+        $crate::using_pipe_impl!($target [ (cascade $delim ($($out)* $m !) ($($rest)*)) $($stack)* ] () { $($inner)* })
+    };
+
+    ($target:ident [] () { }) => {
+        $target
+    };
+
+    ($target:ident [] ($($out:tt)+) { }) => {
+        $($out)+
+    };
+
+    ($target:ident [] () { ; $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [] () { $($rest)* })
+    };
+
+    ($target:ident [] ($($out:tt)+) { ; $($rest:tt)* }) => {
+        {
+            let $target = $($out)+;
+            $crate::using_pipe_impl!($target [] () { $($rest)* })
+        }
+    };
+
+    ($target:ident [] () { . $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [] ($target .) { $($rest)* })
+    };
+
+    ($target:ident [ (paren ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* ( $($out)* )) { $($outer_rest)* })
+    };
+
+    ($target:ident [ (bracket ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* [ $($out)* ]) { $($outer_rest)* })
+    };
+
+    ($target:ident [ (brace ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* { $($out)* }) { $($outer_rest)* })
+    };
+
+    ($target:ident [ (cascade paren ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* ( $($out)* )) { $($outer_rest)* })
+    };
+
+    ($target:ident [ (cascade bracket ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* [ $($out)* ]) { $($outer_rest)* })
+    };
+
+    ($target:ident [ (cascade brace ($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_pipe_impl!($target [ $($stack)* ] ($($outer)* { $($out)* }) { $($outer_rest)* })
+    };
+
+    // The block of a nested cascade macro is copied as is, since `@` means something else there
+    // (e.g. the value of a nested `using_pipe!`).
+    ($target:ident [ (cascade $($frame:tt)*) $($stack:tt)* ] ($($out:tt)*) { => { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [ (cascade $($frame)*) $($stack)* ] ($($out)* => { $($body)* }) { $($rest)* })
+    };
+
+    // A `@` following an identifier is a binding (e.g. `n @ 1..=5` or `name @ expression`), unless
+    // the identifier is a keyword that can precede an expression.
+    ($target:ident $stack:tt ($($out:tt)*) { break @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* break $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { if @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* if $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { in @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* in $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { match @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* match $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { mut @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* mut $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { return @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* return $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { while @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* while $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { yield @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* yield $target) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { $id:ident @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* $id @) { $($rest)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { @ $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* $target) { $($rest)* })
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { $m:ident ! ( $($inner:tt)* ) $($rest:tt)* }) => {
+        $crate::using_pipe_impl!(@call $m paren $target [ $($stack)* ] ($($out)*) ($($inner)*) ($($rest)*))
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { $m:ident ! [ $($inner:tt)* ] $($rest:tt)* }) => {
+        $crate::using_pipe_impl!(@call $m bracket $target [ $($stack)* ] ($($out)*) ($($inner)*) ($($rest)*))
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { $m:ident ! { $($inner:tt)* } $($rest:tt)* }) => {
+        $crate::using_pipe_impl!(@call $m brace $target [ $($stack)* ] ($($out)*) ($($inner)*) ($($rest)*))
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { ( $($inner:tt)* ) $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [ (paren ($($out)*) ($($rest)*)) $($stack)* ] () { $($inner)* })
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { [ $($inner:tt)* ] $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [ (bracket ($($out)*) ($($rest)*)) $($stack)* ] () { $($inner)* })
+    };
+
+    ($target:ident [ $($stack:tt)* ] ($($out:tt)*) { { $($inner:tt)* } $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target [ (brace ($($out)*) ($($rest)*)) $($stack)* ] () { $($inner)* })
+    };
+
+    ($target:ident $stack:tt ($($out:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_pipe_impl!($target $stack ($($out)* $t) { $($rest)* })
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_entries {
+    (@entries ($kind:ident $target:ident $($default:tt)?) [] ($($out:tt)*) { }) => {
+        {
+            { $crate::using_impl! { @named $($default)? $target () block empty { $($out)* } } };
+            $target
+        }
+    };
+
+    (@entries $ctx:tt [ (($($outer:tt)*) ($($outer_rest:tt)*)) $($stack:tt)* ] ($($out:tt)*) { }) => {
+        $crate::using_entries!(@entries $ctx [ $($stack)* ] ($($outer)* { $($out)* }) { $($outer_rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { , $($rest:tt)* }) => {
+        $crate::using_entries!(@entries $ctx $stack ($($out)*) { $($rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { for $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* for) { $($rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { if $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* if) { $($rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { else $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* else) { $($rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { while $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* while) { $($rest)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { loop $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* loop) { $($rest)* })
+    };
+
+    (@entries $ctx:tt [ $($stack:tt)* ] ($($out:tt)*) { { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_entries!(@entries $ctx [ (($($out)*) ($($rest)*)) $($stack)* ] () { $($body)* })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { let $($rest:tt)* }) => {
+        $crate::using_entries!(@statement $ctx $stack ($($out)* let) { $($rest)* })
+    };
+
+    (@entries (map $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { $key:expr => $value:expr $(, $($rest:tt)*)? }) => {
+        $crate::using_entries!(@entries (map $target $($default)?) $stack (
+            $($out)*
+            ::core::iter::Extend::extend(&mut $target, ::core::option::Option::Some(($key, $value)));
+        ) { $($($rest)*)? })
+    };
+
+    (@entries (string $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { $lit:literal ; $($rest:tt)* }) => {
+        $crate::using_entries!(@entries (string $target $($default)?) $stack (
+            $($out)*
+            let _ = ::core::fmt::Write::write_fmt(&mut $target, ::core::format_args!("{}", $lit));
+        ) { $($rest)* })
+    };
+
+    (@entries (string $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { += $value:expr ; $($rest:tt)* }) => {
+        $crate::using_entries!(@entries (string $target $($default)?) $stack ($($out)* $target += $value;) { $($rest)* })
+    };
+
+    (@entries (string $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { write!($($args:tt)*) ; $($rest:tt)* }) => {
+        $crate::using_entries!(@entries (string $target $($default)?) $stack (
+            $($out)*
+            let _ = ::core::fmt::Write::write_fmt(&mut $target, ::core::format_args!($($args)*));
+        ) { $($rest)* })
+    };
+
+    (@entries (string $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { writeln!($($args:tt)*) ; $($rest:tt)* }) => {
+        $crate::using_entries!(@entries (string $target $($default)?) $stack (
+            $($out)*
+            let _ = ::core::fmt::Write::write_fmt(&mut $target, ::core::format_args!($($args)*));
+            $target.push('\n');
+        ) { $($rest)* })
+    };
+
+    (@entries (value $target:ident $($default:tt)?) $stack:tt ($($out:tt)*) { $value:expr $(, $($rest:tt)*)? }) => {
+        $crate::using_entries!(@entries (value $target $($default)?) $stack (
+            $($out)*
+            ::core::iter::Extend::extend(&mut $target, ::core::option::Option::Some($value));
+        ) { $($($rest)*)? })
+    };
+
+    (@entries $ctx:tt $stack:tt ($($out:tt)*) { $($rest:tt)* }) => {
+        $crate::using_entries!(@statement $ctx $stack ($($out)*) { $($rest)* })
+    };
+
+    (@header $ctx:tt [ $($stack:tt)* ] ($($out:tt)*) { { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_entries!(@entries $ctx [ (($($out)*) ($($rest)*)) $($stack)* ] () { $($body)* })
+    };
+
+    (@header $ctx:tt $stack:tt ($($out:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_entries!(@header $ctx $stack ($($out)* $t) { $($rest)* })
+    };
+
+    (@statement $ctx:tt $stack:tt ($($out:tt)*) { ; $($rest:tt)* }) => {
+        $crate::using_entries!(@entries $ctx $stack ($($out)* ;) { $($rest)* })
+    };
+
+    (@statement $ctx:tt $stack:tt ($($out:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_entries!(@statement $ctx $stack ($($out)* $t) { $($rest)* })
+    };
+}
+
+// reports an invalid use of a target expression or a binding that shadows the target, which
+// points to the offending tokens (and suggests a `let` binding) if the `macros` feature is enabled
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_error {
+    ($($t:tt)*) => {
+        $crate::__private::diagnostic!($($t)*)
+    };
+}
+
+#[cfg(not(feature = "macros"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_error {
+    (position $what:literal $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "target expressions cannot be used in ",
+            $what,
+            ", bind the value with `let` first (e.g. `let len = .len();`), ",
+            "or use `block!` (feature `macros`), which has no positional restrictions",
+        ))
+    };
+    (compound $exp:tt $t:tt) => {
+        ::core::compile_error!(::core::concat!(
+            "unexpected `",
+            ::core::stringify!($t),
+            "` after a target expression, which must be followed by `;` or end the block, ",
+            "since compound expressions (e.g. `.len() + 1`) are not supported; ",
+            "bind the value with `let` first (e.g. `let len = .len();`)",
+        ))
+    };
+    (assign $exp:tt $op:tt) => {
+        ::core::compile_error!(::core::concat!(
+            "only fields can be assigned in target expressions (e.g. `.x = 1;` or `.a().x = 1;`), ",
+            "bind the result of the method call with `let` first ",
+            "(e.g. `let last = .last_mut().unwrap(); *last = 1;`)",
+        ))
+    };
+    (deref $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "target expressions cannot be dereferenced, ",
+            "bind the result with `let` first (e.g. `let last = .last_mut().unwrap(); *last = 1;`)",
+        ))
+    };
+    (shadow $name:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "the pattern binds `",
+            ::core::stringify!($name),
+            "`, which shadows the target, so the following target expressions and the ",
+            "result would refer to the new binding, use a different name",
+        ))
+    };
+}
+
+// emits a `for await` loop over a stream, which polls the stream pinned on the stack
+#[cfg(feature = "futures")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_for_await {
+    (($pattern:pat) ($($stream:tt)*) { $($body:tt)* }) => {{
+        let mut stream = ::core::pin::pin!($($stream)*);
+        while let ::core::option::Option::Some($pattern) = $crate::__private::next(stream.as_mut()).await {
+            $($body)*
+        }
+    }};
+}
+
+#[cfg(not(feature = "futures"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_for_await {
+    ($($t:tt)*) => {
+        ::core::compile_error!("`for await` loops require the `futures` feature")
+    };
+}
+
+#[cfg(feature = "log")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_trace_log {
+    ($($st:tt)*) => {
+        $crate::__private::log::trace!(target: "using", "{}", ::core::stringify!($($st)*))
+    };
+}
+
+#[cfg(not(feature = "log"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_trace_log {
+    ($($st:tt)*) => { };
+}
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_trace_tracing {
+    ($($st:tt)*) => {
+        $crate::__private::tracing::trace!(target: "using", statement = ::core::stringify!($($st)*))
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_trace_tracing {
+    ($($st:tt)*) => { };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! using_impl {
+    (@hook (dbg $([$flag:ident])?) $target:tt ($($st:tt)*)) => {
+        ::std::eprintln!(
+            "[{}:{}] {}; {} = {:#?}",
+            ::core::file!(),
+            ::core::line!(),
+            ::core::stringify!($($st)*),
+            ::core::stringify!($target),
+            &$target,
+        )
+    };
+
+    (@hook (record $records:ident $([$flag:ident])?) $target:tt ($($st:tt)*)) => {
+        ::core::iter::Extend::extend(
+            &mut $records,
+            ::core::option::Option::Some(::core::stringify!($($st)*)),
+        )
+    };
+
+    (@hook $mode:tt $target:tt ($($st:tt)*)) => {
+        $crate::using_trace_log!($($st)*);
+        $crate::using_trace_tracing!($($st)*);
+    };
+
+
+
+    (@branch (record $records:ident $([$flag:ident])?) ($($branch:tt)*)) => {
+        ::core::iter::Extend::extend(
+            &mut $records,
+            ::core::option::Option::Some(::core::stringify!($($branch)*)),
+        )
+    };
+
+    (@branch $mode:tt ($($branch:tt)*)) => { };
+
+
+
+    // stores a clone of the target for `rollback;` and continues in the same expansion, so the
+    // local macro does not conflict with the one of a checkpoint in an enclosing block
+    (@checkpoint $target:tt $($rest:tt)*) => {
+        #[allow(unused_variables)]
+        let checkpoint = $target.clone();
+        #[allow(unused_macros)]
+        macro_rules! __using_checkpoint {
+            () => { $target.clone_from(&checkpoint) };
+        }
+        $crate::using_impl! { $target $($rest)* }
+    };
+
+    // restores the last checkpoint if `$exp` is an error, before it is returned by `?`
+    (@rollback $exp:expr) => {
+        match $exp {
+            ::core::result::Result::Ok(value) => ::core::result::Result::Ok(value),
+            ::core::result::Result::Err(err) => {
+                __using_checkpoint!();
+                ::core::result::Result::Err(err)
+            }
+        }
+    };
+
+    (@dangling $target:tt $mode:tt $scope:tt ($exp:expr) { $($rest:tt)* }) => {
+        let _ = &$exp;
+        ::core::compile_error!("expected a field or method name after `.`");
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // a target expression of `using_cow` on the owned value (`mut .x()`), which goes through
+    // `Cow::to_mut` instead of the dereferenced `Cow` and ends at the next `;`
+    (@cow_mut $id:ident $mode:tt $scope:tt ($($exp:tt)*) { }) => {
+        { $crate::using_impl! { ($id.to_mut()) $mode block empty { $($exp)* } } }
+    };
+
+    (@cow_mut $id:ident $mode:tt $scope:tt ($($exp:tt)*) { ; $($rest:tt)* }) => {
+        { $crate::using_impl! { ($id.to_mut()) $mode block empty { $($exp)* ; } } }
+        $crate::using_impl! { (&*$id) $mode $scope empty { $($rest)* } }
+    };
+
+    (@cow_mut $id:ident $mode:tt $scope:tt ($($exp:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { @cow_mut $id $mode $scope ($($exp)* $t) { $($rest)* } }
+    };
+
+    // continues with `$then` if `$t` starts a statement that is parsed token by token, and with
+    // `$else` otherwise
+    (@if_stmt_keyword let ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword if ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword match ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword for ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword while ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword loop ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword move ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_stmt_keyword $t:tt ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    // continues with `$then` if `$name` is a method of the standard library that returns `()`; the
+    // check of `in_trailing` cannot be used for every method, since it selects the method of
+    // `TrailingUnit` while the type of the value is still inferred, e.g. for `.iter().sum()`, and
+    // thereby infers it as `()`
+    (@if_unit_method append ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method clear ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method dedup ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method extend ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method extend_from_slice ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method fill ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method insert ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method push ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method push_back ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method push_front ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method push_str ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method reserve ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method resize ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method retain ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method reverse ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method sort ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method sort_by ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method sort_by_key ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method sort_unstable ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method swap ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method truncate ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_unit_method $name:ident ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    // continues with `$then` if `$args` are the arguments of a method call `$name`, and with `$else`
+    // otherwise (including a dangling `.` before a keyword, e.g. `. let (a, b) = ...`)
+    (@if_call let $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call if $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call match $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call for $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call while $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call move $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    (@if_call $name:ident ( $($args:tt)* ) ($($then:tt)*) $else:tt) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_call $name:tt $args:tt $then:tt ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    // continues with `$then` if `$op` is an assignment operator, and with `$else` otherwise
+    (@if_assign_op = ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op += ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op -= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op *= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op /= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op %= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op &= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op |= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op ^= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op <<= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op >>= ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($then)* }
+    };
+
+    (@if_assign_op $op:tt ($($then:tt)*) ($($else:tt)*)) => {
+        $crate::using_impl! { $($else)* }
+    };
+
+    // defines `__using_shadow_check` for a target named with `@` once per macro invocation, which
+    // reports a binding of a pattern (of a `let` statement, a `for` loop, an `if let` or `while
+    // let` condition or a `match` arm) that shadows the target, since the target expressions in
+    // its scope and the result would refer to the new binding. The default name (marked with
+    // `[default]` by the macros) is hygienic and cannot be shadowed, so it defines a check that
+    // accepts everything instead, which also hides the check of an enclosing invocation.
+    (@named [$default:ident] $($rest:tt)*) => {
+        $crate::using_impl! { @shadow_none ($) $($rest)* }
+    };
+
+    (@named $target:ident $($rest:tt)*) => {
+        $crate::using_impl! { @shadow_check ($) $target $target $($rest)* }
+    };
+
+    (@named (&mut *$target:ident) $($rest:tt)*) => {
+        $crate::using_impl! { @shadow_check ($) $target (&mut *$target) $($rest)* }
+    };
+
+    (@named (&*$target:ident) $($rest:tt)*) => {
+        $crate::using_impl! { @shadow_check ($) $target (&*$target) $($rest)* }
+    };
+
+    (@named $target:tt $($rest:tt)*) => {
+        $crate::using_impl! { $target $($rest)* }
+    };
+
+    (@shadow_none ($d:tt) $($rest:tt)*) => {
+        #[allow(unused_macros)]
+        macro_rules! __using_shadow_check {
+            ($d($d pattern:tt)*) => {};
+        }
+        $crate::using_impl! { $($rest)* }
+    };
+
+    // the uses of `__using_shadow_check` must be expanded from the expansion that defines it, so
+    // it does not conflict with the one of an enclosing macro invocation
+    (@shadow_check ($d:tt) $target:ident $($rest:tt)*) => {
+        #[allow(unused_macros)]
+        macro_rules! __using_shadow_check {
+            () => {};
+            // the identifier is matched again as a fragment to report it with its own span
+            (@ident $target $d name:ident $d($d rest:tt)*) => {
+                $crate::using_error!(shadow $d name)
+            };
+            (@ident $d _:ident $d name:ident $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d rest)*)
+            };
+            // field names in struct patterns and path segments are not bindings
+            ($target : $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d rest)*)
+            };
+            ($target :: $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d rest)*)
+            };
+            (:: $d _:tt $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d rest)*)
+            };
+            ($d name:ident $d($d rest:tt)*) => {
+                __using_shadow_check!(@ident $d name $d name $d($d rest)*)
+            };
+            (($d($d inner:tt)*) $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d inner)* $d($d rest)*)
+            };
+            ([$d($d inner:tt)*] $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d inner)* $d($d rest)*)
+            };
+            ({$d($d inner:tt)*} $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d inner)* $d($d rest)*)
+            };
+            ($d _:tt $d($d rest:tt)*) => {
+                __using_shadow_check!($d($d rest)*)
+            };
+        }
+        $crate::using_impl! { $($rest)* }
+    };
+
+    // checks a pattern with the `__using_shadow_check` defined by `@named`
+    (@shadow $target:ident $($pattern:tt)*) => {
+        __using_shadow_check!($($pattern)*);
+    };
+
+    (@shadow (&mut *$target:ident) $($pattern:tt)*) => {
+        $crate::using_impl! { @shadow $target $($pattern)* }
+    };
+
+    (@shadow (&*$target:ident) $($pattern:tt)*) => {
+        $crate::using_impl! { @shadow $target $($pattern)* }
+    };
+
+    (@shadow $target:tt $($pattern:tt)*) => {};
+
+    // checks the patterns of the `let` expressions in an `if` or `while` condition (e.g. `if let
+    // Some(x) = y && let Ok(z) = x`), which end at the first `=`
+    (@shadow_cond $target:tt ()) => {};
+
+    (@shadow_cond $target:tt (let $($rest:tt)*)) => {
+        $crate::using_impl! { @shadow_let $target () ($($rest)*) }
+    };
+
+    (@shadow_cond $target:tt ($t:tt $($rest:tt)*)) => {
+        $crate::using_impl! { @shadow_cond $target ($($rest)*) }
+    };
+
+    (@shadow_let $target:tt ($($pattern:tt)*) (= $($rest:tt)*)) => {
+        $crate::using_impl!(@shadow $target $($pattern)*);
+        $crate::using_impl! { @shadow_cond $target ($($rest)*) }
+    };
+
+    (@shadow_let $target:tt ($($pattern:tt)*) ($t:tt $($rest:tt)*)) => {
+        $crate::using_impl! { @shadow_let $target ($($pattern)* $t) ($($rest)*) }
+    };
+
+    (@shadow_let $target:tt ($($pattern:tt)*) ()) => {};
+
+    // reports an invalid token `$t` after a target expression, where `$op` is `$t` again to
+    // forward it with its original span
+    (@after_exp $exp:tt = $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt += $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt -= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt *= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt /= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt %= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt &= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt |= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt ^= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt <<= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt >>= $op:tt) => {
+        $crate::using_error!(assign $exp $op)
+    };
+
+    (@after_exp $exp:tt $t:tt $op:tt) => {
+        $crate::using_error!(compound $exp $op)
+    };
+
+
+
+    // reports an invalid header of a macro invocation, e.g. a missing `=>`
+    (@header $mac:literal ($($header:tt)*) { $($block:tt)* }) => {
+        ::core::compile_error!(::core::concat!(
+            "missing `=>` between the target and the block, expected `",
+            $mac,
+            "!(",
+            ::core::stringify!($($header)*),
+            " => { ... })`",
+        ))
+    };
+
+    (@header $mac:literal ($($header:tt)*) $t:tt $($rest:tt)*) => {
+        $crate::using_impl! { @header $mac ($($header)* $t) $($rest)* }
+    };
+
+    (@header $mac:literal ($($header:tt)*)) => {
+        ::core::compile_error!(::core::concat!(
+            "invalid invocation, expected `",
+            $mac,
+            "!(expression => { ... })` or `",
+            $mac,
+            "!(identifier @ expression => { ... })`",
+        ))
+    };
+
+
+
+    // emits `$emit` if the condition `$cond` does not contain target expressions, which cannot be
+    // parsed in conditions, and a clear error otherwise
+    (@guard $what:literal ($($cond:tt)*) ($($emit:tt)*)) => {
+        $crate::using_impl! { @guard_scan $what start ($($cond)*) () ($($emit)*) }
+    };
+
+    (@guard_scan $what:literal $state:ident () () ($($emit:tt)*)) => {
+        $($emit)*
+    };
+
+    (@guard_scan $what:literal $state:ident () (($($next:tt)*) $($stack:tt)*) $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what exp ($($next)*) ($($stack)*) $emit }
+    };
+
+    (@guard_scan $what:literal exp (. $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what start ($($rest)*) $stack $emit }
+    };
+
+    (@guard_scan $what:literal start (. $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_error!(position $what . $($rest)*)
+    };
+
+    (@guard_scan $what:literal $state:ident (( $($group:tt)* ) $($rest:tt)*) ($($stack:tt)*) $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what start ($($group)*) (($($rest)*) $($stack)*) $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident ([ $($group:tt)* ] $($rest:tt)*) ($($stack:tt)*) $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what start ($($group)*) (($($rest)*) $($stack)*) $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident ({ $($group:tt)* } $($rest:tt)*) ($($stack:tt)*) $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what start ($($group)*) (($($rest)*) $($stack)*) $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident ($t:ident $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what exp ($($rest)*) $stack $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident ($t:literal $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what exp ($($rest)*) $stack $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident (? $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what exp ($($rest)*) $stack $emit }
+    };
+
+    (@guard_scan $what:literal $state:ident ($t:tt $($rest:tt)*) $stack:tt $emit:tt) => {
+        $crate::using_impl! { @guard_scan $what start ($($rest)*) $stack $emit }
+    };
+
+
+
+    // the value of `if`, `match` and `loop` expressions is only used if they are the last
+    // statement, otherwise they are evaluated as statements, so that the value is dropped at once
+    ($target:tt $mode:tt $scope:tt maybe_trailing_exp ($($exp:tt)*) { }) => {
+        $($exp)*
+    };
+
+    ($target:tt $mode:tt $scope:tt maybe_trailing_exp ($($exp:tt)*) { ; $($rest:tt)* }) => {
+        $($exp)*;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt maybe_trailing_exp ($($exp:tt)*) { $($rest:tt)* }) => {
+        $($exp)*;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+
+
+    ($target:tt $mode:tt root empty { }) => {
+        $target
+    };
+
+    ($target:tt $mode:tt (root $ret:ident) empty { }) => {
+        $ret
+    };
+
+    ($target:tt $mode:tt block empty { }) => {
+        #[allow(unreachable_code)]
+        ()
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { ; $($rest:tt)* }) => {
+        ;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+
+
+    ($target:tt (cascade $([$flag:ident])?) $scope:tt empty { .. $($rest:tt)* }) => {
+        $crate::using_impl! { $target (cascade $([$flag])?) $scope in_exp ($target) { . $($rest)* } }
+    };
+
+    ($target:tt (cascade $([$flag:ident])?) $scope:tt empty { | $($rest:tt)* }) => {
+        $crate::using_impl! { $target (cascade $([$flag])?) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt empty { . $($rest:tt)* }) => {
+        $crate::using_impl! { $target (assert $([$flag])?) $scope in_exp ($target) { . $($rest)* } }
+    };
+
+    // method calls are emitted with the name and the arguments of the caller (matched as `tt`,
+    // since `ident` and `expr` fragments lose their location), so that a panic in a
+    // `#[track_caller]` method points to the statement instead of the whole invocation. The fast
+    // paths below match any two tokens after the `.`, so a dangling `.` before a `;`, calls of
+    // free functions and `?` in `(collect ...)` mode, which are handled differently, skip them.
+    ($target:tt $mode:tt $scope:tt empty { . ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($target) { . ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . > $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_pipe ($target) () { $($rest)* } }
+    };
+
+    ($target:tt (collect $errors:ident $failed:ident $([$flag:ident])?) $scope:tt empty { . $name:tt $args:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target (collect $errors $failed $([$flag])?) $scope in_exp ($target) { . $name $args ; $($rest)* } }
+    };
+
+    // fast path for runs of method calls (e.g. `.push(1); .push(2); ...`), which are emitted four
+    // at a time, since every step of the recursion passes the rest of the block
+    ($target:tt $mode:tt $scope:tt empty {
+        . $name1:tt $args1:tt ;
+        . $name2:tt $args2:tt ;
+        . $name3:tt $args3:tt ;
+        . $name4:tt $args4:tt ;
+        $($rest:tt)*
+    }) => {
+        $target.$name1 $args1;
+        $crate::using_impl!(@hook $mode $target ($target.$name1 $args1));
+        $target.$name2 $args2;
+        $crate::using_impl!(@hook $mode $target ($target.$name2 $args2));
+        $target.$name3 $args3;
+        $crate::using_impl!(@hook $mode $target ($target.$name3 $args3));
+        $target.$name4 $args4;
+        $crate::using_impl!(@hook $mode $target ($target.$name4 $args4));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // fast path for single method calls (e.g. `.push(1);`), which only needs one level of
+    // recursion per statement instead of three
+    ($target:tt $mode:tt $scope:tt empty { . $name:tt $args:tt ; $($rest:tt)* }) => {
+        $target.$name $args;
+        $crate::using_impl!(@hook $mode $target ($target.$name $args));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . $name:tt ::<$($ty:ty),* $(,)?> $args:tt ; $($rest:tt)* }) => {
+        $target.$name::<$($ty),*> $args;
+        $crate::using_impl!(@hook $mode $target ($target.$name::<$($ty),*> $args));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // shortcuts for the first step of longer target expressions and for assignments, which skip
+    // the `in_exp` state (a trailing method call still goes through it because of its warning)
+    ($target:tt $mode:tt $scope:tt empty { . $name:tt ::<$($ty:ty),* $(,)?> $args:tt $next:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($target.$name::<$($ty),*> $args) { $next $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . $name:ident . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($target.$name) { . $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . $name:ident = $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_assign ($target) $name = { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . $name:tt $op:tt $next:tt $($rest:tt)* }) => {
+        $crate::using_impl! {@if_call $name $op
+            ($target $mode $scope in_exp ($target.$name $op) { $next $($rest)* })
+            (@if_assign_op $op
+                ($target $mode $scope in_assign ($target) $name $op { $next $($rest)* })
+                ($target $mode $scope in_exp ($target) { . $name $op $next $($rest)* }))
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($target) { . $($rest)* } }
+    };
+
+    // a dangling `.` (e.g. while typing), which is reported and skipped, so that the rest of the
+    // block is still expanded
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . ; $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . let $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { let $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . if $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { if $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . match $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { match $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . for $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { for $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . while $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { while $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . loop $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { loop $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . move $($rest:tt)* }) => {
+        $crate::using_impl! { @dangling $target $mode $scope ($exp) { move $($rest)* } }
+    };
+
+    // optional chaining (e.g. `.logger?.set_level(level);`), where the rest of the statement is
+    // collected and applied to the value of the field if it is `Some`
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . $name:ident ? . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_opt ($exp) $name () { . $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_opt ($exp:expr) $name:ident ($($chain:tt)*) { ; $($rest:tt)* }) => {
+        if let ::core::option::Option::Some(value) = $exp.$name.as_mut() {
+            $crate::using_impl! { value $mode block empty { $($chain)* ; } }
+        }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_opt ($exp:expr) $name:ident ($($chain:tt)*) { }) => {
+        ::core::compile_error!(::core::concat!(
+            "optional chaining with `.",
+            ::core::stringify!($name),
+            "?` can only be used as a statement ending with `;`, not as a value",
+        ))
+    };
+
+    ($target:tt $mode:tt $scope:tt in_opt ($exp:expr) $name:ident ($($chain:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_opt ($exp) $name ($($chain)* $t) { $($rest)* } }
+    };
+
+    // calls of free functions (e.g. `.>apply_defaults();`), where the path of the function is
+    // collected until its arguments, and a mutable borrow of the target expression is passed as
+    // the first argument
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . > $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_pipe ($exp) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) () { ($($args:tt)*) $($rest:tt)* }) => {
+        ::core::compile_error!("expected a function after `.>`, e.g. `.>apply_defaults();`")
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) ($($path:tt)+) { () $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($($path)+ (&mut $exp)) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) ($($path:tt)+) { ($($args:tt)*) $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($($path)+ (&mut $exp, $($args)*)) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) ($($path:tt)*) { ; $($rest:tt)* }) => {
+        ::core::compile_error!(::core::concat!(
+            "expected arguments after `.>",
+            $(::core::stringify!($path),)*
+            "`, e.g. `.>apply_defaults();`",
+        ))
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) ($($path:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_pipe ($exp) ($($path)* $t) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_pipe ($exp:expr) ($($path:tt)*) { }) => {
+        ::core::compile_error!(::core::concat!(
+            "expected arguments after `.>",
+            $(::core::stringify!($path),)*
+            "`, e.g. `.>apply_defaults();`",
+        ))
+    };
+
+    ($target:tt $mode:tt root in_exp ($exp:expr) { . $name:tt ::<$($ty:ty),* $(,)?> $args:tt }) => {
+        $crate::using_impl! {@if_unit_method $name
+            ($target $mode root in_trailing ($exp.$name::<$($ty),*> $args))
+            ($target $mode root in_exp ($exp.$name::<$($ty),*> $args) { })
+        }
+    };
+
+    ($target:tt $mode:tt root in_exp ($exp:expr) { . $name:tt $args:tt }) => {
+        $crate::using_impl! {@if_call $name $args
+            (@if_unit_method $name
+                ($target $mode root in_trailing ($exp.$name $args))
+                ($target $mode root in_exp ($exp.$name $args) { }))
+            (@if_assign_op $args
+                ($target $mode root in_assign ($exp) $name $args { })
+                ($target $mode root in_exp ($exp.$name) { $args }))
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . $name:tt ::<$($ty:ty),* $(,)?> $args:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($exp.$name::<$($ty),*> $args) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . $name:tt $op:tt $($rest:tt)* }) => {
+        $crate::using_impl! {@if_call $name $op
+            ($target $mode $scope in_exp ($exp.$name $op) { $($rest)* })
+            (@if_assign_op $op
+                ($target $mode $scope in_assign ($exp) $name $op { $($rest)* })
+                ($target $mode $scope in_exp ($exp.$name) { $op $($rest)* }))
+        }
+    };
+
+    // assignments are emitted with the operator of the caller, so that errors (e.g. a missing
+    // implementation of `AddAssign`) point to it instead of the whole invocation
+    ($target:tt $mode:tt $scope:tt in_assign ($exp:expr) $name:tt $op:tt { $value:expr; $($rest:tt)* }) => {
+        $exp.$name $op $value;
+        $crate::using_impl!(@hook $mode $target ($exp.$name $op $value));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_assign ($exp:expr) $name:tt $op:tt { $($rest:tt)* }) => {
+        ::core::compile_error!(::core::concat!(
+            "expected `;` after the assignment to `.",
+            ::core::stringify!($name),
+            "`",
+        ))
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { . $name:ident $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($exp.$name) { $($rest)* } }
+    };
+
+    ($target:tt (collect $errors:ident $failed:ident $([$flag:ident])?) $scope:tt in_exp ($exp:expr) { ? ; $($rest:tt)* }) => {
+        if let ::core::result::Result::Err(err) = $exp {
+            ::core::iter::Extend::extend(&mut $errors, ::core::option::Option::Some(err));
+            $failed = true;
+        }
+        $crate::using_impl! { $target (collect $errors $failed $([$flag])?) $scope empty { $($rest)* } }
+    };
+
+    // after `checkpoint auto;`, the target is rolled back before an error is returned
+    ($target:tt ($($mode:ident)* [rollback]) $scope:tt in_exp ($exp:expr) { ? $($rest:tt)* }) => {
+        $crate::using_impl! { $target ($($mode)* [rollback]) $scope in_exp ($crate::using_impl!(@rollback $exp)?) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { ? $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_exp ($exp?) { $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_exp ($exp:expr) { }) => {
+        ::core::assert!($exp)
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_exp ($exp:expr) { ; $($rest:tt)* }) => {
+        ::core::assert!($exp);
+        $crate::using_impl! { $target (assert $([$flag])?) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_exp ($exp:expr) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target (assert $([$flag])?) $scope in_assert ($exp $t) { $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_assert ($($cond:tt)*) { }) => {
+        ::core::assert!($($cond)*)
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_assert ($($cond:tt)*) { ; $($rest:tt)* }) => {
+        ::core::assert!($($cond)*);
+        $crate::using_impl! { $target (assert $([$flag])?) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_assert ($($cond:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target (assert $([$flag])?) $scope in_assert ($($cond)* $t) { $($rest)* } }
+    };
+
+    // a trailing call of a method that usually returns `()` (e.g. `.push(1)` instead of
+    // `.push(1);`) in the block of the macro, which warns if it actually returns `()`, since the
+    // target is returned without it
+    ($target:tt (assert $([$flag:ident])?) root in_trailing ($exp:expr)) => {
+        $crate::using_impl! { $target (assert $([$flag])?) root in_exp ($exp) { } }
+    };
+
+    ($target:tt $mode:tt root in_trailing ($exp:expr)) => {
+        match $exp {
+            value => {
+                #[allow(unused_imports)]
+                use $crate::__private::{TrailingUnit as _, TrailingValue as _};
+                (&$crate::__private::Trailing(&value)).check();
+                value
+            }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { }) => {
+        $exp
+    };
+
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { ; $($rest:tt)* }) => {
+        $exp;
+        $crate::using_impl!(@hook $mode $target ($exp));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // the target expression is still emitted, so that IDEs can resolve it while it is incomplete,
+    // e.g. when completing `.` in a line without a `;`, and the rest of the block is still expanded
+    // if it starts with a statement
+    ($target:tt $mode:tt $scope:tt in_exp ($exp:expr) { $t:tt $($rest:tt)* }) => {
+        let _ = &$exp;
+        $crate::using_impl!(@after_exp ($exp) $t $t);
+        $crate::using_impl! {@if_stmt_keyword $t
+            ($target $mode $scope empty { $t $($rest)* })
+            ($target $mode $scope empty { })
+        }
+    };
+
+
+
+    ($target:tt $mode:tt $scope:tt empty { { $($block:tt)* } }) => {
+        $crate::using_impl! { $target $mode block empty { $($block)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { { $($block:tt)* } $($rest:tt)* }) => {
+        { $crate::using_impl! { $target $mode block empty { $($block)* } } };
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+
+
+    // the target is not returned after it has been dropped, so the rest of the block is expanded
+    // like a nested block
+    ($target:tt $mode:tt root empty { drop; $($rest:tt)* }) => {
+        ::core::mem::drop($target);
+        $crate::using_impl! { $target $mode block empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { drop; $($rest:tt)* }) => {
+        ::core::compile_error!(
+            "`drop;` can only be used at the top level of the block of a macro that returns its target"
+        );
+    };
+
+
+
+    // the checkpoint is restored by a local macro, since the variable is not visible to the
+    // following statements, which are expanded by other invocations of this macro; the mode is
+    // flagged with `[checkpoint]`, or `[rollback]` after `checkpoint auto;`, so `rollback;` without
+    // a checkpoint can be reported
+    ($target:tt ($($mode:ident)* [$flag:ident]) $scope:tt empty { checkpoint; $($rest:tt)* }) => {
+        $crate::using_impl! { @checkpoint $target ($($mode)* [$flag]) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt ($($mode:ident)*) $scope:tt empty { checkpoint; $($rest:tt)* }) => {
+        $crate::using_impl! { @checkpoint $target ($($mode)* [checkpoint]) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt ($($mode:ident)* $([$flag:ident])?) $scope:tt empty { checkpoint auto; $($rest:tt)* }) => {
+        $crate::using_impl! { @checkpoint $target ($($mode)* [rollback]) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt ($($mode:ident)* [$flag:ident]) $scope:tt empty { rollback; $($rest:tt)* }) => {
+        __using_checkpoint!();
+        $crate::using_impl! { $target ($($mode)* [$flag]) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { rollback; $($rest:tt)* }) => {
+        ::core::compile_error!(
+            "`rollback;` requires a preceding `checkpoint;` in the same block or an enclosing block"
+        );
+    };
+
+    // in nested blocks, the rebinding would be lost at the end of the block
+    ($target:tt $mode:tt block empty { move . $($rest:tt)* }) => {
+        ::core::compile_error!("`move` statements are only supported at the top level of the block");
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { move . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($target .) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { }) => {
+        $($exp)*
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { ; $($rest:tt)* }) => {
+        #[allow(unused_mut)]
+        let mut $target = $($exp)*;
+        $crate::using_impl!(@hook $mode $target ($($exp)*));
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // the tokens are accumulated four at a time (if none of them ends the accumulation), which
+    // reduces the recursion depth
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a) { } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt $b:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a $b) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt $b:tt }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a $b) { } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt $b:tt $c:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a $b $c) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt $b:tt $c:tt }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a $b $c) { } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $a $b $c $d) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_move ($($exp:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_move ($($exp)* $t) { $($rest)* } }
+    };
+
+
+
+    // `let` statements with a single identifier as pattern skip the munching of the pattern
+    ($target:tt $mode:tt $scope:tt empty { let mut $name:ident = $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp (mut $name) (_) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { let mut $name:ident : $ty:ty = $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp (mut $name) ($ty) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { let $name:ident = $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($name) (_) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { let $name:ident : $ty:ty = $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($name) ($ty) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { let $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let () { $($rest)* } }
+    };
+
+    // `_ .x();` discards the value of the target expression like `let _ = .x();`
+    ($target:tt (cascade $([$flag:ident])?) $scope:tt empty { _ .. $($rest:tt)* }) => {
+        $crate::using_impl! { $target (cascade $([$flag])?) $scope in_let_exp (_) (_) (..) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { _ . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp (_) (_) (.) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let
+        ($($pattern:tt)*)
+        { = $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) (_) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let
+        ($($pattern:tt)*)
+        { : $ty:ty = $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let
+        ($($pattern:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_let ($($pattern)* $t) { $($rest)* } }
+    };
+
+    // initializers that are plain expressions are parsed in one step instead of token by token,
+    // which keeps the expansion time linear for long blocks; initializers that start with a
+    // target expression or a block expression are still munched, since they are parsed by the
+    // `empty` arms
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (.) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { .. $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (..) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { | $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (|) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { * $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (*) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { mut . $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (mut .) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { if $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (if) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { match $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (match) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { loop $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (loop) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { while $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (while) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { for $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) (for) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { { $($block:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ({ $($block)* }) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) () { $value:expr; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($value) { ; $($rest)* } }
+    };
+
+    ($target:tt (assert $([$flag:ident])?) $scope:tt in_let_exp
+        ($($pattern:tt)*)
+        ($ty:ty)
+        ($($exp:tt)*)
+        { ; $($rest:tt)* }
+    ) => {
+        $crate::using_impl!(@shadow $target $($pattern)*);
+        let $($pattern)*: $ty = { $crate::using_impl! { $target ($([$flag])?) block empty { $($exp)* } } };
+        $crate::using_impl! { $target (assert $([$flag])?) $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp
+        ($($pattern:tt)*)
+        ($ty:ty)
+        ($($exp:tt)*)
+        { ; $($rest:tt)* }
+    ) => {
+        $crate::using_impl!(@shadow $target $($pattern)*);
+        let $($pattern)*: $ty = { $crate::using_impl! { $target $mode block empty { $($exp)* } } };
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) ($($exp:tt)*) { $a:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($($exp)* $a) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) ($($exp:tt)*) { $a:tt $b:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($($exp)* $a $b) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) ($($exp:tt)*) { $a:tt $b:tt $c:tt ; $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($($exp)* $a $b $c) { ; $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp ($($pattern:tt)*) ($ty:ty) ($($exp:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($($exp)* $a $b $c $d) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_let_exp
+        ($($pattern:tt)*)
+        ($ty:ty)
+        ($($exp:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_let_exp ($($pattern)*) ($ty) ($($exp)* $t) { $($rest)* } }
+    };
+
+
+
+    // shortcut for single-token conditions (e.g. `if flag { ... }`)
+    ($target:tt $mode:tt $scope:tt empty { if $cond:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if_next () (($cond) { $($body)* }) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { if $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if () () () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if
+        ($($if_curr:tt)*)
+        ()
+        ()
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_if_next
+            ()
+            (($($if_curr)*) { $($body)* })
+            ()
+            { $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if
+        ($($if_curr:tt)*)
+        ($($if_first:tt)*)
+        ($($if_rest:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_if_next
+            ()
+            ($($if_first)*)
+            ($($if_rest)* (($($if_curr)*) { $($body)* }))
+            { $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if ($($if_curr:tt)*) ($($if_first:tt)*) ($($if_rest:tt)*) { $a:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if ($($if_curr)* $a) ($($if_first)*) ($($if_rest)*) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if ($($if_curr:tt)*) ($($if_first:tt)*) ($($if_rest:tt)*) { $a:tt $b:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if ($($if_curr)* $a $b) ($($if_first)*) ($($if_rest)*) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if ($($if_curr:tt)*) ($($if_first:tt)*) ($($if_rest:tt)*) { $a:tt $b:tt $c:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if ($($if_curr)* $a $b $c) ($($if_first)*) ($($if_rest)*) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if ($($if_curr:tt)*) ($($if_first:tt)*) ($($if_rest:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_if ($($if_curr)* $a $b $c $d) ($($if_first)*) ($($if_rest)*) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if
+        ($($if_curr:tt)*)
+        ($($if_first:tt)*)
+        ($($if_rest:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_if
+            ($($if_curr)* $t)
+            ($($if_first)*)
+            ($($if_rest)*)
+            { $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if_next
+        ()
+        ($($if_first:tt)*)
+        ($($if_rest:tt)*)
+        { else if $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_if
+            ()
+            ($($if_first)*)
+            ($($if_rest)*)
+            { $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if_next
+        ()
+        (($($if_first_cond:tt)*) { $($if_first_body:tt)* })
+        ($( (($($if_rest_cond:tt)*) { $($if_rest_body:tt)* }) )*)
+        { else { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @shadow_cond $target ($($if_first_cond)* $( $($if_rest_cond)* )*) }
+        $crate::using_impl! { $target $mode $scope maybe_trailing_exp ($crate::using_impl!(@guard "`if` conditions" ($($if_first_cond)* $( , $($if_rest_cond)* )*) (
+            if $($if_first_cond)* {
+                $crate::using_impl!(@branch $mode (if $($if_first_cond)*));
+                $crate::using_impl! { $target $mode block empty { $($if_first_body)* } }
+            } $( else if $($if_rest_cond)* {
+                $crate::using_impl!(@branch $mode (else if $($if_rest_cond)*));
+                $crate::using_impl! { $target $mode block empty { $($if_rest_body)* } }
+            } )* else {
+                $crate::using_impl!(@branch $mode (else));
+                $crate::using_impl! { $target $mode block empty { $($body)* } }
+            }
+        ))) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_if_next
+        ()
+        (($($if_first_cond:tt)*) { $($if_first_body:tt)* })
+        ($( (($($if_rest_cond:tt)*) { $($if_rest_body:tt)* }) )*)
+        { $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @shadow_cond $target ($($if_first_cond)* $( $($if_rest_cond)* )*) }
+        $crate::using_impl! { @guard "`if` conditions" ($($if_first_cond)* $( , $($if_rest_cond)* )*) (
+            if $($if_first_cond)* {
+                $crate::using_impl!(@branch $mode (if $($if_first_cond)*));
+                $crate::using_impl! {$target $mode block empty { $($if_first_body)* }}
+            } $( else if $($if_rest_cond)* {
+                $crate::using_impl!(@branch $mode (else if $($if_rest_cond)*));
+                $crate::using_impl! {$target $mode block empty { $($if_rest_body)* }}
+            } )*
+        ) }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+
+
+    // shortcut for single-token scrutinees (e.g. `match value { ... }`)
+    ($target:tt $mode:tt $scope:tt empty { match $cond:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match_body ($cond) () { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { match $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match
+        ($($match_cond:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_match_body ($($match_cond)*) () { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match ($($match_cond:tt)*) { $a:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match ($($match_cond)* $a) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match ($($match_cond:tt)*) { $a:tt $b:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match ($($match_cond)* $a $b) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match ($($match_cond:tt)*) { $a:tt $b:tt $c:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match ($($match_cond)* $a $b $c) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match ($($match_cond:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match ($($match_cond)* $a $b $c $d) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match
+        ($($match_cond:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_match ($($match_cond)* $t) { $($rest)* } }
+    };
+
+    // the pattern of each arm is munched until the `=>` or the guard, so it can be checked for
+    // bindings that shadow the target (see `@named`), and then parsed as `pat` by the
+    // `in_match_arm` arms
+    ($target:tt $mode:tt $scope:tt in_match_body
+        ($($match_cond:tt)*)
+        ($( ($pattern:pat $( if $guard:expr )? => { $($exp:tt)* }) )*)
+        { { } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope maybe_trailing_exp ($crate::using_impl!(@guard "`match` scrutinees" ($($match_cond)*) (
+            match $($match_cond)* {
+                $( $pattern $( if $guard )* => {
+                    $crate::using_impl!(@branch $mode ($pattern $( if $guard )* =>));
+                    $crate::using_impl! { $target $mode block empty { $($exp)* } }
+                }, )*
+            }
+        ))) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body ($($match_cond:tt)*) ($($match_cases:tt)*) { { $a:tt => $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl!(@shadow $target $a);
+        $crate::using_impl! { $target $mode $scope in_match_arm ($($match_cond)*) ($($match_cases)*) { { $a => $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body ($($match_cond:tt)*) ($($match_cases:tt)*) { { $a:tt $b:tt => $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl!(@shadow $target $a $b);
+        $crate::using_impl! { $target $mode $scope in_match_arm ($($match_cond)*) ($($match_cases)*) { { $a $b => $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body ($($match_cond:tt)*) ($($match_cases:tt)*) { $body:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_match_pat ($($match_cond)*) ($($match_cases)*) () { $body $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_pat
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        ($($pattern:tt)*)
+        { { => $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl!(@shadow $target $($pattern)*);
+        $crate::using_impl! {$target $mode $scope in_match_arm
+            ($($match_cond)*)
+            ($($match_cases)*)
+            { { $($pattern)* => $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_pat
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        ($($pattern:tt)*)
+        { { if $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl!(@shadow $target $($pattern)*);
+        $crate::using_impl! {$target $mode $scope in_match_arm
+            ($($match_cond)*)
+            ($($match_cases)*)
+            { { $($pattern)* if $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_pat
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        ($($pattern:tt)*)
+        { { $t:tt $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_pat
+            ($($match_cond)*)
+            ($($match_cases)*)
+            ($($pattern)* $t)
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    // fast path for arms with a single method call or field (e.g. `1 => .push(1),`)
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => . $name:tt $args:tt , $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { . $name $args }))
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => . $name:tt $args:tt } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { . $name $args }))
+            { { } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => . $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($pattern) $($guard)*)
+            (.)
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { , $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($match_pattern $( if $match_guard )* => { $($match_exp)* }))
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($match_pattern $( if $match_guard )* => { $($match_exp)* }))
+            { { } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt , $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a)
+            { { , $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a)
+            { { } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt $b:tt , $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a $b)
+            { { , $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt $b:tt } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a $b)
+            { { } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt $b:tt $c:tt , $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a $b $c)
+            { { , $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt $b:tt $c:tt } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a $b $c)
+            { { } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $a:tt $b:tt $c:tt $d:tt $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $a $b $c $d)
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_body_in_exp
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        (($match_pattern:pat) $($match_guard:expr)?)
+        ($($match_exp:tt)*)
+        { { $t:tt $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body_in_exp
+            ($($match_cond)*)
+            ($($match_cases)*)
+            (($match_pattern) $($match_guard)*)
+            ($($match_exp)* $t)
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => { $($exp:tt)* }, $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { $($exp)* }))
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => { $($exp:tt)* } $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { $($exp)* }))
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => $exp:expr, $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { $exp }))
+            { { $($body)* } $($rest)* }
+        }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_match_arm
+        ($($match_cond:tt)*)
+        ($($match_cases:tt)*)
+        { { $pattern:pat $( if $guard:expr )? => $exp:expr } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! {$target $mode $scope in_match_body
+            ($($match_cond)*)
+            ($($match_cases)* ($pattern $( if $guard )* => { $exp }))
+            { { } $($rest)* }
+        }
+    };
+
+
+
+    ($target:tt $mode:tt $scope:tt empty { loop { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope maybe_trailing_exp (loop {
+            $crate::using_impl! { $target $mode block empty { $($body)* } }
+        }) { $($rest)* } }
+    };
+
+
+
+    ($target:tt $mode:tt $scope:tt empty { while $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_while () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while
+        ($($while_cond:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @shadow_cond $target ($($while_cond)*) }
+        $crate::using_impl! { $target $mode $scope in_while_body ($($while_cond)*) { { $($body)* } $($rest)* } }
+    };
+
+    // a target expression as the scrutinee of a `while let` loop (e.g. `while let Some(item) =
+    // .pop() { ... }`), which is evaluated again before every iteration
+    ($target:tt $mode:tt $scope:tt in_while_body
+        (let $while_pattern:pat = . $($while_exp:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @guard "`while` conditions" ($($while_exp)*) (
+            while let $while_pattern = $target.$($while_exp)* {
+                $crate::using_impl! {$target $mode block empty { $($body)* }}
+            }
+        ) }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while_body
+        ($($while_cond:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @guard "`while` conditions" ($($while_cond)*) (
+            while $($while_cond)* {
+                $crate::using_impl! {$target $mode block empty { $($body)* }}
+            }
+        ) }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while ($($while_cond:tt)*) { $a:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_while ($($while_cond)* $a) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while ($($while_cond:tt)*) { $a:tt $b:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_while ($($while_cond)* $a $b) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while ($($while_cond:tt)*) { $a:tt $b:tt $c:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_while ($($while_cond)* $a $b $c) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while ($($while_cond:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_while ($($while_cond)* $a $b $c $d) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_while
+        ($($while_cond:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_while ($($while_cond)* $t) { $($rest)* } }
+    };
+
+
+
+    // the pattern is munched until the `in`, so it can be checked for bindings that shadow the
+    // target (see `@named`); `for await` loops over streams, see `using_for_await`
+    ($target:tt $mode:tt $scope:tt empty { for $for_pattern:tt in $($rest:tt)* }) => {
+        $crate::using_impl!(@shadow $target $for_pattern);
+        $crate::using_impl! { $target $mode $scope in_for ($for_pattern) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { for $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for_pat () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for_pat ($($for_pattern:tt)*) { in $($rest:tt)* }) => {
+        $crate::using_impl!(@shadow $target $($for_pattern)*);
+        $crate::using_impl! { $target $mode $scope in_for ($($for_pattern)*) () { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for_pat ($($for_pattern:tt)*) { $t:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for_pat ($($for_pattern)* $t) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for
+        (await $for_pattern:pat)
+        ($($for_exp:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @guard "`for` loop streams" ($($for_exp)*) (
+            $crate::using_for_await!(($for_pattern) ($($for_exp)*) {
+                $crate::using_impl! {$target $mode block empty { $($body)* }}
+            });
+        ) }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for
+        ($($for_pattern:tt)*)
+        ($($for_exp:tt)*)
+        { { $($body:tt)* } $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { @guard "`for` loop iterators" ($($for_exp)*) (
+            for $($for_pattern)* in $($for_exp)* {
+                $crate::using_impl! {$target $mode block empty { $($body)* }}
+            }
+        ) }
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for $for_pattern:tt ($($for_exp:tt)*) { $a:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for $for_pattern ($($for_exp)* $a) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for $for_pattern:tt ($($for_exp:tt)*) { $a:tt $b:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for $for_pattern ($($for_exp)* $a $b) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for $for_pattern:tt ($($for_exp:tt)*) { $a:tt $b:tt $c:tt { $($body:tt)* } $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for $for_pattern ($($for_exp)* $a $b $c) { { $($body)* } $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for $for_pattern:tt ($($for_exp:tt)*) { $a:tt $b:tt $c:tt $d:tt $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_for $for_pattern ($($for_exp)* $a $b $c $d) { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_for
+        $for_pattern:tt
+        ($($for_exp:tt)*)
+        { $t:tt $($rest:tt)* }
+    ) => {
+        $crate::using_impl! { $target $mode $scope in_for $for_pattern ($($for_exp)* $t) { $($rest)* } }
+    };
+
+
+
+    ($target:tt $mode:tt $scope:tt empty { * . $($rest:tt)* }) => {
+        $crate::using_error!(deref * . $($rest)*)
+    };
+
+    // target expressions of `using_cow` on the owned value (see `@cow_mut`)
+    ((&*$id:ident) $mode:tt $scope:tt empty { mut . $($rest:tt)* }) => {
+        $crate::using_impl! { @cow_mut $id $mode $scope () { . $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { mut . $($rest:tt)* }) => {
+        ::core::compile_error!("`mut` target expressions are only supported in `using_cow`");
+    };
+
+    // items and statements with attributes, which cannot be parsed as expressions (the parser
+    // fails instead of trying the next arm), and macro calls, which must stay in statement position
+    // to be able to expand to statements
+    ($target:tt $mode:tt $scope:tt empty { static $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { static $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { unsafe fn $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { unsafe fn $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { unsafe impl $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { unsafe impl $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { unsafe trait $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { unsafe trait $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { unsafe extern $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { unsafe extern $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { async fn $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { async fn $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { async unsafe $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { async unsafe $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { const fn $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { const fn $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { const unsafe $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { const unsafe $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { const async $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { const async $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { # $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { # $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { $($path:ident)::+ ! { $($args:tt)* } $next:tt $($rest:tt)* }) => {
+        $($path)::+ ! { $($args)* }
+        $crate::using_impl! { $target $mode $scope empty { $next $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { $($path:ident)::+ ! $args:tt ; $($rest:tt)* }) => {
+        $($path)::+ ! $args ;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { :: $($path:ident)::+ ! $args:tt ; $($rest:tt)* }) => {
+        :: $($path)::+ ! $args ;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // expression statements are emitted with their `;`, since a `stmt` fragment followed by a `;`
+    // is a statement that must have the type `()` (e.g. `vec.len();` or `$e;` in other macros)
+    ($target:tt $mode:tt $scope:tt empty { $exp:expr; $($rest:tt)* }) => {
+        $exp;
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { $exp:expr }) => {
+        $exp
+    };
+
+    ($target:tt $mode:tt $scope:tt empty { $($rest:tt)* }) => {
+        $crate::using_impl! { $target $mode $scope in_stmt { $($rest)* } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_stmt { $st:stmt; $($rest:tt)* }) => {
+        $st
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+
+    // statements that are not followed by a `;`, i.e. items (e.g. `fn f() {}`) and `stmt` fragments
+    // forwarded by other macros as the last statement
+    ($target:tt $mode:tt $scope:tt in_stmt { $st:stmt }) => {
+        $st
+        $crate::using_impl! { $target $mode $scope empty { } }
+    };
+
+    ($target:tt $mode:tt $scope:tt in_stmt { $item:item $($rest:tt)* }) => {
+        $item
+        $crate::using_impl! { $target $mode $scope empty { $($rest)* } }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn simple() {
+        let vec = using!(Vec::new() => {
+            .push(1);
+            .push(2);
+            .push(3);
+            .push(4);
+            .push(5);
+        });
+        assert_eq!(vec.iter().sum::<i32>(), 15);
+    }
+
+    #[test]
+    fn brace_invocation() {
+        let vec = using! { Vec::new() => {
+            .push(1);
+            .push(2);
+        } };
+        assert_eq!(vec, [ 1, 2 ]);
+
+        let mut vec = vec;
+        using! { ref mut vec => {
+            .push(3);
+        } }
+        using! { vec @ Vec::new() => {
+            .push(4);
+            assert_eq!(vec, [ 4 ])
+        } }
+        assert_eq!(vec, [ 1, 2, 3 ]);
+    }
+
+    #[test]
+    fn simple_expr() {
+        let sum = using!(Vec::new() => {
+            .push(1);
+            .push(2);
+            .push(3);
+            .push(4);
+            .push(5);
+            .iter().sum::<i32>()
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn trailing_method() {
+        let inserted = using!(std::collections::BTreeSet::new() => {
+            .insert(1);
+            .insert(1)
+        });
+        assert!(!inserted);
+
+        let sum: i32 = using!([1, 2, 3] => {
+            .iter().sum()
+        });
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    fn unused_control_flow_value() {
+        let cell = std::cell::RefCell::new(1);
+        let vec = using!(Vec::new() => {
+            match 0 {
+                0 => cell.borrow_mut(),
+                _ => unreachable!(),
+            }
+            if true { cell.borrow_mut() } else { unreachable!() };
+            .push(*cell.borrow());
+        });
+        assert_eq!(vec, [1]);
+
+        let first = using!(values @ [1, 2] => {
+            if values.is_empty() { None } else { Some(values[0]) }
+        });
+        assert_eq!(first, Some(1));
+    }
+
+    fn seven() -> i32 {
+        7
+    }
+
+    #[test]
+    fn macro_fragments() {
+        macro_rules! set {
+            ($name:ident) => {
+                let $name = 1;
+            };
+        }
+
+        macro_rules! forward {
+            ($target:expr => { $($t:tt)* }) => {
+                using!($target => { $($t)* })
+            };
+        }
+
+        macro_rules! fragments {
+            ($target:expr, $e:expr, $s:stmt, $p:pat, $ty:ty, $name:ident) => {
+                using!($target => {
+                    $s;
+                    let $p = $e;
+                    let b: $ty = $e;
+                    $e;
+                    .$name(b);
+                    .$name($crate::tests::seven());
+                    $crate::tests::seven();
+                    set!(c);
+                    .$name(c);
+                    if $e > 0 {
+                        .$name($e);
+                    }
+                })
+            };
+        }
+
+        let vec = forward!(Vec::new() => { .push(1); .push(2); });
+        assert_eq!(vec, [1, 2]);
+
+        let vec = fragments!(Vec::new(), seven(), let _x = 1, _, i32, push);
+        assert_eq!(vec, [7, 7, 1, 7]);
+    }
+
+    #[test]
+    fn statements() {
+        let vec = using!(Vec::new() => {
+            fn four() -> i32 {
+                4
+            }
+            static FIVE: i32 = 5;
+            .push(four());
+            .push(FIVE);
+            seven();
+            ::std::vec![1].pop();
+            thread_local! { static SIX: i32 = 6; }
+            .push(SIX.with(|six| *six));
+        });
+        assert_eq!(vec, [4, 5, 6]);
+    }
+
+    #[test]
+    fn block_expr() {
+        let sum: i32 = using!(Vec::new() => {
+            .push(1);
+            {
+                .push(2);
+                .push(3);
+            }
+            .push(4);
+            {
+                .push(5);
+                .iter().sum()
+            }
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn if_expr() {
+        for i in 0..3 {
+            let res = using!(Vec::new() => {
+                if let 0 = i {
+                    .push(0);
+                } else if i == 1 {
+                    .push(1);
+                } else {
+                    .push(2);
+                }
+                .pop().unwrap()
+            });
+            assert_eq!(res, i);
+        }
+    }
+
+    #[test]
+    fn match_expr() {
+        for i in 0..9 {
+            let res = using!(vec @ Vec::new() => {
+                match i {
+                    0 => .push(0),
+                    1 => vec.push(1),
+                    2 => { .push(2) }
+                    3 => { .push(3) },
+                    4 if true => .push(4),
+                    5 if true => vec.push(5),
+                    6 if true => { .push(6) }
+                    7 if true => { .push(7) },
+                    _ => { .push(8) }
+                }
+                .pop().unwrap()
+            });
+            assert_eq!(res, i);
+        }
+    }
+
+    #[test]
+    fn loop_expr() {
+        let sum: i32 = using!(Vec::new() => {
+            let mut i = 1;
+            loop {
+                if i > 5 {
+                    break;
+                }
+                .push(i);
+                i += 1;
+            }
+            .iter().sum()
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn while_loop() {
+        let sum: i32 = using!(Vec::new() => {
+            let mut i = 1;
+            while i <= 5 {
+                .push(i);
+                i += 1;
+            }
+            .iter().sum()
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn while_let() {
+        let sum: i32 = using!(Vec::new() => {
+            let mut i = 1;
+            while let Some(_) = (i <= 5).then_some(i) {
+                .push(i);
+                i += 1;
+            }
+            .iter().sum()
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn while_let_target() {
+        let mut popped = Vec::new();
+        let vec = using!(vec![ 1, 2, 3 ] => {
+            while let Some(value) = .pop() {
+                popped.push(value);
+                if value == 2 {
+                    .push(0);
+                    .push(0);
+                }
+            }
+            .push(4);
+        });
+        assert_eq!(popped, [ 3, 2, 0, 0, 1 ]);
+        assert_eq!(vec, [ 4 ]);
+    }
+
+    #[test]
+    fn async_using() {
+        use futures::executor::block_on;
+        use futures::stream::{self, StreamExt};
+
+        let future = async_using!(stream::iter([ 1, 2, 3 ]) => {
+            let first = .next().await;
+            let mut rest = Vec::new();
+            while let Some(value) = .next().await {
+                rest.push(value);
+            }
+            (first, rest)
+        });
+        assert_eq!(block_on(future), (Some(1), vec![ 2, 3 ]));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn for_await() {
+        use futures::executor::block_on;
+        use futures::stream::{self, StreamExt};
+
+        let future = async_using!(Vec::new() => {
+            for await (i, batch) in stream::iter([ vec![ 1, 2 ], vec![], vec![ 3 ] ]).enumerate() {
+                if i == 1 {
+                    continue;
+                }
+                .extend(batch);
+            }
+            for await value in stream::iter(10..) {
+                if value > 11 {
+                    break;
+                }
+                .push(value);
+            }
+        });
+        assert_eq!(block_on(future), [ 1, 2, 3, 10, 11 ]);
+    }
+
+    #[test]
+    fn for_loop() {
+        let sum: i32 = using!(Vec::new() => {
+            for i in 1..=5 {
+                .push(i);
+            }
+            .iter().sum()
+        });
+        assert_eq!(sum, 15);
+    }
+
+    #[test]
+    fn if_in_for() {
+        let sum: i32 = using!(Vec::new() => {
+            for i in 1..=10 {
+                if i % 2 == 0 {
+                    .push(i);
+                }
+            }
+            .iter().sum()
+        });
+        assert_eq!(sum, 30);
+    }
+
+    #[test]
+    fn let_exp() {
+        let sum: i32 = using!(Vec::new() => {
+            .push(1);
+            .push(2);
+            .push(3);
+            let sum = .iter().sum();
+            .push(sum);
+            let res = { .pop().unwrap() };
+            2 * res
+        });
+        assert_eq!(sum, 12);
+    }
+
+    #[test]
+    fn let_complex() {
+        let res = using!(Vec::new() => {
+            .push(2);
+            .push(3);
+            .push(5);
+            let a = loop { let x = .last().unwrap(); break *x };
+            let b = if a < 10 { .first().is_some() } else { .is_empty() };
+            let c = match b { true => .len(), false => 0 };
+            (a, b, c)
+        });
+        assert_eq!(res, (5, true, 3));
+    }
+
+    #[test]
+    fn nested_using() {
+        let sum: i32 = using!(Vec::new() => {
+            .push(1);
+            .push(2);
+            .push(3);
+            .push(4);
+            .push(5);
+            .push(using!(Vec::new() => {
+                .push(2);
+                .push(3);
+                .iter().product()
+            }));
+            .iter().sum()
+        });
+        assert_eq!(sum, 21);
+    }
+
+    #[test]
+    fn shadow_check() {
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        let x = using!(x @ Vec::new() => {
+            let Point { x: first, y } = Point { x: 1, y: 2 };
+            .push(first);
+            .push(y);
+            let inner = using!(y @ 3 => {
+                let (z, _) = (y + 1, y);
+                z
+            });
+            .push(inner);
+            for Point { x: first, .. } in [Point { x: 5, y: 0 }] {
+                .push(first);
+            }
+            if let Some(Point { x: first, y: _ }) = Some(Point { x: 6, y: 0 }) {
+                .push(first);
+            }
+            match (7, 0) {
+                (first, 0) if first > 0 => { .push(first); }
+                _ => { }
+            }
+            // the default name of a nested invocation hides the check of the enclosing one
+            let inner = using!(Vec::new() => {
+                for x in [8] {
+                    .push(x);
+                }
+            });
+            .extend(inner);
+        });
+        assert_eq!(x, [1, 2, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn ref_mut_place() {
+        struct Config {
+            retries: Vec<i32>,
+        }
+        let mut config = Config { retries: Vec::new() };
+        using!(ref mut config.retries => {
+            .push(1);
+            .push(2);
+        });
+        let len = using!(ref mut retries @ config.retries => {
+            .push(3);
+            retries.push(4);
+            .len()
+        });
+        assert_eq!(len, 4);
+        assert_eq!(config.retries, [ 1, 2, 3, 4 ]);
+    }
+
+    #[test]
+    fn move_stmt() {
+        struct Empty;
+        struct Named(&'static str);
+
+        impl Empty {
+            fn name(self, name: &'static str) -> Named {
+                Named(name)
+            }
+        }
+
+        impl Named {
+            fn suffix(self, suffix: &'static str) -> String {
+                format!("{}{}", self.0, suffix)
+            }
+        }
+
+        let name = using!(Empty => {
+            move .name("a");
+            move .suffix("b");
+            .push('c');
+        });
+        assert_eq!(name, "abc");
+
+        let len = using!(s @ String::new() => {
+            .push('a');
+            move .repeat(3);
+            s.len()
+        });
+        assert_eq!(len, 3);
+
+        let name = using!(Empty => {
+            move .name("x")
+                .suffix("y")
+        });
+        assert_eq!(name, "xy");
+
+        let (name, records): (_, Vec<_>) = using_record!(Empty => {
+            move .name("a");
+        });
+        assert_eq!(name.0, "a");
+        assert_eq!(records, [ "target.name(\"a\")" ]);
+    }
+
+    #[test]
+    fn drop_stmt() {
+        use core::cell::Cell;
+
+        struct Guard<'a> {
+            dropped: &'a Cell<bool>,
+            values: Vec<i32>,
+        }
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.dropped.set(true);
+            }
+        }
+
+        let dropped = Cell::new(false);
+        let (sum, was_dropped) = using!(Guard { dropped: &dropped, values: Vec::new() } => {
+            .values.push(1);
+            let sum: i32 = .values.iter().sum();
+            drop;
+            (sum, dropped.get())
+        });
+        assert_eq!((sum, was_dropped), (1, true));
+
+        let dropped = Cell::new(false);
+        let () = using!(Guard { dropped: &dropped, values: Vec::new() } => {
+            .values.push(1);
+            drop;
+        });
+        assert!(dropped.get());
+    }
+
+    #[test]
+    #[deny(unused_must_use)]
+    fn discard_stmt() {
+        #[derive(Default)]
+        struct Counter {
+            count: i32,
+        }
+
+        impl Counter {
+            #[must_use]
+            fn inc(&mut self) -> &mut Self {
+                self.count += 1;
+                self
+            }
+
+            fn check(&self) -> Result<(), i32> {
+                Err(self.count)
+            }
+        }
+
+        let counter = using!(Counter::default() => {
+            _ .inc();
+            _ .inc().inc();
+            if true {
+                _ .check();
+            }
+        });
+        assert_eq!(counter.count, 3);
+
+        let counter = cascade! {
+            Counter::default();
+            _ ..inc();
+            _ .check();
+        };
+        assert_eq!(counter.count, 1);
+    }
+
+    #[test]
+    fn free_fn_call() {
+        mod helpers {
+            pub fn defaults(values: &mut Vec<i32>) {
+                values.push(0);
+            }
+        }
+
+        fn add(values: &mut Vec<i32>, value: i32) -> &mut Vec<i32> {
+            values.push(value);
+            values
+        }
+
+        fn len(values: &mut [i32]) -> Result<usize, ()> {
+            Ok(values.len())
+        }
+
+        let values = (|| -> Result<_, ()> {
+            Ok(using!(Vec::new() => {
+                .>helpers::defaults();
+                .>add(1).push(2);
+                let n = .>len()?;
+                if n == 3 {
+                    .>add(3);
+                }
+                match n {
+                    3 => .>add(4),
+                    _ => .>add(5),
+                };
+            }))
+        })();
+        assert_eq!(values, Ok(vec![ 0, 1, 2, 3, 4 ]));
+
+        struct Pair {
+            left: Vec<i32>,
+            right: Vec<i32>,
+        }
+
+        let mut pair = Pair { left: vec![ 1 ], right: vec![ 2 ] };
+        using!(ref mut pair => {
+            .left.>add(3);
+            .right.>add(4).>add(5);
+        });
+        assert_eq!((pair.left, pair.right), (vec![ 1, 3 ], vec![ 2, 4, 5 ]));
+
+        let values = cascade! {
+            Vec::new();
+            ..>add(1);
+            ..>helpers::defaults();
+        };
+        assert_eq!(values, [ 1, 0 ]);
+    }
+
+    #[test]
+    fn optional_chaining() {
+        #[derive(Default)]
+        struct Logger {
+            level: u8,
+            targets: Vec<&'static str>,
+        }
+
+        impl Logger {
+            fn set_level(&mut self, level: u8) -> &mut Self {
+                self.level = level;
+                self
+            }
+        }
+
+        #[derive(Default)]
+        struct Server {
+            logger: Option<Logger>,
+            backup: Option<Box<Server>>,
+        }
+
+        let server = using!(Server::default() => {
+            .logger?.set_level(1);
+            .logger = Some(Logger::default());
+            .logger?.set_level(2).targets.push("stderr");
+            .logger?.level += 1;
+            .backup = Some(Box::default());
+            .backup?.logger?.set_level(4);
+            if true {
+                .backup?.logger = Some(Logger::default());
+                .backup?.logger?.set_level(5);
+            }
+        });
+        let logger = server.logger.unwrap();
+        assert_eq!((logger.level, logger.targets), (3, vec![ "stderr" ]));
+        assert_eq!(server.backup.unwrap().logger.unwrap().level, 5);
+    }
+
+    #[test]
+    fn checkpoint_rollback() {
+        #[derive(Clone, Debug, Default, PartialEq)]
+        struct Config {
+            host: &'static str,
+            port: u16,
+        }
+
+        impl Config {
+            fn port(&mut self, port: u16) -> Result<&mut Self, &'static str> {
+                if port == 0 {
+                    return Err("invalid port");
+                }
+                self.port = port;
+                Ok(self)
+            }
+        }
+
+        let config = using!(Config::default() => {
+            .host = "a";
+            checkpoint;
+            .host = "b";
+            if true {
+                .port = 1;
+                checkpoint;
+                .port = 2;
+                rollback;
+            }
+            let port = .port;
+            assert_eq!(port, 1);
+            rollback;
+            .port = 3;
+        });
+        assert_eq!(config, Config { host: "a", port: 3 });
+
+        fn configure(config: &mut Config, port: u16) -> Result<(), &'static str> {
+            using_in!(config => {
+                checkpoint auto;
+                .host = "b";
+                .port(port)?.host = "c";
+            });
+            Ok(())
+        }
+
+        let mut config = Config { host: "a", port: 1 };
+        assert_eq!(configure(&mut config, 0), Err("invalid port"));
+        assert_eq!(config, Config { host: "a", port: 1 });
+        assert_eq!(configure(&mut config, 2), Ok(()));
+        assert_eq!(config, Config { host: "c", port: 2 });
+
+        fn recorded(port: u16) -> Result<(Config, Vec<&'static str>), &'static str> {
+            Ok(using_record!(Config::default() => {
+                checkpoint auto;
+                .host = "b";
+                .port(port)?;
+                rollback;
+            }))
+        }
+
+        assert_eq!(recorded(0), Err("invalid port"));
+        let (config, records) = recorded(2).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], "target.host = \"b\"");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_ext() {
+        use crate::std_ext::ThreadBuilderExt;
+
+        let builder = using!(std::thread::Builder::new() => {
+            .set_name("a");
+            if true {
+                .set_name(String::from("b"));
+            }
+        });
+        let name = builder.spawn(|| std::thread::current().name().unwrap().to_string()).unwrap();
+        assert_eq!(name.join().unwrap(), "b");
+    }
+
+    #[test]
+    fn using_default() {
+        let vec = using_default!(Vec<i32> => {
+            .push(1);
+            .push(2);
+        });
+        assert_eq!(vec, [ 1, 2 ]);
+
+        let len = using_default!(s @ String => {
+            .push_str("abc");
+            s.len()
+        });
+        assert_eq!(len, 3);
+    }
+
+    #[test]
+    fn using_new() {
+        let set = using_new!(std::collections::BTreeSet<i32> => {
+            .insert(2);
+            .insert(1);
+        });
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), [ 1, 2 ]);
+
+        let first = using_new!(vec @ Vec<&str> => {
+            .push("a");
+            vec[0]
+        });
+        assert_eq!(first, "a");
+    }
+
+    #[test]
+    fn using_in() {
+        fn configure(vec: &mut Vec<i32>) -> usize {
+            using_in!(vec => {
+                .push(3);
+                .push(4);
+                .len()
+            })
+        }
+
+        let mut vec = vec![1, 2];
+        using_in!(&mut vec => {
+            .push(1);
+            .retain(|x| *x != 1);
+        });
+        assert_eq!(configure(&mut vec), 3);
+        assert_eq!(vec, [ 2, 3, 4 ]);
+    }
+
+    #[test]
+    fn trait_object_targets() {
+        use core::fmt::Write;
+
+        fn split(iter: &mut dyn Iterator<Item = i32>) -> (Vec<i32>, Option<i32>) {
+            using_in!(iter => {
+                let first = .take(2).collect();
+                .nth(1);
+                let next = .next();
+                (first, next)
+            })
+        }
+
+        let mut iter = 0..6;
+        assert_eq!(split(&mut iter), (vec![ 0, 1 ], Some(4)));
+        assert_eq!(iter.next(), Some(5));
+
+        let mut iter = 0..3;
+        let mut dyn_iter: &mut dyn Iterator<Item = i32> = &mut iter;
+        let (first, last) = using!(ref mut dyn_iter => {
+            let first = .by_ref().next();
+            let last = .last();
+            (first, last)
+        });
+        assert_eq!((first, last), (Some(0), Some(2)));
+
+        let mut s = String::new();
+        let write = using_lazy!(dyn Write => {
+            .write_str("a").unwrap();
+            .write_char('b').unwrap();
+        });
+        write(&mut s);
+        write(&mut s);
+        assert_eq!(s, "abab");
+
+        let mut first = String::new();
+        let mut second = String::new();
+        using_all!(&mut first as &mut dyn Write, &mut second as &mut dyn Write => {
+            .write_str("c").unwrap();
+        });
+        assert_eq!((first.as_str(), second.as_str()), ("c", "c"));
+    }
+
+    #[test]
+    fn using_clone() {
+        let base = vec![1, 2, 3];
+        let modified = using_clone!(&base => {
+            .push(4);
+            .retain(|x| x % 2 == 0);
+        });
+        let len = using_clone!(vec @ base => {
+            .push(5);
+            vec.len()
+        });
+        assert_eq!(base, [ 1, 2, 3 ]);
+        assert_eq!(modified, [ 2, 4 ]);
+        assert_eq!(len, 4);
+    }
+
+    #[test]
+    fn using_lock() {
+        use std::sync::{Mutex, RwLock};
+
+        let mutex = Mutex::new(Vec::new());
+        using_lock!(mutex => {
+            .push(1);
+            .push(2);
+        });
+        let sum: i32 = using_lock!(vec @ &mutex => {
+            .push(3);
+            vec.iter().sum()
+        });
+        assert_eq!(sum, 6);
+
+        let rwlock = RwLock::new(Vec::new());
+        using_lock!(write rwlock => {
+            .push(1);
+        });
+        let sum: i32 = using_lock!(vec @ write rwlock => {
+            .push(2);
+            vec.iter().sum()
+        });
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn using_cell() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let cell = RefCell::new(Vec::new());
+        using_cell!(cell => {
+            .push(1);
+        });
+        let shared = Rc::new(cell);
+        let sum: i32 = using_cell!(vec @ shared => {
+            .push(2);
+            vec.iter().sum()
+        });
+        assert_eq!(sum, 3);
+
+        let other = Rc::clone(&shared);
+        let sum: i32 = using_cell!(per_statement shared => {
+            .push(3);
+            other.borrow_mut().push(4);
+            .iter().sum()
+        });
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn using_tls() {
+        use std::cell::{Cell, RefCell};
+
+        thread_local! {
+            static VALUES: RefCell<Vec<i32>> = const { RefCell::new(Vec::new()) };
+            static COUNTER: Cell<u32> = const { Cell::new(0) };
+        }
+
+        using_tls!(VALUES => {
+            .push(1);
+            .push(2);
+        });
+        let len = using_tls!(values @ VALUES => {
+            .push(3);
+            values.len()
+        });
+        assert_eq!(len, 3);
+        assert_eq!(VALUES.with_borrow(Clone::clone), [ 1, 2, 3 ]);
+
+        using_tls!(ref COUNTER => {
+            .set(1);
+        });
+        let count = using_tls!(ref counter @ COUNTER => {
+            .set(counter.get() + 1);
+            .get()
+        });
+        assert_eq!(count, 2);
+
+        let len = std::thread::spawn(|| using_tls!(VALUES => { .len() })).join().unwrap();
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn using_global() {
+        use std::sync::{LazyLock, Mutex, OnceLock, RwLock};
+
+        static VALUES: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+        static LAZY: LazyLock<RwLock<Vec<i32>>> = LazyLock::new(|| RwLock::new(vec![ 1 ]));
+        static ONCE: OnceLock<Mutex<Vec<i32>>> = OnceLock::new();
+
+        using_global!(VALUES => {
+            .push(1);
+        });
+        let len = using_global!(values @ VALUES => {
+            .push(2);
+            values.len()
+        });
+        assert_eq!(len, 2);
+
+        using_global!(write LAZY => {
+            .push(2);
+        });
+        assert_eq!(*LAZY.read().unwrap(), [ 1, 2 ]);
+
+        for value in 1..=2 {
+            using_global!(ONCE = vec![ 0 ] => {
+                .push(value);
+            });
+        }
+        let values = using_global!(values @ ONCE = vec![ 0 ] => {
+            values.clone()
+        });
+        assert_eq!(values, [ 0, 1, 2 ]);
+    }
+
+    #[test]
+    fn using_ptr() {
+        struct Unique<T>(Option<T>);
+
+        impl<T> crate::TargetMut for Unique<T> {
+            type Target = T;
+
+            fn try_target_mut(&mut self) -> Option<&mut T> {
+                self.0.as_mut()
+            }
+        }
+
+        let ptr = using_ptr!(Unique(Some(Vec::new())) => {
+            .push(1);
+            .push(2);
+        });
+        assert_eq!(ptr.0.unwrap(), [ 1, 2 ]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn using_ptr_alloc() {
+        use std::rc::Rc;
+        use std::sync::Arc;
+
+        let boxed = using_ptr!(Box::new(Vec::new()) => {
+            .push(1);
+        });
+        let rc = using_ptr!(Rc::new(*boxed) => {
+            .push(2);
+        });
+        let sum: i32 = using_ptr!(vec @ Arc::new(Rc::into_inner(rc).unwrap()) => {
+            .push(3);
+            vec.iter().sum()
+        });
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    #[should_panic]
+    fn using_ptr_shared() {
+        use std::rc::Rc;
+
+        let rc = Rc::new(Vec::new());
+        let _other = Rc::clone(&rc);
+        using_ptr!(rc => {
+            .push(1);
+        });
+    }
+
+    #[test]
+    fn apply() {
+        use crate::Apply;
+
+        let vec = Vec::new().apply(|vec| {
+            vec.push(1);
+            vec.push(2);
+        });
+        assert_eq!(vec, [ 1, 2 ]);
+
+        let mut vec = vec;
+        let len = vec.apply_ref(|vec| vec.push(3)).apply_ref(|vec| vec.retain(|&x| x != 1)).len();
+        assert_eq!((len, vec), (2, vec![ 2, 3 ]));
+
+        let slice: &mut [i32] = &mut [ 3, 1, 2 ];
+        slice.apply_ref(|slice| slice.sort()).reverse();
+        assert_eq!(slice, [ 3, 2, 1 ]);
+
+        let strings: Vec<_> = [ (1, None), (2, Some("b")) ].into_iter().map(|(i, suffix)| {
+            String::new()
+                .apply_if(i > 1, |s| s.push('a'))
+                .apply_if_some(suffix, String::push_str)
+        }).collect();
+        assert_eq!(strings, [ "", "ab" ]);
+
+        let rows = [ ("a", 1), ("b", 2) ];
+        let map = std::collections::BTreeMap::new().apply_all(rows, |map, (key, value)| {
+            map.insert(key, value);
+        });
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), rows);
+    }
+
+    #[test]
+    fn try_apply() {
+        use crate::TryApply;
+
+        fn push(vec: &mut Vec<i32>, x: i32) -> Result<(), &'static str> {
+            if x < 0 { Err("negative") } else { vec.push(x); Ok(()) }
+        }
+
+        let vec = Vec::new().try_apply(|vec| {
+            push(vec, 1)?;
+            push(vec, 2)
+        });
+        assert_eq!(vec, Ok(vec![ 1, 2 ]));
+        let vec = Vec::new().try_apply(|vec| push(vec, -1));
+        assert_eq!(vec, Err("negative"));
+
+        let mut vec = vec![ 1 ];
+        let len = vec.try_apply_ref(|vec| push(vec, 2)).map(|vec| vec.len());
+        assert_eq!(len, Ok(2));
+        assert_eq!(vec.try_apply_ref(|vec| push(vec, -1)).map(|vec| vec.len()), Err("negative"));
+        assert_eq!(vec, [ 1, 2 ]);
+    }
+
+    #[test]
+    fn configure() {
+        use crate::Configure;
+
+        fn configured(configure: impl Configure<Vec<i32>>) -> Vec<i32> {
+            using!(vec @ vec![ 0 ] => {
+                configure.configure(&mut vec);
+            })
+        }
+
+        let items = vec![ 1, 2 ];
+        assert_eq!(configured(move |vec: &mut Vec<i32>| vec.extend(items)), [ 0, 1, 2 ]);
+        assert_eq!(configured(Vec::clear), [ ] as [i32; 0]);
+        assert_eq!(configured(using_lazy!(Vec<i32> => { .push(3); })), [ 0, 3 ]);
+    }
+
+    #[test]
+    fn dyn_configure() {
+        use crate::DynConfigure;
+
+        fn double(vec: &mut Vec<i32>) {
+            let doubled: Vec<_> = vec.iter().map(|x| x * 2).collect();
+            *vec = doubled;
+        }
+
+        let offset = 1;
+        let steps: Vec<Box<dyn DynConfigure<Vec<i32>> + Send + Sync>> = vec![
+            Box::new(move |vec: &mut Vec<i32>| vec.push(offset)),
+            Box::new(double),
+        ];
+        let vec = using!(vec @ vec![ 1 ] => {
+            for step in &steps {
+                step.configure(&mut vec);
+            }
+            steps[0].configure(&mut vec);
+        });
+        assert_eq!(vec, [ 2, 2, 1 ]);
+
+        let step: &dyn DynConfigure<[i32]> = &|slice: &mut [i32]| slice.reverse();
+        let mut array = [ 1, 2, 3 ];
+        step.configure(&mut array);
+        assert_eq!(array, [ 3, 2, 1 ]);
+    }
+
+    #[test]
+    fn with_default() {
+        use crate::WithDefault;
+
+        #[derive(Default)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let point = Point::with(|point| {
+            point.x = 1;
+            point.y = point.x + 1;
+        });
+        assert_eq!((point.x, point.y), (1, 2));
+        assert_eq!(Vec::with(|vec| vec.push(1)), [ 1 ]);
+    }
+
+    #[test]
+    fn apply_option() {
+        use crate::ApplyOption;
+
+        let mut opt: Option<Vec<i32>> = None;
+        opt.get_or_insert_using(|vec| vec.push(1));
+        let len = opt.get_or_insert_using(|vec| vec.push(2)).len();
+        assert_eq!((len, opt), (2, Some(vec![ 1, 2 ])));
+
+        assert_eq!(Some(vec![ 1 ]).map_using(|vec| vec.push(2)), Some(vec![ 1, 2 ]));
+        assert_eq!(None::<Vec<i32>>.map_using(|vec| vec.push(2)), None);
+    }
+
+    #[test]
+    fn apply_result() {
+        use crate::ApplyResult;
+
+        fn push(vec: &mut Vec<i32>, x: i32) -> Result<(), &'static str> {
+            if x < 0 { Err("negative") } else { vec.push(x); Ok(()) }
+        }
+
+        let vec = Ok(vec![ 1 ]).map_using(|vec| vec.push(2)).and_then_using(|vec| push(vec, 3));
+        assert_eq!(vec, Ok(vec![ 1, 2, 3 ]));
+        assert_eq!(Ok(vec![ 1 ]).and_then_using(|vec| push(vec, -1)), Err("negative"));
+
+        let mut calls = 0;
+        let vec = Err("missing").map_using(|vec: &mut Vec<i32>| { calls += 1; vec.push(1) });
+        let vec = vec.and_then_using(|vec| { calls += 1; push(vec, 1) });
+        assert_eq!((vec, calls), (Err("missing"), 0));
+    }
+
+    #[test]
+    fn tap() {
+        use crate::{Apply, Tap};
+
+        let mut seen = Vec::new();
+        let vec = Vec::new()
+            .apply(|vec| vec.push(1))
+            .tap(|vec| seen.push(vec.clone()))
+            .apply(|vec| vec.push(2));
+        assert_eq!(vec, [ 1, 2 ]);
+        assert_eq!(seen, [ [ 1 ] ]);
+        #[cfg(feature = "std")]
+        assert_eq!(vec.tap_dbg(), [ 1, 2 ]);
+    }
+
+    #[test]
+    fn using_cow() {
+        use std::borrow::Cow;
+
+        let base = [ 1, 2, 3 ];
+        let cow = using_cow!(vec @ Cow::Borrowed(&base[..]) => {
+            if vec.len() > 3 {
+                mut .push(4);
+            }
+        });
+        assert!(matches!(cow, Cow::Borrowed(_)));
+
+        let cow = using_cow!(Cow::Borrowed(&base[..]) => {
+            mut .push(4);
+            let len = .len();
+            mut .push(len as i32 + 1);
+            let last = mut .pop();
+            assert_eq!(last, Some(5));
+        });
+        assert!(matches!(cow, Cow::Owned(vec) if vec == [ 1, 2, 3, 4 ]));
+
+        let cow = using_cow!(Cow::Borrowed(&base[..]) => {
+            let len = .len();
+            if len > 3 {
+                mut .push(4);
+            }
+            let first = .first().copied();
+            assert_eq!((len, first), (3, Some(1)));
+        });
+        assert!(matches!(cow, Cow::Borrowed(_)));
+        let len = using_cow!(Cow::Borrowed("ab") => {
+            .len()
+        });
+        assert_eq!(len, 2);
+        let len = using_cow!(Cow::Borrowed("ab") => {
+            mut .push('c');
+            mut .len()
+        });
+        assert_eq!(len, 3);
+    }
+
+    #[test]
+    fn using_some() {
+        let vec = using_some!(Some(Vec::new()) => {
+            .push(1);
+            .push(2);
+        });
+        assert_eq!(vec, Some(vec![1, 2]));
+
+        let none: Option<Vec<i32>> = None;
+        let vec = using_some!(vec @ none => {
+            vec.push(1);
+        });
+        assert_eq!(vec, None);
+    }
+
+    #[test]
+    fn using_ok() {
+        let ok: Result<Vec<i32>, &str> = Ok(Vec::new());
+        let len = using_ok!(ok => {
+            .push(1);
+            .len()
+        });
+        assert_eq!(len, Ok(1));
+
+        let err: Result<Vec<i32>, &str> = Err("error");
+        let vec = using_ok!(vec @ err => {
+            vec.push(1);
+        });
+        assert_eq!(vec, Err("error"));
+    }
+
+    #[test]
+    fn try_exp() {
+        fn sum(values: &[&str]) -> Result<i32, std::num::ParseIntError> {
+            using!(Vec::new() => {
+                for value in values {
+                    .push(value.parse::<i32>()?);
+                }
+                let sum = .iter().sum::<i32>().to_string().parse::<i32>()?;
+                Ok(sum)
+            })
+        }
+
+        assert_eq!(sum(&[ "1", "2", "3" ]), Ok(6));
+        assert!(sum(&[ "1", "x" ]).is_err());
+    }
+
+    #[test]
+    fn using_each() {
+        let vecs: Vec<Vec<i32>> = using_each!([ vec![1], vec![2] ] => {
+            .push(0);
+        });
+        assert_eq!(vecs, [ vec![1, 0], vec![2, 0] ]);
+
+        fn parse_all(values: &[&str]) -> Result<Vec<i32>, std::num::ParseIntError> {
+            using_each!(value in values.iter().filter(|value| !value.is_empty()) => {
+                let number = .parse::<i32>()?;
+                Ok(number * 2)
+            })
+        }
+
+        assert_eq!(parse_all(&[ "1", "", "3" ]), Ok(vec![2, 6]));
+        assert!(parse_all(&[ "1", "x" ]).is_err());
+
+        let boxed: Box<[String]> = using_each!(1..=2 => {
+            .to_string()
+        });
+        assert_eq!(&*boxed, [ "1", "2" ]);
+        let none: Option<Vec<i32>> = using_each!([ Some(1), None ] => {
+            .map(|x| x + 1)
+        });
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn using_with() {
+        let (vec, sum) = using_with!(vec @ Vec::new() => {
+            .push(1);
+            .push(2);
+            vec.iter().sum::<i32>()
+        });
+        assert_eq!(vec, [ 1, 2 ]);
+        assert_eq!(sum, 3);
+
+        let (vec, ()) = using_with!(Vec::new() => {
+            .push(1);
+        });
+        assert_eq!(vec, [ 1 ]);
+    }
+
+    #[test]
+    fn using_do() {
+        let mut sum = 0;
+        using_do!(vec @ Vec::new() => {
+            .push(1);
+            .push(2);
+            sum += vec.iter().sum::<i32>();
+        });
+        using_do!(Vec::new() => {
+            .push(3);
+            .pop()
+        });
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn using_keep() {
+        let vec = using_keep!(vec @ Vec::new() => {
+            .push(1);
+            .push(2);
+            vec.len()
+        });
+        assert_eq!(vec, [ 1, 2 ]);
+    }
+
+    #[test]
+    fn using_lazy() {
+        fn configure_all(vecs: &mut [Vec<i32>], configure: impl Fn(&mut Vec<i32>) -> usize) -> usize {
+            vecs.iter_mut().map(configure).sum()
+        }
+
+        let mut vecs = [ vec![1], vec![] ];
+        let len = configure_all(&mut vecs, using_lazy!(vec @ Vec<i32> => {
+            .push(2);
+            vec.len()
+        }));
+        assert_eq!(len, 3);
+        assert_eq!(vecs, [ vec![1, 2], vec![2] ]);
+    }
+
+    #[test]
+    fn using_take() {
+        let mut state = Some(vec![1]);
+        let len = using_take!(state => {
+            .push(2);
+            .len()
+        });
+        assert_eq!(len, Some(2));
+        assert_eq!(state, Some(vec![1, 2]));
+
+        let sum = using_take!(vec @ state => {
+            .push(3);
+            vec.iter().sum::<i32>()
+        } else {
+            -1
+        });
+        assert_eq!(sum, 6);
+
+        let vec = using_take!(move state => {
+            .push(4);
+        } else {
+            Vec::new()
+        });
+        assert_eq!(vec, [ 1, 2, 3, 4 ]);
+        assert_eq!(state, None);
+
+        assert_eq!(using_take!(state => { .len() }), None);
+        assert_eq!(using_take!(state => { .len() } else { 0 }), 0);
+        assert_eq!(using_take!(move vec @ state => { vec.len() }), None);
+
+        // the target is put back on early exits and panics
+        fn first(state: &mut Option<Vec<i32>>) -> Option<i32> {
+            using_take!(*state => {
+                let first = .first()?;
+                return Some(*first);
+            });
+            None
+        }
+        let mut state = Some(vec![]);
+        assert_eq!(first(&mut state), None);
+        assert_eq!(state, Some(vec![]));
+        state = Some(vec![5]);
+        assert_eq!(first(&mut state), Some(5));
+        assert_eq!(state, Some(vec![5]));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            using_take!(state => {
+                .push(6);
+                panic!("failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(state, Some(vec![5, 6]));
+    }
+
+    #[test]
+    fn using_all() {
+        struct Fleet {
+            primary: Vec<i32>,
+            replica: std::collections::VecDeque<i32>,
+        }
+
+        let mut fleet = Fleet { primary: Vec::new(), replica: Default::default() };
+        using_all!(fleet.primary, fleet.replica => {
+            .extend([ 1 ]);
+        });
+        let (primary, replica) = using_all!(node @ fleet.primary, fleet.replica => {
+            .extend([ 2, 3 ]);
+            node.iter().sum::<i32>()
+        });
+        assert_eq!((primary, replica), (6, 6));
+        assert_eq!(fleet.primary, [ 1, 2, 3 ]);
+        assert_eq!(fleet.replica, [ 1, 2, 3 ]);
+    }
+
+    #[test]
+    fn using_pipe() {
+        let words = using_pipe!("b a c" => {
+            .split(' ');
+            @.collect::<Vec<_>>();
+            {
+                let mut words = @;
+                words.sort();
+                words
+            };
+            [ @, vec![ "d" ] ].concat();
+        });
+        assert_eq!(words, [ "a", "b", "c", "d" ]);
+
+        let len = using_pipe!(vec![ 1, 2, 3 ] => {
+            .into_iter().filter(|x| x % 2 == 1).collect::<Vec<_>>();
+            (@.len(), @)
+        });
+        assert_eq!(len, (2, vec![ 1, 3 ]));
+
+        let doubled = using_pipe!(3 => {
+            match @ { n @ 1..=5 => n * 2, _ => 0 };
+        });
+        assert_eq!(doubled, 6);
+
+        let vec = using_pipe!(2 => {
+            using!(v @ Vec::with_capacity(@) => {
+                .push(v.capacity() >= 2);
+                .push(using_pipe!(1 => { @ + 1 }) == 2);
+            });
+        });
+        assert_eq!(vec, [ true, true ]);
+    }
+
+    #[test]
+    fn using_fn() {
+        use std::cell::RefCell;
+
+        using_fn! {
+            fn range(Vec::new(), n: i32) -> Vec<i32> {
+                for i in 0..n {
+                    .push(i);
+                }
+            }
+
+            #[inline]
+            fn append(cell @ target, target: &RefCell<Vec<i32>>, value: i32,) {
+                .borrow_mut().push(value);
+            }
+
+            fn named<B: Into<String>>(String::new(), name: B) -> String {
+                .push_str(&name.into());
+            }
+
+            fn nested<'a, T: Clone + Into<Option<i32>>>(Vec::new(), values: &'a [T]) -> Vec<Option<i32>> {
+                .extend(values.iter().cloned().map(Into::into));
+            }
+
+            fn bounded<T>(Vec::new(), value: T, count: usize) -> Vec<T>
+            where
+                T: Clone,
+            {
+                .resize(count, value);
+            }
+
+            fn patterns(Vec::new(), mut base: Vec<i32>, (a, b): (i32, i32), _: ()) -> Vec<i32> {
+                base.push(a);
+                .append(&mut base);
+                .push(b);
+            }
+
+            fn clear<T>(target, target: &mut Vec<T>) where T: Copy {
+                .clear();
+            }
+        }
+
+        assert_eq!(range(3), [ 0, 1, 2 ]);
+        let cell = RefCell::new(range(1));
+        append(&cell, 5);
+        assert_eq!(cell.into_inner(), [ 0, 5 ]);
+        assert_eq!(named("a"), "a");
+        assert_eq!(nested(&[ 1, 2 ]), [ Some(1), Some(2) ]);
+        assert_eq!(bounded('a', 2), [ 'a', 'a' ]);
+        assert_eq!(patterns(vec![ 0 ], (1, 2), ()), [ 0, 1, 2 ]);
+        let mut vec = vec![ 1 ];
+        clear(&mut vec);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "macros")]
+    fn block() {
+        #[derive(Default)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let point = crate::block!(Point::default() => {
+            .x = 2;
+            .y = .x * 3 + Point { x: 1, y: 0 }.x;
+            if .x > 1 {
+                .x += 1;
+            } else {
+                .x -= 1;
+            }
+            .y -= if .x > 2 { 1 } else { 2 }.min(.x);
+        });
+        assert_eq!((point.x, point.y), (3, 6));
+
+        let sum = crate::block!(vec @ Vec::new() => {
+            #[allow(unused_parens)]
+            for i in (1..=3) {
+                .push(i);
+            }
+            .retain(|x| x % 2 == 1);
+            let inner = using!(Vec::new() => {
+                .push(10);
+            });
+            .extend(inner);
+            match vec[..] {
+                [first, ..] if first > 0 => .iter().sum::<i32>(),
+                _ => 0,
+            }
+        });
+        assert_eq!(sum, 14);
+
+        fn first_len(values: &[&str]) -> Option<usize> {
+            crate::block!(values.iter() => {
+                let first = .next()?;
+                if first.is_empty() {
+                    return .next().map(|value| value.len());
+                }
+                Some(first.len())
+            })
+        }
+        assert_eq!(first_len(&[ "ab", "c" ]), Some(2));
+        assert_eq!(first_len(&[ "", "c" ]), Some(1));
+        assert_eq!(first_len(&[]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "macros")]
+    fn using_expand() {
+        const CODE: &str = crate::using_expand!(Vec::new() => {
+            .push(.len());
+            let inner = using!(Vec::new() => {
+                .push(1);
+            });
+            inner
+        });
+        // nested invocations are not expanded
+        assert_eq!(CODE, "\
+{
+    #[allow(unused_mut)]
+    let mut target = Vec::new();
+    target.push(target.len());
+    let inner = using!(Vec::new() => { .push(1); });
+    inner
+}");
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_setters() {
+        #[derive(Default, crate::Setters)]
+        struct Pair<T: Default> where T: Clone {
+            first: T,
+            second: Option<T>,
+        }
+
+        let pair = using!(Pair::default() => {
+            .first(1);
+            .second(Some(2));
+        });
+        assert_eq!((pair.first, pair.second), (1, Some(2)));
+
+        #[derive(Default, crate::Setters)]
+        struct Person {
+            #[setter(into)]
+            name: String,
+            age: u8,
+        }
+
+        let person = using!(Person::default() => {
+            .name("a");
+            .age(1);
+        });
+        assert_eq!((person.name.as_str(), person.age), ("a", 1));
+
+        #[derive(Default, crate::Setters)]
+        #[setter(into)]
+        struct Limits {
+            size: u64,
+            name: Option<String>,
+        }
+
+        let limits = using!(Limits::default() => {
+            .size(1u8);
+            .name(String::from("a"));
+        });
+        assert_eq!((limits.size, limits.name.as_deref()), (1, Some("a")));
+
+        #[derive(Default, crate::Setters)]
+        struct Query {
+            #[setter(strip_option, into)]
+            filter: Option<String>,
+            #[setter(strip_option)]
+            limit: Option<usize>,
+        }
+
+        let query = using!(Query::default() => {
+            .filter("a");
+            .limit(10);
+            .limit_none();
+        });
+        assert_eq!((query.filter.as_deref(), query.limit), (Some("a"), None));
+
+        #[derive(Default, crate::Setters)]
+        struct Request {
+            #[setter(each = "header", into)]
+            headers: std::collections::BTreeMap<String, String>,
+            #[setter(each = "arg")]
+            args: Vec<i32>,
+        }
+
+        let request = using!(Request::default() => {
+            .header("a", "1");
+            for i in 0..3 {
+                .arg(i);
+            }
+            .header("b", "2");
+        });
+        assert_eq!(request.headers.len(), 2);
+        assert_eq!(request.headers["b"], "2");
+        assert_eq!(request.args, [ 0, 1, 2 ]);
+
+        #[derive(Default, crate::Setters)]
+        #[setter(with)]
+        struct Point {
+            x: i32,
+            #[setter(into)]
+            y: i64,
+        }
+
+        let point = Point::default().with_x(1).with_y(2);
+        assert_eq!((point.x, point.y), (1, 2));
+        let point = using!(point => {
+            .x(3);
+        });
+        assert_eq!((point.x, point.y), (3, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder() {
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Message<'a, T> where T: Clone {
+            topic: &'a str,
+            payload: Vec<T>,
+        }
+
+        let message = using_build!(Message::builder() => {
+            .topic("a");
+            .payload(vec![ 1, 2 ]);
+        });
+        assert_eq!(message, Ok(Message { topic: "a", payload: vec![ 1, 2 ] }));
+
+        let message = using!(Message::<i32>::builder() => {
+            .topic("a");
+            .build()
+        });
+        assert_eq!(message, Err(MessageBuilderError::MissingPayload));
+        assert_eq!(MessageBuilderError::MissingTopic.to_string(), "field `topic` is not set");
+
+        let message = Message::try_from(using!(Message::builder() => {
+            .topic("a");
+            .payload(vec![ 1 ]);
+        }));
+        assert_eq!(message, Ok(Message { topic: "a", payload: vec![ 1 ] }));
+        let message: Result<Message<i32>, _> = Message::builder().try_into();
+        assert_eq!(message, Err(MessageBuilderError::MissingTopic));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Person {
+            #[setter(into)]
+            name: String,
+            age: u8,
+        }
+
+        let person = using_build!(Person::builder() => {
+            .name("a");
+            .age(1);
+        });
+        assert_eq!(person, Ok(Person { name: "a".to_string(), age: 1 }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Query {
+            table: &'static str,
+            #[setter(strip_option)]
+            limit: Option<usize>,
+        }
+
+        let query = using_build!(Query::builder() => {
+            .table("a");
+            .limit(10);
+        });
+        assert_eq!(query, Ok(Query { table: "a", limit: Some(10) }));
+        let query = using_build!(Query::builder() => {
+            .table("a");
+            .limit(10);
+            .limit_none();
+        });
+        assert_eq!(query, Ok(Query { table: "a", limit: None }));
+        assert_eq!(Query::builder().build(), Err(QueryBuilderError::MissingTable));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Command {
+            program: &'static str,
+            #[setter(each = "arg")]
+            args: Vec<&'static str>,
+        }
+
+        let command = using_build!(Command::builder() => {
+            .program("a");
+            .arg("b");
+            .arg("c");
+        });
+        assert_eq!(command, Ok(Command { program: "a", args: vec![ "b", "c" ] }));
+        let command = using_build!(Command::builder() => {
+            .program("a");
+        });
+        assert_eq!(command, Ok(Command { program: "a", args: vec![] }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Config {
+            #[builder(default = "localhost")]
+            host: &'static str,
+            #[builder(default = 8000 + 80)]
+            port: u16,
+            #[builder(default)]
+            verbose: bool,
+            name: &'static str,
+        }
+
+        let config = using_build!(Config::builder() => {
+            .name("a");
+            .port(443);
+        });
+        assert_eq!(config, Ok(Config { host: "localhost", port: 443, verbose: false, name: "a" }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Options {
+            #[builder(default = 1)]
+            level: u8,
+        }
+
+        let options = Options::from(using!(Options::builder() => {
+            .level(2);
+        }));
+        assert_eq!(options, Options { level: 2 });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[setter(with)]
+        struct Window {
+            title: &'static str,
+            #[builder(default, validator = check_port)]
+            port: u16,
+        }
+
+        let window = Window::builder().with_title("a").with_port(1).and_then(WindowBuilder::build);
+        assert_eq!(window, Ok(Window { title: "a", port: 1 }));
+        assert_eq!(Window::builder().with_port(0).err(), Some(WindowBuilderError::InvalidPort("port must not be 0")));
+        assert_eq!(Config::builder().build(), Err(ConfigBuilderError::MissingName));
+
+        fn check_port(port: &u16) -> Result<(), &'static str> {
+            if *port == 0 { Err("port must not be 0") } else { Ok(()) }
+        }
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Server {
+            #[builder(validator = check_port)]
+            port: u16,
+            #[builder(default, validator = check_port)]
+            admin_port: u16,
+        }
+
+        let server = (|| using!(Server::builder() => {
+            .port(80)?;
+            .admin_port(8080)?;
+            .build()
+        }))();
+        assert_eq!(server, Ok(Server { port: 80, admin_port: 8080 }));
+        let mut builder = Server::builder();
+        assert_eq!(builder.port(0), Err(ServerBuilderError::InvalidPort("port must not be 0")));
+        assert_eq!(builder.build(), Err(ServerBuilderError::MissingPort));
+        assert_eq!(ServerBuilderError::InvalidPort("a").to_string(), "invalid value for field `port`: a");
+
+        mod user {
+            #[derive(Debug, PartialEq, crate::UsingBuilder)]
+            pub struct User {
+                #[builder(rename = "with_name", vis = "pub(crate)")]
+                pub name: &'static str,
+                #[builder(rename = "with_nick")]
+                #[setter(strip_option)]
+                pub nick: Option<&'static str>,
+                #[builder(vis = "", default)]
+                pub admin: bool,
+            }
+        }
+
+        let user = using_build!(user::User::builder() => {
+            .with_name("a");
+            .with_nick("b");
+            .with_nick_none();
+        });
+        assert_eq!(user, Ok(user::User { name: "a", nick: None, admin: false }));
+
+        fn check_rating(rating: &u8) -> Result<(), &'static str> {
+            if *rating > 5 { Err("too high") } else { Ok(()) }
+        }
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Article {
+            /// The title of the article.
+            title: &'static str,
+            #[deprecated = "use `tags`"]
+            #[setter(strip_option, with)]
+            tag: Option<&'static str>,
+            #[deprecated = "use `reviews`"]
+            #[builder(default, validator = check_rating)]
+            #[setter(with)]
+            rating: u8,
+        }
+
+        #[allow(deprecated)]
+        {
+            let article = using_build!(Article::builder() => {
+                .title("a");
+                .tag("b");
+            });
+            assert_eq!(article, Ok(Article { title: "a", tag: Some("b"), rating: 0 }));
+            let article = Article::builder().with_tag("b").build();
+            assert_eq!(article, Err(ArticleBuilderError::MissingTitle));
+            assert!(matches!(Article::builder().with_rating(6), Err(ArticleBuilderError::InvalidRating(_))));
+        }
+
+        #[cfg(feature = "macros")]
+        {
+            #[derive(Debug, PartialEq, crate::UsingBuilder)]
+            #[builder(getters)]
+            struct Endpoint {
+                host: &'static str,
+                #[builder(rename = "set_port")]
+                port: u16,
+                #[setter(strip_option)]
+                path: Option<&'static str>,
+                #[builder(default = 3)]
+                retries: u8,
+            }
+
+            let endpoint = crate::block!(Endpoint::builder() => {
+                .host("a");
+                assert_eq!(.get_port(), None);
+                if .get_port().is_none() {
+                    .set_port(80);
+                }
+                if let Some(&port) = .get_port() {
+                    .set_port(port + 1);
+                }
+                assert_eq!(.get_path(), None);
+                assert_eq!(.get_retries(), None);
+                .build()
+            });
+            assert_eq!(endpoint, Ok(Endpoint { host: "a", port: 81, path: None, retries: 3 }));
+        }
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Address {
+            host: &'static str,
+            #[builder(default = 80)]
+            port: u16,
+            #[builder(skip = format!("{}:{}", host, port))]
+            address: String,
+            #[builder(skip)]
+            connections: Vec<u32>,
+        }
+
+        let address = using_build!(Address::builder() => {
+            .host("a");
+        });
+        assert_eq!(address, Ok(Address { host: "a", port: 80, address: "a:80".into(), connections: vec![] }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        enum Cache<T> {
+            Memory {
+                capacity: usize,
+                #[builder(skip = Vec::with_capacity(capacity))]
+                entries: Vec<T>,
+            },
+        }
+
+        let cache = Cache::<u8>::memory_builder().capacity(4).build();
+        assert!(matches!(cache, Cache::Memory { capacity: 4, entries } if entries.capacity() >= 4));
+
+        #[derive(crate::UsingBuilder)]
+        struct Empty { }
+        assert!(Empty::builder().build().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_generics() {
+        use core::fmt::Debug;
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Message<'a, T: Debug + ?Sized, const N: usize, U = u8> where U: Copy, T: 'a {
+            body: &'a T,
+            tags: [U; N],
+            #[builder(default)]
+            #[setter(each = "header")]
+            headers: Vec<(&'a str, U)>,
+        }
+
+        let message = using_build!(Message::builder() => {
+            .body("a");
+            .tags([ 1, 2 ]);
+            .header(("b", 3));
+        });
+        assert_eq!(message, Ok(Message { body: "a", tags: [ 1u8, 2 ], headers: vec![ ("b", 3) ] }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Typed<'a, T: Debug + ?Sized, const N: usize, U = u8> where U: Copy, T: 'a {
+            body: &'a T,
+            tags: [U; N],
+            #[builder(default)]
+            #[setter(each = "header")]
+            headers: Vec<(&'a str, U)>,
+        }
+
+        let message = using!(Typed::builder() => {
+            move .body("a");
+            .header(("b", 3));
+            move .tags([ 1, 2 ]);
+            .build()
+        });
+        assert_eq!(message, Typed { body: "a", tags: [ 1u8, 2 ], headers: vec![ ("b", 3) ] });
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_enum() {
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        enum Shape<T> {
+            Circle {
+                radius: T,
+            },
+            Rect {
+                width: T,
+                #[builder(default)]
+                height: Option<T>,
+            },
+            HTTPEmpty,
+        }
+
+        let shape = using_build!(Shape::rect_builder() => {
+            .width(1);
+        });
+        assert_eq!(shape, Ok(Shape::Rect { width: 1, height: None }));
+        assert_eq!(Shape::<i32>::circle_builder().build(), Err(ShapeCircleBuilderError::MissingRadius));
+        assert_eq!(Shape::<i32>::http_empty_builder().build(), Ok(Shape::HTTPEmpty));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        enum Event {
+            Click {
+                x: i32,
+                y: i32,
+            },
+        }
+
+        let event = using!(Event::click_builder() => {
+            move .x(1);
+            move .y(2);
+            .build()
+        });
+        assert_eq!(event, Event::Click { x: 1, y: 2 });
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_typestate() {
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Message<'a, T> where T: Clone {
+            topic: &'a str,
+            payload: Vec<T>,
+        }
+
+        let message = using!(Message::builder() => {
+            move .payload(vec![ 1 ]);
+            move .topic("a");
+            move .payload(vec![ 1, 2 ]);
+            .build()
+        });
+        assert_eq!(message, Message { topic: "a", payload: vec![ 1, 2 ] });
+
+        let builder = Message::builder().topic("b").payload(Vec::<i32>::new());
+        assert_eq!(crate::UsingBuild::build(builder), Message { topic: "b", payload: vec![] });
+        let message: Message<i32> = Message::builder().topic("c").payload(vec![ 1 ]).into();
+        assert_eq!(message, Message { topic: "c", payload: vec![ 1 ] });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        #[setter(into)]
+        struct Person {
+            name: String,
+            age: u64,
+        }
+
+        let person = using!(Person::builder() => {
+            move .name("a");
+            move .age(1u8);
+            .build()
+        });
+        assert_eq!(person, Person { name: "a".to_string(), age: 1 });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Query {
+            #[setter(strip_option)]
+            limit: Option<usize>,
+            table: &'static str,
+            #[setter(strip_option)]
+            offset: Option<usize>,
+        }
+
+        let query = using!(Query::builder() => {
+            .limit(10);
+            move .table("a");
+            if true {
+                .offset(5);
+            }
+            .build()
+        });
+        assert_eq!(query, Query { limit: Some(10), table: "a", offset: Some(5) });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Command {
+            program: &'static str,
+            #[setter(each = "arg")]
+            args: Vec<&'static str>,
+        }
+
+        let command = using!(Command::builder() => {
+            .arg("b");
+            move .program("a");
+            .arg("c");
+            .build()
+        });
+        assert_eq!(command, Command { program: "a", args: vec![ "b", "c" ] });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Config {
+            #[builder(default = "localhost")]
+            host: &'static str,
+            port: u16,
+        }
+
+        let config = using!(Config::builder() => {
+            move .port(443);
+            .build()
+        });
+        assert_eq!(config, Config { host: "localhost", port: 443 });
+
+        // the states are not named after the fields, which could collide
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Pair {
+            _x: i32,
+            x: i32,
+        }
+
+        let pair = using!(Pair::builder() => {
+            move ._x(1);
+            move .x(2);
+            .build()
+        });
+        assert_eq!(pair, Pair { _x: 1, x: 2 });
+
+        fn check_port(port: &u16) -> Result<(), &'static str> {
+            if *port == 0 { Err("port must not be 0") } else { Ok(()) }
+        }
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Server {
+            #[builder(validator = check_port)]
+            port: u16,
+        }
+
+        let server = (|| Ok::<_, ServerBuilderError>(using!(Server::builder() => {
+            move .port(80)?;
+            .build()
+        })))();
+        assert_eq!(server, Ok(Server { port: 80 }));
+        assert_eq!(Server::builder().port(0).err(), Some(ServerBuilderError::InvalidPort("port must not be 0")));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct User {
+            #[builder(rename = "with_name")]
+            name: &'static str,
+        }
+
+        let user = using!(User::builder() => {
+            move .with_name("a");
+            .build()
+        });
+        assert_eq!(user, User { name: "a" });
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate, getters)]
+        struct Point {
+            x: i32,
+            #[builder(default)]
+            y: i32,
+        }
+
+        let builder = Point::builder();
+        assert_eq!(builder.get_x(), None);
+        let mut builder = builder.x(1);
+        if builder.get_x() == Some(&1) {
+            builder.y(2);
+        }
+        assert_eq!(builder.get_y(), Some(&2));
+        assert_eq!(builder.build(), Point { x: 1, y: 2 });
+
+        #[derive(crate::UsingBuilder)]
+        #[builder(typestate)]
+        struct Empty { }
+        let Empty { } = Empty::builder().build();
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_const() {
+        const fn check_port(port: &u16) -> Result<(), &'static str> {
+            if *port == 0 { Err("zero") } else { Ok(()) }
+        }
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(const)]
+        struct Server {
+            name: &'static str,
+            #[builder(default = 80, validator = check_port)]
+            port: u16,
+            #[setter(strip_option)]
+            root: Option<&'static str>,
+        }
+
+        const SERVERS: [Server; 2] = [
+            match {
+                let mut builder = Server::builder();
+                builder.name("a");
+                builder.root("/srv");
+                builder
+            }.build() {
+                Ok(server) => server,
+                Err(_) => panic!(),
+            },
+            match {
+                let mut builder = ServerBuilder::new();
+                builder.name("b");
+                if builder.port(8080).is_err() {
+                    panic!();
+                }
+                builder
+            }.build() {
+                Ok(server) => server,
+                Err(_) => panic!(),
+            },
+        ];
+        assert_eq!(SERVERS, [
+            Server { name: "a", port: 80, root: Some("/srv") },
+            Server { name: "b", port: 8080, root: None },
+        ]);
+        assert_eq!(Server::builder().port(0), Err(ServerBuilderError::InvalidPort("zero")));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Options {
+            #[builder(const, default)]
+            verbose: bool,
+            #[builder(default)]
+            #[setter(into)]
+            name: String,
+        }
+
+        let mut builder = Options::builder();
+        const fn enable(builder: &mut OptionsBuilder) {
+            builder.verbose(true);
+        }
+        enable(&mut builder);
+        builder.name("a");
+        assert_eq!(builder.build(), Ok(Options { verbose: true, name: "a".into() }));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(typestate, const)]
+        struct Point {
+            x: i32,
+            #[builder(default = 0)]
+            y: i32,
+        }
+
+        const ORIGIN: PointBuilder = {
+            let mut builder = PointBuilder::new();
+            builder.y(1);
+            builder
+        };
+        assert_eq!(ORIGIN.x(2).build(), Point { x: 2, y: 1 });
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_merge() {
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Config {
+            name: String,
+            value: u32,
+            #[builder(default = 4)]
+            threads: u8,
+            #[setter(strip_option)]
+            proxy: Option<String>,
+            #[setter(each = "tag")]
+            tags: Vec<&'static str>,
+        }
 
+        let mut config = using!(Config::builder() => {
+            .name("a".to_string());
+            .value(1);
+            .proxy("p".to_string());
+            .tag("b");
+        });
+        config.merge(using!(Config::builder() => {
+            .value(2);
+            .threads(8);
+            .tag("c");
+        }));
+        assert_eq!(config.build(), Ok(Config {
+            name: "a".to_string(),
+            value: 2,
+            threads: 8,
+            proxy: Some("p".to_string()),
+            tags: vec![ "b", "c" ],
+        }));
 
-    ($target:ident $scope:ident empty { let $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_let () { $($rest)* })
-    };
+        let mut config = using!(Config::builder() => {
+            .name("a".to_string());
+            .value(1);
+            .threads(8);
+        });
+        config.merge(using!(Config::builder() => {
+            .value(2);
+        }));
+        assert_eq!(config.build().map(|config| (config.value, config.threads)), Ok((2, 8)));
+        let config = using_build!(Config::builder() => {
+            .name("a".to_string());
+            .value(1);
+            .merge(Config::builder());
+        });
+        assert_eq!(config.map(|config| config.threads), Ok(4));
 
-    ($target:ident $scope:ident in_let
-        ($($pattern:tt)*)
-        { = $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_let_exp ($($pattern)*) (_) () { $($rest)* })
-    };
+        let config = using_build!(Config::builder() => {
+            .threads(8);
+            .merge(Config::builder());
+        });
+        assert_eq!(config, Err(ConfigBuilderError::MissingName));
 
-    ($target:ident $scope:ident in_let
-        ($($pattern:tt)*)
-        { : $ty:ty = $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_let_exp ($($pattern)*) ($ty) () { $($rest)* })
-    };
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(getters)]
+        struct Client {
+            #[builder(default = Some(3))]
+            #[setter(strip_option)]
+            retries: Option<u32>,
+        }
 
-    ($target:ident $scope:ident in_let
-        ($($pattern:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_let ($($pattern)* $t) { $($rest)* })
-    };
+        let client = using_build!(Client::builder() => {
+            .retries(5);
+            .merge(Client::builder());
+        });
+        assert_eq!(client, Ok(Client { retries: Some(5) }));
 
-    ($target:ident $scope:ident in_let_exp
-        ($pattern:pat)
-        ($ty:ty)
-        ($($exp:tt)*)
-        { ; $($rest:tt)* }
-    ) => {
-        {
-            let $pattern: $ty = $crate::using_impl!($target block empty { $($exp)* });
-            $crate::using_impl!($target $scope empty { $($rest)* })
+        let client = using_build!(Client::builder() => {
+            .retries_none();
+            .merge(Client::builder());
+        });
+        assert_eq!(client, Ok(Client { retries: None }));
+
+        let client = using_build!(Client::builder() => {
+            .retries(5);
+            .merge(using!(Client::builder() => { .retries_none(); }));
+        });
+        assert_eq!(client, Ok(Client { retries: None }));
+
+        let client = using!(Client::builder() => {
+            let unset = .get_retries().is_none();
+            .retries(5);
+            let set = .get_retries().copied();
+            let client = .build();
+            (unset, set, client)
+        });
+        assert_eq!(client, (true, Some(5), Ok(Client { retries: Some(5) })));
+        assert_eq!(Client::builder().build(), Ok(Client { retries: Some(3) }));
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_missing_fields() {
+        #[derive(crate::UsingBuilder)]
+        struct Config {
+            r#type: String,
+            #[builder(default)]
+            threads: u8,
+            value: u32,
+            #[setter(strip_option)]
+            proxy: Option<String>,
         }
-    };
 
-    ($target:ident $scope:ident in_let_exp
-        ($pattern:pat)
-        ($ty:ty)
-        ($($exp:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_let_exp ($pattern) ($ty) ($($exp)* $t) { $($rest)* })
-    };
+        let mut config = Config::builder();
+        assert_eq!(config.missing_fields().collect::<Vec<_>>(), [ "type", "value" ]);
+        config.value(1);
+        assert_eq!(config.missing_fields().collect::<Vec<_>>(), [ "type" ]);
+        config.r#type("a".to_string());
+        assert_eq!(config.missing_fields().next(), None);
+        let config = config.build().unwrap();
+        assert_eq!((config.r#type.as_str(), config.threads, config.value, config.proxy), ("a", 0, 1, None));
 
+        #[derive(crate::UsingBuilder)]
+        struct Empty {
+            #[builder(default)]
+            value: u32,
+        }
 
+        let empty = Empty::builder();
+        assert_eq!(empty.missing_fields().next(), None);
+        assert_eq!(empty.build().unwrap().value, 0);
+    }
 
-    ($target:ident $scope:ident empty { if $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_if () () () { $($rest)* })
-    };
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derive_using_builder_set_field() {
+        fn non_zero(value: &u32) -> Result<(), &'static str> {
+            if *value == 0 { Err("must not be zero") } else { Ok(()) }
+        }
 
-    ($target:ident $scope:ident in_if
-        ($($if_curr:tt)*)
-        ()
-        ()
-        { { $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_if_next
-            ()
-            (($($if_curr)*) { $($body)* })
-            ()
-            { $($rest)* }
-        )
-    };
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        struct Config<T> {
+            r#type: T,
+            #[builder(validator = non_zero)]
+            value: u32,
+            #[setter(strip_option)]
+            proxy: Option<String>,
+            #[setter(each = "tag")]
+            tags: Vec<String>,
+            #[builder(vis = "pub(crate)")]
+            hidden: bool,
+        }
 
-    ($target:ident $scope:ident in_if
-        ($($if_curr:tt)*)
-        ($($if_first:tt)*)
-        ($($if_rest:tt)*)
-        { { $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_if_next
-            ()
-            ($($if_first)*)
-            ($($if_rest)* (($($if_curr)*) { $($body)* }))
-            { $($rest)* }
-        )
-    };
+        let mut config = Config::builder();
+        let fields = [
+            ConfigFieldValue::Type("a"),
+            ConfigFieldValue::Value(1),
+            ConfigFieldValue::Proxy("localhost".to_string()),
+            ConfigFieldValue::Tags(vec!["b".to_string()]),
+        ];
+        for value in fields {
+            assert_eq!(config.set_field(value), Ok(()));
+        }
+        assert_eq!(config.set_field(ConfigFieldValue::Value(0)), Err(ConfigBuilderError::InvalidValue("must not be zero")));
+        config.hidden(true);
+        assert_eq!(config.build(), Ok(Config {
+            r#type: "a",
+            value: 1,
+            proxy: Some("localhost".to_string()),
+            tags: vec!["b".to_string()],
+            hidden: true,
+        }));
+    }
 
-    ($target:ident $scope:ident in_if
-        ($($if_curr:tt)*)
-        ($($if_first:tt)*)
-        ($($if_rest:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_if
-            ($($if_curr)* $t)
-            ($($if_first)*)
-            ($($if_rest)*)
-            { $($rest)* }
-        )
-    };
+    #[test]
+    #[cfg(all(feature = "derive", feature = "serde"))]
+    fn derive_using_builder_deserialize() {
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(deserialize)]
+        struct Config<T> {
+            name: String,
+            value: T,
+            #[builder(default = 4)]
+            threads: u8,
+            #[setter(strip_option)]
+            proxy: Option<String>,
+            #[setter(each = "tag")]
+            tags: Vec<String>,
+        }
 
-    ($target:ident $scope:ident in_if_next
-        ()
-        ($($if_first:tt)*)
-        ($($if_rest:tt)*)
-        { else if $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_if
-            ()
-            ($($if_first)*)
-            ($($if_rest)*)
-            { $($rest)* }
-        )
-    };
+        let file: ConfigBuilder<u32> = serde_json::from_str(r#"{ "name": "a", "threads": 8, "tags": [ "b" ] }"#).unwrap();
+        let config = using_build!(file => {
+            .value(1);
+            .tag("c".to_string());
+        });
+        assert_eq!(config, Ok(Config {
+            name: "a".to_string(),
+            value: 1,
+            threads: 8,
+            proxy: None,
+            tags: vec![ "b".to_string(), "c".to_string() ],
+        }));
 
-    ($target:ident $scope:ident in_if_next
-        ()
-        (($($if_first_cond:tt)*) { $($if_first_body:tt)* })
-        ($( (($($if_rest_cond:tt)*) { $($if_rest_body:tt)* }) )*)
-        { else { $($body:tt)* } $($rest:tt)* }
-    ) => {
-        {
-            let _tmp = if $($if_first_cond)* {
-                $crate::using_impl!($target block empty { $($if_first_body)* })
-            } $( else if $($if_rest_cond)* {
-                $crate::using_impl!($target block empty { $($if_rest_body)* })
-            } )* else {
-                $crate::using_impl!($target block empty { $($body)* })
-            };
-            $crate::using_impl!($target $scope maybe_trailing_exp (_tmp) { $($rest)* })
+        let file: ConfigBuilder<u32> = serde_json::from_str(r#"{ "value": 2, "proxy": "p" }"#).unwrap();
+        let config = using_build!(file => { });
+        assert_eq!(config, Err(ConfigBuilderError::MissingName));
+
+        #[derive(Debug, PartialEq, crate::UsingBuilder)]
+        #[builder(deserialize)]
+        enum Shape {
+            Rect { width: u32, height: u32 },
         }
-    };
 
-    ($target:ident $scope:ident in_if_next
-        ()
-        (($($if_first_cond:tt)*) { $($if_first_body:tt)* })
-        ($( (($($if_rest_cond:tt)*) { $($if_rest_body:tt)* }) )*)
-        { $($rest:tt)* }
-    ) => {
-        {
-            if $($if_first_cond)* {
-                $crate::using_impl!($target block empty { $($if_first_body)* })
-            } $( else if $($if_rest_cond)* {
-                $crate::using_impl!($target block empty { $($if_rest_body)* })
-            } )*
-            $crate::using_impl!($target $scope empty { $($rest)* })
+        let file: ShapeRectBuilder = serde_json::from_str(r#"{ "width": 2 }"#).unwrap();
+        let shape = using_build!(file => {
+            .height(1);
+        });
+        assert_eq!(shape, Ok(Shape::Rect { width: 2, height: 1 }));
+    }
+
+    #[test]
+    #[cfg(feature = "macros")]
+    fn attr_adapter() {
+        use crate::attr::adapter;
+
+        struct Fluent<T> {
+            values: Vec<T>,
+            name: &'static str,
         }
-    };
 
+        impl<T> Fluent<T> {
+            fn value(mut self, value: T) -> Self {
+                self.values.push(value);
+                self
+            }
 
+            fn extend<I: IntoIterator<Item = T>>(mut self, values: I) -> Self {
+                self.values.extend(values);
+                self
+            }
 
-    ($target:ident $scope:ident empty { match $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_match () { $($rest)* })
-    };
+            fn name(self, (name, _): (&'static str, bool)) -> Self {
+                Fluent { name, ..self }
+            }
+        }
 
-    ($target:ident $scope:ident in_match
-        ($($match_cond:tt)*)
-        { { $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body ($($match_cond)*) () { { $($body)* } $($rest)* })
-    };
+        #[adapter(
+            /// Adds a value.
+            fn value(value: T);
+            fn extend<I: IntoIterator<Item = T>>(values: I);
+            fn name(arg: (&'static str, bool));
+        )]
+        #[derive(Default)]
+        struct Adapter<T>(Fluent<T>);
 
-    ($target:ident $scope:ident in_match
-        ($($match_cond:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match ($($match_cond)* $t) { $($rest)* })
-    };
+        let fluent = using!(Adapter::new(Fluent { values: vec![], name: "" }) => {
+            .value(1);
+            for i in 2..4 {
+                .value(i);
+            }
+            .extend([ 4 ]);
+            .name(("a", true));
+            let len = .values.len();
+            assert_eq!(len, 4);
+            .into_inner()
+        });
+        assert_eq!((fluent.values, fluent.name), (vec![ 1, 2, 3, 4 ], "a"));
+        assert!(Adapter::<i32>::default().0.is_none());
+    }
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        { { $pattern:pat $( if $guard:expr )? => . $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body_in_exp
-            ($($match_cond)*)
-            ($($match_cases)*)
-            (($pattern) $($guard)*)
-            (.)
-            { { $($body)* } $($rest)* }
-        )
-    };
+    #[test]
+    #[cfg(feature = "macros")]
+    fn attr_cascade_api() {
+        use crate::attr::cascade_api;
 
-    ($target:ident $scope:ident in_match_body_in_exp
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        (($match_pattern:pat) $($match_guard:expr)?)
-        ($($match_exp:tt)*)
-        { { , $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($match_pattern $( if $match_guard )* => { $($match_exp)* }))
-            { { $($body)* } $($rest)* }
-        )
-    };
+        #[derive(Default)]
+        struct Query<T> {
+            table: String,
+            filters: Vec<T>,
+            limit: Option<usize>,
+        }
 
-    ($target:ident $scope:ident in_match_body_in_exp
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        (($match_pattern:pat) $($match_guard:expr)?)
-        ($($match_exp:tt)*)
-        { { } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($match_pattern $( if $match_guard )* => { $($match_exp)* }))
-            { { } $($rest)* }
-        )
-    };
+        #[cascade_api(owned)]
+        impl<T: Clone> Query<T> {
+            fn set_table(&mut self, table: impl Into<String>) {
+                self.table = table.into();
+            }
 
-    ($target:ident $scope:ident in_match_body_in_exp
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        (($match_pattern:pat) $($match_guard:expr)?)
-        ($($match_exp:tt)*)
-        { { $t:tt $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body_in_exp
-            ($($match_cond)*)
-            ($($match_cases)*)
-            (($match_pattern) $($match_guard)*)
-            ($($match_exp)* $t)
-            { { $($body)* } $($rest)* }
-        )
-    };
+            fn set_filters<I>(&mut self, filters: I) where I: IntoIterator<Item = T> {
+                self.filters = filters.into_iter().collect();
+            }
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        { { $pattern:pat $( if $guard:expr )? => { $($exp:tt)* }, $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($pattern $( if $guard )* => { $($exp)* }))
-            { { $($body)* } $($rest)* }
-        )
-    };
+            fn set_limit(&mut self, (limit, enabled): (usize, bool)) {
+                self.limit = enabled.then_some(limit);
+            }
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        { { $pattern:pat $( if $guard:expr )? => { $($exp:tt)* } $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($pattern $( if $guard )* => { $($exp)* }))
-            { { $($body)* } $($rest)* }
-        )
-    };
+            fn len(&self) -> usize {
+                self.filters.len()
+            }
+        }
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        { { $pattern:pat $( if $guard:expr )? => $exp:expr, $($body:tt)* } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($pattern $( if $guard )* => { $exp }))
-            { { $($body)* } $($rest)* }
-        )
-    };
+        let mut query = Query::default();
+        query.table("a").filters([ 1, 2 ]).limit((10, true));
+        assert_eq!((query.table.as_str(), query.len(), query.limit), ("a", 2, Some(10)));
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($($match_cases:tt)*)
-        { { $pattern:pat $( if $guard:expr )? => $exp:expr } $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_match_body
-            ($($match_cond)*)
-            ($($match_cases)* ($pattern $( if $guard )* => { $exp }))
-            { { } $($rest)* }
-        )
-    };
+        let query = Query::default().with_table("b").with_filters([ 3 ]).with_limit((10, false));
+        assert_eq!((query.table.as_str(), query.filters, query.limit), ("b", vec![ 3 ], None));
 
-    ($target:ident $scope:ident in_match_body
-        ($($match_cond:tt)*)
-        ($( ($pattern:pat $( if $guard:expr )? => { $($exp:tt)* }) )*)
-        { { } $($rest:tt)* }
-    ) => {
-        {
-            let _tmp = match $($match_cond)* {
-                $( $pattern $( if $guard )* => { $crate::using_impl!($target block empty { $($exp)* }) }, )*
-            };
-            $crate::using_impl!($target $scope maybe_trailing_exp (_tmp) { $($rest)* })
+        #[derive(Default)]
+        struct Shape {
+            width: u32,
+            kind: &'static str,
         }
-    };
 
+        #[cascade_api(owned)]
+        impl Shape {
+            fn width(&self) -> u32 {
+                self.width
+            }
 
+            fn set_width(&mut self, width: u32) {
+                self.width = width;
+            }
 
-    ($target:ident $scope:ident empty { loop { $($body:tt)* } $($rest:tt)* }) => {
-        {
-            let _tmp = loop {
-                $crate::using_impl!($target block empty { $($body)* })
-            };
-            $crate::using_impl!($target $scope maybe_trailing_exp (_tmp) { $($rest)* })
+            fn set_type(&mut self, kind: &'static str) {
+                self.kind = kind;
+            }
         }
-    };
 
+        let mut shape = Shape::default();
+        shape.set_width(2);
+        shape.r#type("a");
+        assert_eq!((shape.width(), shape.kind), (2, "a"));
 
+        let shape = Shape::default().with_width(3).with_type("b");
+        assert_eq!((shape.width(), shape.kind), (3, "b"));
+    }
 
-    ($target:ident $scope:ident empty { while $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_while () { $($rest)* })
-    };
+    #[test]
+    #[cfg(feature = "macros")]
+    fn attr_using() {
+        use crate::attr::using;
+
+        struct Stack {
+            values: Vec<i32>,
+        }
+
+        impl Stack {
+            #[using(self.values)]
+            fn push_all(&mut self, values: &[i32]) -> usize {
+                if let [first, rest @ ..] = values {
+                    _.push(*first);
+                    rest.iter().for_each(|value| _.push(*value));
+                    assert_eq!(_.last(), values.last());
+                }
+                _.len()
+            }
+        }
+
+        let mut stack = Stack { values: vec![0] };
+        assert_eq!(stack.push_all(&[]), 1);
+        assert_eq!(stack.push_all(&[1, 2]), 3);
+        assert_eq!(stack.values, [ 0, 1, 2 ]);
+    }
+
+    #[test]
+    fn using_build() {
+        struct JoinBuilder {
+            parts: Vec<&'static str>,
+        }
+
+        impl crate::UsingBuild for JoinBuilder {
+            type Output = String;
+
+            fn build(self) -> String {
+                self.parts.join(", ")
+            }
+        }
+
+        let joined = using_build!(builder @ JoinBuilder { parts: Vec::new() } => {
+            .parts.push("Hello");
+            builder.parts.push("World!");
+        });
+        assert_eq!(joined, "Hello, World!");
 
-    ($target:ident $scope:ident in_while
-        ($($while_cond:tt)*)
-        { { $($body:tt)* } $($rest:tt)* }
-    ) => {
+        #[cfg(feature = "derive")]
         {
-            while $($while_cond)* {
-                $crate::using_impl!($target block empty { $($body)* })
+            fn make<B: crate::UsingBuild + Default>(configure: impl FnOnce(&mut B)) -> B::Output {
+                using!(builder @ B::default() => {
+                    configure(&mut builder);
+                    .build()
+                })
             }
-            $crate::using_impl!($target $scope empty { $($rest)* })
-        }
-    };
 
-    ($target:ident $scope:ident in_while
-        ($($while_cond:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_while ($($while_cond)* $t) { $($rest)* })
-    };
+            #[derive(Debug, PartialEq, crate::UsingBuilder)]
+            struct Point {
+                x: i32,
+                #[builder(default)]
+                y: i32,
+            }
 
+            assert_eq!(make(|builder: &mut PointBuilder| builder.x(1)), Ok(Point { x: 1, y: 0 }));
+        }
+    }
 
+    #[test]
+    fn using_validated() {
+        #[derive(Debug)]
+        struct NonEmpty {
+            values: Vec<i32>,
+        }
 
-    ($target:ident $scope:ident empty { for $for_pattern:pat in $($rest:tt)* }) => {
-        $crate::using_impl!($target $scope in_for ($for_pattern) () { $($rest)* })
-    };
+        impl crate::Validate for NonEmpty {
+            type Error = &'static str;
 
-    ($target:ident $scope:ident in_for
-        ($for_pattern:pat)
-        ($($for_exp:tt)*)
-        { { $($body:tt)* } $($rest:tt)* }
-    ) => {
-        {
-            for $for_pattern in $($for_exp)* {
-                $crate::using_impl!($target block empty { $($body)* })
+            fn validate(&self) -> Result<(), &'static str> {
+                if self.values.is_empty() { Err("empty") } else { Ok(()) }
             }
-            $crate::using_impl!($target $scope empty { $($rest)* })
         }
-    };
-
-    ($target:ident $scope:ident in_for
-        ($for_pattern:pat)
-        ($($for_exp:tt)*)
-        { $t:tt $($rest:tt)* }
-    ) => {
-        $crate::using_impl!($target $scope in_for ($for_pattern) ($($for_exp)* $t) { $($rest)* })
-    };
 
+        let ok = using_validated!(NonEmpty { values: Vec::new() } => {
+            .values.push(1);
+        });
+        assert_eq!(ok.unwrap().values, [ 1 ]);
 
+        let err = using_validated!(list @ NonEmpty { values: vec![1] } => {
+            list.values.clear();
+        });
+        assert_eq!(err.unwrap_err(), "empty");
+    }
 
-    ($target:ident $scope:ident empty { $st:stmt; $($rest:tt)* }) => {
-        {
-            $st
-            $crate::using_impl!($target $scope empty { $($rest)* })
+    #[test]
+    fn using_errors() {
+        #[derive(Debug, Default)]
+        struct Numbers {
+            values: Vec<i32>,
         }
-    };
 
-    ($target:ident $scope:ident empty { $exp:expr }) => {
-        $exp
-    };
-}
+        impl Numbers {
+            fn push(&mut self, value: &str) -> Result<(), String> {
+                self.values.push(value.parse().map_err(|_| value.to_string())?);
+                Ok(())
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use crate::using;
+        let res: Result<Numbers, Vec<String>> = using_errors!(Numbers::default() => {
+            for value in [ "1", "a", "2", "b" ] {
+                .push(value)?;
+            }
+        });
+        assert_eq!(res.unwrap_err(), [ "a", "b" ]);
 
-    #[test]
-    fn simple() {
-        let vec = using!(Vec::new() => {
-            .push(1);
-            .push(2);
-            .push(3);
-            .push(4);
-            .push(5);
+        let res: Result<Numbers, Vec<String>> = using_errors!(numbers @ Numbers::default() => {
+            .push("1")?;
+            if numbers.values.len() == 1 {
+                .push("2")?;
+            }
         });
-        assert_eq!(vec.iter().sum::<i32>(), 15);
+        assert_eq!(res.unwrap().values, [ 1, 2 ]);
     }
 
     #[test]
-    fn simple_expr() {
-        let sum = using!(Vec::new() => {
+    #[cfg(feature = "std")]
+    fn using_catch() {
+        let vec = using_catch!(Vec::new() => {
             .push(1);
             .push(2);
-            .push(3);
-            .push(4);
-            .push(5);
-            .iter().sum::<i32>()
         });
-        assert_eq!(sum, 15);
-    }
+        assert_eq!(vec.unwrap(), [ 1, 2 ]);
 
-    #[test]
-    fn block_expr() {
-        let sum: i32 = using!(Vec::new() => {
+        let res = using_catch!(vec @ Vec::<i32>::new() => {
             .push(1);
-            {
-                .push(2);
-                .push(3);
-            }
-            .push(4);
-            {
-                .push(5);
-                .iter().sum()
+            if vec.len() == 1 {
+                panic!("plugin failed");
             }
+            vec.len()
         });
-        assert_eq!(sum, 15);
+        assert_eq!(res.unwrap_err().downcast_ref::<&str>(), Some(&"plugin failed"));
+
+        let mut vec = vec![1];
+        let res = using_catch!(ref mut vec => {
+            .push(2);
+            .remove(5);
+        });
+        assert!(res.is_err());
+        assert_eq!(vec, [ 1, 2 ]);
     }
 
     #[test]
-    fn if_expr() {
-        for i in 0..3 {
-            let res = using!(Vec::new() => {
-                if let 0 = i {
-                    .push(0);
-                } else if i == 1 {
-                    .push(1);
-                } else {
-                    .push(2);
-                }
-                .pop().unwrap()
-            });
-            assert_eq!(res, i);
+    fn assign() {
+        struct Point {
+            x: i32,
+            y: i32,
+            history: Vec<Point>,
         }
+
+        let point = using!(Point { x: 0, y: 0, history: Vec::new() } => {
+            .x = 1;
+            .y -= 2;
+            .history.push(Point { x: 3, y: 4, history: Vec::new() });
+            .history.last_mut().unwrap().x *= 2;
+        });
+        assert_eq!((point.x, point.y), (1, -2));
+        assert_eq!(point.history[0].x, 6);
     }
 
     #[test]
-    fn match_expr() {
-        for i in 0..9 {
-            let res = using!(vec @ Vec::new() => {
-                match i {
-                    0 => .push(0),
-                    1 => vec.push(1),
-                    2 => { .push(2) }
-                    3 => { .push(3) },
-                    4 if true => .push(4),
-                    5 if true => vec.push(5),
-                    6 if true => { .push(6) }
-                    7 if true => { .push(7) },
-                    _ => { .push(8) }
-                }
-                .pop().unwrap()
-            });
-            assert_eq!(res, i);
-        }
+    fn cascade() {
+        let vec = cascade! {
+            Vec::new();
+            ..push(1);
+            .push(2);
+            if true {
+                ..push(3);
+            }
+        };
+        assert_eq!(vec, [ 1, 2, 3 ]);
+
+        let sum: i32 = cascade! {
+            let v: Vec<i32> = Vec::new();
+            ..push(1);
+            | v.push(2);
+            ..iter().sum()
+        };
+        assert_eq!(sum, 3);
     }
 
     #[test]
-    fn loop_expr() {
-        let sum: i32 = using!(Vec::new() => {
-            let mut i = 1;
-            loop {
-                if i > 5 {
-                    break;
-                }
-                .push(i);
-                i += 1;
-            }
-            .iter().sum()
-        });
-        assert_eq!(sum, 15);
+    fn with() {
+        let vec = with! {
+            vec = Vec::new();
+            vec.push(1);
+            .push(2);
+        };
+        assert_eq!(vec, [ 1, 2 ]);
+
+        let sum = with! {
+            vec: Vec<i32> = Vec::new();
+            vec.push(1);
+            vec.push(2);
+            vec.iter().sum::<i32>()
+        };
+        assert_eq!(sum, 3);
     }
 
     #[test]
-    fn while_loop() {
-        let sum: i32 = using!(Vec::new() => {
-            let mut i = 1;
-            while i <= 5 {
-                .push(i);
-                i += 1;
+    fn using_dbg() {
+        #[derive(Debug)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let point = using_dbg!(Point { x: 0, y: 0 } => {
+            .x = 1;
+            for _ in 0..2 {
+                .y += 1;
             }
-            .iter().sum()
         });
-        assert_eq!(sum, 15);
+        assert_eq!((point.x, point.y), (1, 2));
+
+        let sum: i32 = using_dbg!(vec @ Vec::new() => {
+            .push(1);
+            .push(2);
+            vec.iter().sum()
+        });
+        assert_eq!(sum, 3);
     }
 
     #[test]
-    fn while_let() {
-        let sum: i32 = using!(Vec::new() => {
-            let mut i = 1;
-            while let Some(_) = (i <= 5).then_some(i) {
-                .push(i);
-                i += 1;
+    #[cfg(feature = "log")]
+    fn trace_log() {
+        use std::sync::Mutex;
+
+        struct Logger(Mutex<Vec<String>>);
+
+        impl log::Log for Logger {
+            fn enabled(&self, metadata: &log::Metadata) -> bool {
+                metadata.target() == "using"
+            }
+
+            fn log(&self, record: &log::Record) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) { }
+        }
+
+        static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let vec = using!(vec @ Vec::new() => {
+            .push(1);
+            if vec.len() > 1 {
+                .push(2);
             }
-            .iter().sum()
         });
-        assert_eq!(sum, 15);
+        assert_eq!(vec, [ 1 ]);
+        let vec = crate::Tap::tap_log(vec, log::Level::Info, "vec");
+        assert_eq!(vec, [ 1 ]);
+        let logged = LOGGER.0.lock().unwrap();
+        assert!(logged.iter().any(|st| st == "vec.push(1)"));
+        assert!(!logged.iter().any(|st| st == "vec.push(2)"));
+        assert!(logged.iter().any(|st| st == "vec: [1]"));
     }
 
     #[test]
-    fn for_loop() {
-        let sum: i32 = using!(Vec::new() => {
-            for i in 1..=5 {
-                .push(i);
+    fn using_record() {
+        let (sum, records): (i32, Vec<&str>) = using_record!(Vec::new() => {
+            for i in 0..3 {
+                match i {
+                    0 => .push(1),
+                    n if n > 1 => { .push(n); }
+                    _ => { }
+                }
             }
             .iter().sum()
         });
-        assert_eq!(sum, 15);
+        assert_eq!(sum, 3);
+        assert_eq!(records, [ "0 =>", "_ =>", "n if n > 1 =>", "target.push(n)" ]);
     }
 
     #[test]
-    fn if_in_for() {
-        let sum: i32 = using!(Vec::new() => {
-            for i in 1..=10 {
-                if i % 2 == 0 {
-                    .push(i);
+    fn assert_using() {
+        let vec = vec![1, 2, 3];
+        assert_using!(v @ vec => {
+            .len() == 3;
+            .first().copied() == Some(1);
+            let first = .first().copied();
+            assert_eq!(first, Some(1));
+            for i in 1..=3 {
+                if i > 1 {
+                    .contains(&i);
                 }
             }
-            .iter().sum()
+            assert_eq!(v.len(), 3);
+            .iter().all(|x| *x > 0)
         });
-        assert_eq!(sum, 30);
     }
 
     #[test]
-    fn let_exp() {
-        let sum: i32 = using!(Vec::new() => {
-            .push(1);
-            .push(2);
-            .push(3);
-            let sum = .iter().sum();
-            .push(sum);
-            let res = { .pop().unwrap() };
-            2 * res
+    #[should_panic(expected = "target.len() > 3")]
+    fn assert_using_fail() {
+        assert_using!(vec![1, 2, 3] => {
+            .len() > 3;
         });
-        assert_eq!(sum, 12);
     }
 
     #[test]
-    fn let_complex() {
-        let res = using!(Vec::new() => {
-            .push(2);
-            .push(3);
-            .push(5);
-            let a = loop { let x = .last().unwrap(); break *x };
-            let b = if a < 10 { .first().is_some() } else { .is_empty() };
-            let c = match b { true => .len(), false => 0 };
-            (a, b, c)
-        });
-        assert_eq!(res, (5, true, 3));
+    fn using_map() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let map: HashMap<_, _> = using_map! {
+            "a" => 1,
+            "b" => 2
+        };
+        assert_eq!(map.len(), 2);
+
+        let map: BTreeMap<_, _> = using_map! {
+            let mut i = 0;
+            while i < 3 {
+                i => i * 2,
+                i += 1;
+            }
+            loop {
+                10 => 20,
+                break;
+            }
+            if i == 0 {
+                0 => 0,
+            } else if i == 3 {
+                -1 => -1,
+            } else {
+                -2 => -2,
+            }
+        };
+        assert_eq!(map, BTreeMap::from([ (-1, -1), (0, 0), (1, 2), (2, 4), (10, 20) ]));
     }
 
     #[test]
-    fn nested_using() {
-        let sum: i32 = using!(Vec::new() => {
-            .push(1);
-            .push(2);
-            .push(3);
-            .push(4);
-            .push(5);
-            .push(using!(Vec::new() => {
-                .push(2);
-                .push(3);
-                .iter().product()
-            }));
-            .iter().sum()
+    fn using_set_vec() {
+        use std::collections::BTreeSet;
+
+        let set: BTreeSet<_> = using_set![ 3, 1, 2, 1 ];
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), [ 1, 2, 3 ]);
+
+        let vec: Vec<i32> = using_vec![];
+        assert!(vec.is_empty());
+
+        let vec: Vec<_> = using_vec![
+            1,
+            for i in 0..2 {
+                { i * 10, }
+                (if i == 0 { 5 } else { 6 }),
+            }
+        ];
+        assert_eq!(vec, [ 1, 0, 5, 10, 6 ]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn using_string() {
+        let name = "World";
+        let greeting = using_string! {
+            "Hello";
+            ',';
+            ' ';
+            += name;
+            writeln!("{}", '!');
+            for i in 0..3 {
+                write!("{}", i);
+            }
+            .push('.');
+        };
+        assert_eq!(greeting, "Hello, World!\n012.");
+
+        let empty = using_string!(s @ {
+            if !s.is_empty() {
+                "unreachable";
+            }
         });
-        assert_eq!(sum, 21);
+        assert!(empty.is_empty());
+    }
+
+    #[no_implicit_prelude]
+    mod no_prelude {
+        use ::std::collections::BTreeMap;
+        use ::std::vec::Vec;
+
+        // shadows the prelude, so the expansion must not refer to it
+        #[allow(dead_code)]
+        struct Some;
+        #[allow(dead_code)]
+        trait Clone { }
+
+        #[derive(::core::default::Default)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[::core::prelude::v1::test]
+        fn declarative() {
+            let vec = using!(vec @ Vec::new() => {
+                .push(1);
+                if vec.len() == 1 {
+                    .push(2);
+                }
+                match vec.len() {
+                    2 => .push(3),
+                    _ => { }
+                }
+                for i in 4..6 {
+                    .push(i);
+                }
+                let len = .len();
+                ::core::assert_eq!(len, 5);
+            });
+            ::core::assert_eq!(vec, [ 1, 2, 3, 4, 5 ]);
+
+            let cloned = using_clone!(&vec => {
+                .retain(|x| x % 2 == 0);
+            });
+            ::core::assert_eq!(cloned, [ 2, 4 ]);
+
+            let point = using_default!(Point => {
+                .x = 1;
+                .y += 2;
+            });
+            ::core::assert_eq!((point.x, point.y), (1, 2));
+
+            let mut option = ::core::option::Option::Some(vec);
+            let len = using_take!(option => {
+                .push(6);
+                .len()
+            });
+            ::core::assert_eq!(len, ::core::option::Option::Some(6));
+
+            let map: BTreeMap<_, _> = using_map! {
+                "a" => 1,
+            };
+            let set: ::std::collections::BTreeSet<_> = using_set![ 2, 1 ];
+            let vec: Vec<_> = using_vec![ 1, for i in 2..4 { i, } ];
+            ::core::assert_eq!((map.len(), set.len(), vec), (1, 2, ::std::vec![ 1, 2, 3 ]));
+
+            assert_using!(Vec::<i32>::new() => {
+                .is_empty();
+            });
+        }
+
+        #[::core::prelude::v1::test]
+        #[cfg(feature = "macros")]
+        fn procedural() {
+            #[derive(::core::default::Default, crate::Setters, crate::UsingBuilder)]
+            struct Pair {
+                first: i32,
+                #[setter(strip_option)]
+                second: ::core::option::Option<i32>,
+            }
+
+            let pair = crate::block!(<Pair as ::core::default::Default>::default() => {
+                .first(1);
+                .second(2);
+            });
+            ::core::assert_eq!((pair.first, pair.second), (1, ::core::option::Option::Some(2)));
+
+            let pair = using_build!(Pair::builder() => {
+                .first(3);
+            });
+            ::core::assert_eq!(pair.map(|pair| pair.first), ::core::result::Result::Ok(3));
+        }
     }
 }
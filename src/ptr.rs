@@ -0,0 +1,54 @@
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, rc::Rc, sync::Arc};
+
+/// A trait for pointer types that can provide unique mutable access to the value they point to.
+///
+/// This trait is used by [`using_ptr`](crate::using_ptr) to cascade on the pointee instead of the
+/// pointer itself. With the `alloc` feature, it is implemented for `Box<T>`, `Rc<T>` and `Arc<T>`.
+pub trait TargetMut {
+    /// The type of the value the pointer points to.
+    type Target: ?Sized;
+
+    /// Returns a mutable reference to the pointee, or `None` if the pointee cannot be accessed
+    /// mutably, e.g. because it is shared with other pointers.
+    fn try_target_mut(&mut self) -> Option<&mut Self::Target>;
+
+    /// Returns a mutable reference to the pointee.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_target_mut`](TargetMut::try_target_mut) returns `None`.
+    fn target_mut(&mut self) -> &mut Self::Target {
+        match self.try_target_mut() {
+            Some(target) => target,
+            None => panic!("the target of the pointer cannot be accessed mutably"),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> TargetMut for Box<T> {
+    type Target = T;
+
+    fn try_target_mut(&mut self) -> Option<&mut T> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> TargetMut for Rc<T> {
+    type Target = T;
+
+    fn try_target_mut(&mut self) -> Option<&mut T> {
+        Rc::get_mut(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: ?Sized> TargetMut for Arc<T> {
+    type Target = T;
+
+    fn try_target_mut(&mut self) -> Option<&mut T> {
+        Arc::get_mut(self)
+    }
+}
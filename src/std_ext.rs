@@ -0,0 +1,45 @@
+//! Extension traits for types of the standard library that are not cascade-friendly.
+//!
+//! Most builders of the standard library already work well with [`using`](crate::using), e.g.
+//! the methods of `process::Command` and `fs::OpenOptions` take `&mut self`, and the socket
+//! options of `net::TcpStream` and `net::UdpSocket` take `&self` and return an `io::Result`, which
+//! can be used with `?`. This module provides `&mut self` methods for the remaining types, whose
+//! methods consume `self`.
+//!
+//! This module is only available with the `std` feature.
+
+use std::string::String;
+use std::thread::Builder;
+
+/// Extension methods for `std::thread::Builder` taking `&mut self`.
+///
+/// # Examples:
+///
+/// ```
+/// # use using::using;
+/// use using::std_ext::ThreadBuilderExt;
+///
+/// let handle = using!(std::thread::Builder::new() => {
+///     .set_name("worker");
+///     .set_stack_size(64 * 1024);
+///     .spawn(|| std::thread::current().name().map(String::from)).unwrap()
+/// });
+/// assert_eq!(handle.join().unwrap().as_deref(), Some("worker"));
+/// ```
+pub trait ThreadBuilderExt {
+    /// Sets the name of the thread, like `Builder::name`.
+    fn set_name(&mut self, name: impl Into<String>);
+
+    /// Sets the size of the stack of the thread in bytes, like `Builder::stack_size`.
+    fn set_stack_size(&mut self, size: usize);
+}
+
+impl ThreadBuilderExt for Builder {
+    fn set_name(&mut self, name: impl Into<String>) {
+        *self = core::mem::replace(self, Builder::new()).name(name.into());
+    }
+
+    fn set_stack_size(&mut self, size: usize) {
+        *self = core::mem::replace(self, Builder::new()).stack_size(size);
+    }
+}
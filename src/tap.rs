@@ -0,0 +1,46 @@
+#[cfg(any(feature = "std", feature = "log"))]
+use core::fmt::Debug;
+
+/// An extension trait for observing a value in a chain of method calls without changing it.
+///
+/// This trait is implemented for all types and complements [`Apply`](crate::Apply) and
+/// [`using`](crate::using) for debugging long construction chains, e.g.:
+///
+/// ```
+/// use using::{Apply, Tap};
+///
+/// let mut lens = Vec::new();
+/// let vec = Vec::new()
+///     .apply(|vec| vec.push(1))
+///     .tap(|vec| lens.push(vec.len()))
+///     .apply(|vec| vec.push(2));
+/// assert_eq!(vec, [ 1, 2 ]);
+/// assert_eq!(lens, [ 1 ]);
+/// ```
+pub trait Tap {
+    /// Calls `f` with a reference to `self` and returns `self`.
+    fn tap(self, f: impl FnOnce(&Self)) -> Self where Self: Sized {
+        f(&self);
+        self
+    }
+
+    /// Prints `self` to stderr like `dbg!`, including the location of the call, and returns
+    /// `self`. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    fn tap_dbg(self) -> Self where Self: Debug + Sized {
+        let location = core::panic::Location::caller();
+        std::eprintln!("[{}:{}] {:#?}", location.file(), location.line(), &self);
+        self
+    }
+
+    /// Logs `msg` and `self` with the `log` crate at level `level` with the target `"using"`, and
+    /// returns `self`. Requires the `log` feature.
+    #[cfg(feature = "log")]
+    fn tap_log(self, level: log::Level, msg: &str) -> Self where Self: Debug + Sized {
+        log::log!(target: "using", level, "{}: {:?}", msg, &self);
+        self
+    }
+}
+
+impl<T: ?Sized> Tap for T { }
@@ -0,0 +1,31 @@
+//! Marker types for builders that track the state of their fields in their type.
+//!
+//! These types are used by the builders generated by `#[derive(UsingBuilder)]` with
+//! `#[builder(typestate)]`, where each field of the builder has a type parameter that is either
+//! [`Unset`] or [`Set`], so that `build` is only available if all fields are set.
+
+/// The state of a field that is not set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Unset;
+
+/// The state of a field that is set, containing the value of the field.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Set<T>(pub T);
+
+/// The state of a field of type `T`, i.e. [`Unset`] or [`Set<T>`](Set).
+pub trait FieldState<T> {
+    /// Returns the value of the field if it is set.
+    fn get(&self) -> Option<&T>;
+}
+
+impl<T> FieldState<T> for Unset {
+    fn get(&self) -> Option<&T> {
+        None
+    }
+}
+
+impl<T> FieldState<T> for Set<T> {
+    fn get(&self) -> Option<&T> {
+        Some(&self.0)
+    }
+}
@@ -0,0 +1,11 @@
+/// A trait for objects that can check their own consistency.
+///
+/// This trait is used by [`using_validated`](crate::using_validated) to check the target after the
+/// block has been evaluated.
+pub trait Validate {
+    /// The type of the error returned if the object is not valid.
+    type Error;
+
+    /// Checks whether the object is valid.
+    fn validate(&self) -> Result<(), Self::Error>;
+}
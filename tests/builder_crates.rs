@@ -0,0 +1,74 @@
+//! The builders generated by other crates work with `using!` without any glue code.
+
+use using::using;
+
+#[derive(Debug, PartialEq, derive_builder::Builder)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, PartialEq, typed_builder::TypedBuilder)]
+struct Client {
+    host: String,
+    #[builder(default = 5)]
+    retries: u32,
+}
+
+#[derive(Debug, PartialEq, bon::Builder)]
+struct Proxy {
+    server: Server,
+    client: Client,
+}
+
+#[test]
+fn derive_builder() {
+    let server = using!(ServerBuilder::default() => {
+        .host("localhost".to_string());
+        .port(8080);
+        .build()
+    });
+    assert_eq!(server.unwrap(), Server { host: "localhost".to_string(), port: 8080 });
+
+    let missing = using!(ServerBuilder::default() => {
+        .port(8080);
+        .build()
+    });
+    assert!(missing.is_err());
+
+    fn build(host: &str) -> Result<Server, ServerBuilderError> {
+        Ok(using!(ServerBuilder::default() => {
+            .host(host.to_string());
+            .port(80);
+            .build()?
+        }))
+    }
+    assert_eq!(build("a").unwrap().port, 80);
+}
+
+#[test]
+fn typed_builder() {
+    let client = using!(Client::builder() => {
+        move .host("localhost".to_string());
+        move .retries(2);
+        .build()
+    });
+    assert_eq!(client, Client { host: "localhost".to_string(), retries: 2 });
+
+    let client = using!(Client::builder() => {
+        move .host("localhost".to_string());
+        .build()
+    });
+    assert_eq!(client.retries, 5);
+}
+
+#[test]
+fn bon() {
+    let proxy = using!(Proxy::builder() => {
+        move .server(Server { host: "a".to_string(), port: 80 });
+        move .client(Client { host: "b".to_string(), retries: 1 });
+        .build()
+    });
+    assert_eq!(proxy.server.port, 80);
+    assert_eq!(proxy.client.retries, 1);
+}
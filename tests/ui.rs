@@ -0,0 +1,11 @@
+//! The diagnostics of the declarative macros without the `macros` feature, which are reported with
+//! `compile_error!` for the whole invocation (see `using-macros/tests/ui.rs` for the diagnostics
+//! with the feature). Run with `TRYBUILD=overwrite` to update the expected errors.
+
+#![cfg(not(feature = "macros"))]
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
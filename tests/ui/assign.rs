@@ -0,0 +1,7 @@
+use using::using;
+
+fn main() {
+    let _ = using!(vec![1, 2, 3] => {
+        .last_mut().unwrap() += 1;
+    });
+}
@@ -0,0 +1,104 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Error, Fields, FnArg, ItemStruct, Pat, ReturnType, TraitItemFn};
+
+/// The methods of the wrapped builder listed in `#[adapter(...)]`.
+pub(crate) struct Methods(Vec<TraitItemFn>);
+
+impl Parse for Methods {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut methods = Vec::new();
+        while !input.is_empty() {
+            let method: TraitItemFn = input.parse()?;
+            if let Some(block) = &method.default {
+                return Err(Error::new_spanned(block, "expected `;`, the method is forwarded to the wrapped builder"));
+            }
+            if let Some(receiver) = method.sig.receiver() {
+                return Err(Error::new_spanned(receiver, "the receiver is implied, remove it"));
+            }
+            if !matches!(method.sig.output, ReturnType::Default) {
+                return Err(Error::new_spanned(&method.sig.output, "the methods of the wrapped builder are expected to return `Self`, remove the return type"));
+            }
+            methods.push(method);
+        }
+        Ok(Methods(methods))
+    }
+}
+
+/// Implements `#[adapter(...)]`.
+pub(crate) fn expand(methods: Methods, item: ItemStruct) -> syn::Result<TokenStream> {
+    let inner = match &item.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+        _ => return Err(Error::new_spanned(&item.ident, "`adapter` can only be used on tuple structs with a single field, e.g. `struct Name(Builder);`")),
+    };
+
+    let ItemStruct { attrs, vis, ident, generics, .. } = &item;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let expect = format!("the builder of `{}` is missing, because a previous method panicked", ident);
+
+    let methods = methods.0.iter().map(|method| {
+        let sig = &method.sig;
+        let name = &sig.ident;
+        let method_attrs = &method.attrs;
+        let (method_generics, _, method_where_clause) = sig.generics.split_for_impl();
+        let (args, types): (Vec<_>, Vec<_>) = sig.inputs.iter().enumerate().map(|(i, arg)| match arg {
+            FnArg::Typed(arg) => {
+                let name = match &*arg.pat {
+                    Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+                    _ => format_ident!("arg{}", i),
+                };
+                (name, &*arg.ty)
+            }
+            FnArg::Receiver(_) => unreachable!("receivers are rejected while parsing"),
+        }).unzip();
+        let doc = format!("Calls `{}` of the wrapped builder.", name);
+        quote! {
+            #(#method_attrs)*
+            #[doc = #doc]
+            #vis fn #name #method_generics (&mut self, #( #args: #types ),*) #method_where_clause {
+                let builder = ::core::option::Option::take(&mut self.0).expect(#expect);
+                self.0 = ::core::option::Option::Some(builder.#name(#(#args),*));
+            }
+        }
+    });
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis struct #ident #generics (::core::option::Option<#inner>) #where_clause;
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Wraps `builder`.
+            #vis fn new(builder: #inner) -> Self {
+                #ident(::core::option::Option::Some(builder))
+            }
+
+            /// Returns the wrapped builder.
+            #vis fn into_inner(self) -> #inner {
+                self.0.expect(#expect)
+            }
+
+            #(#methods)*
+        }
+
+        impl #impl_generics ::core::convert::From<#inner> for #ident #ty_generics #where_clause {
+            fn from(builder: #inner) -> Self {
+                #ident::new(builder)
+            }
+        }
+
+        impl #impl_generics ::core::ops::Deref for #ident #ty_generics #where_clause {
+            type Target = #inner;
+
+            fn deref(&self) -> &#inner {
+                self.0.as_ref().expect(#expect)
+            }
+        }
+
+        impl #impl_generics ::core::ops::DerefMut for #ident #ty_generics #where_clause {
+            fn deref_mut(&mut self) -> &mut #inner {
+                self.0.as_mut().expect(#expect)
+            }
+        }
+    })
+}
@@ -0,0 +1,881 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, Fields, GenericParam, Generics, Ident, LitStr, Path, Token, Type, Visibility};
+
+use crate::setters::{option_inner, SetterOptions};
+
+/// The options of the `#[builder(...)]` attributes of the struct.
+#[derive(Default)]
+struct Options {
+    /// Whether the builder tracks the set fields in its type (`#[builder(typestate)]`).
+    typestate: bool,
+    /// Whether all setters, `new` and `build` are `const fn` (`#[builder(const)]`).
+    constness: bool,
+    /// Whether a getter `get_name` is generated for each field (`#[builder(getters)]`).
+    getters: bool,
+    /// Whether the builder implements `Deserialize` of `serde` (`#[builder(deserialize)]`).
+    deserialize: bool,
+}
+
+impl Options {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("typestate") {
+                    options.typestate = true;
+                    Ok(())
+                } else if meta.path.is_ident("const") {
+                    options.constness = true;
+                    Ok(())
+                } else if meta.path.is_ident("getters") {
+                    options.getters = true;
+                    Ok(())
+                } else if meta.path.is_ident("deserialize") {
+                    options.deserialize = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown builder option"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// The options of the `#[builder(...)]` attributes of a field.
+#[derive(Default)]
+struct FieldOptions {
+    /// The default value of the field if it is not set (`#[builder(default)]` or
+    /// `#[builder(default = expression)]`).
+    default: Option<TokenStream>,
+    /// A function checking a new value of the field (`#[builder(validator = path)]`).
+    validator: Option<Path>,
+    /// The name of the setter of the field (`#[builder(rename = "name")]`).
+    rename: Option<Ident>,
+    /// The visibility of the setters of the field (`#[builder(vis = "visibility")]`).
+    vis: Option<Visibility>,
+    /// Whether the setters of the field taking `&mut self` are `const fn` (`#[builder(const)]`).
+    constness: bool,
+    /// The value of the field if it is not stored in the builder and has no setters, which is
+    /// computed in `build` (`#[builder(skip)]` or `#[builder(skip = expression)]`).
+    skip: Option<TokenStream>,
+}
+
+impl FieldOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = FieldOptions::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("builder")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    options.default = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<Expr>()?.into_token_stream()
+                    } else {
+                        quote!(::core::default::Default::default())
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    options.skip = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<Expr>()?.into_token_stream()
+                    } else {
+                        quote!(::core::default::Default::default())
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("validator") {
+                    options.validator = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    options.rename = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("vis") {
+                    options.vis = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("const") {
+                    options.constness = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown builder option"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// A field of the struct.
+struct BuilderField<'a> {
+    name: &'a Ident,
+    ty: &'a Type,
+    /// The name of the setter.
+    setter_name: Ident,
+    /// The visibility of the setters.
+    vis: Visibility,
+    /// `const` if the setters taking `&mut self` are `const fn`.
+    constness: Option<Token![const]>,
+    options: FieldOptions,
+    setter: SetterOptions,
+    /// The type of the parameter `value` of the setter.
+    param: TokenStream,
+    /// The expression converting `value` to `ty`.
+    value: TokenStream,
+}
+
+impl BuilderField<'_> {
+    /// Whether the field can be omitted, in which case it is not tracked in the type of the
+    /// typestate builder.
+    fn is_optional(&self) -> bool {
+        self.options.default.is_some() || self.setter.strip_option || self.setter.each.is_some()
+    }
+
+    /// Whether the default of the field is applied by `build`, in which case the builder stores an
+    /// `Option` (e.g. `Option<Option<T>>` for `strip_option` fields), so it knows whether the field
+    /// is set, e.g. for `merge`. Other optional fields are stored directly in the builder and
+    /// initialized with their default value.
+    fn has_default(&self) -> bool {
+        self.options.default.is_some() && self.setter.each.is_none()
+    }
+
+    /// Returns the initial value of the field in the builder if the field is optional.
+    fn default(&self) -> TokenStream {
+        match &self.options.default {
+            Some(_) if self.has_default() => quote!(::core::option::Option::None),
+            Some(default) => default.clone(),
+            // `None` instead of `Default::default()`, which cannot be used in `const fn new`
+            None if self.setter.strip_option => quote!(::core::option::Option::None),
+            None => quote!(::core::default::Default::default()),
+        }
+    }
+
+    /// Returns the type of the optional field in the builder.
+    fn storage(&self) -> TokenStream {
+        let ty = self.ty;
+        if self.has_default() { quote!(::core::option::Option<#ty>) } else { quote!(#ty) }
+    }
+
+    /// Returns the value of the optional field in `build`, where `builder` is the builder.
+    fn value(&self, builder: &TokenStream) -> TokenStream {
+        let name = self.name;
+        match &self.options.default {
+            Some(default) if self.has_default() => quote! {
+                match #builder.#name {
+                    ::core::option::Option::Some(value) => value,
+                    ::core::option::Option::None => #default,
+                }
+            },
+            _ => quote!(#builder.#name),
+        }
+    }
+
+    /// Returns the name of the variant of the error for a missing value of the field.
+    fn missing(&self) -> Ident {
+        format_ident!("Missing{}", upper_camel_case(&self.name.unraw().to_string()))
+    }
+
+    /// Returns the name of the variant of the value enum for the field.
+    fn variant(&self) -> Ident {
+        format_ident!("{}", upper_camel_case(&self.name.unraw().to_string()))
+    }
+
+    /// Returns the name of the variant of the error for an invalid value of the field.
+    fn invalid(&self) -> Ident {
+        format_ident!("Invalid{}", upper_camel_case(&self.name.unraw().to_string()))
+    }
+
+    /// Returns the statement returning an error of type `error` if the validator rejects `value`.
+    fn check(&self, error: &Ident) -> Option<TokenStream> {
+        let validator = self.options.validator.as_ref()?;
+        let invalid = self.invalid();
+        Some(quote! {
+            if let ::core::result::Result::Err(message) = #validator(&value) {
+                return ::core::result::Result::Err(#error::#invalid(message));
+            }
+        })
+    }
+
+    /// Returns a setter of the field taking `&mut self` and storing `wrap(value)`, which returns a
+    /// `Result` if the field has a validator.
+    fn mut_setter(&self, error: &Ident, wrap: impl FnOnce(TokenStream) -> TokenStream) -> TokenStream {
+        let BuilderField { name, setter_name, vis, constness, param, value, .. } = self;
+        let attrs = self.setter.attrs(name);
+        let fallible = self.options.validator.is_some().then_some(error);
+        let with_setter = self.setter.with_setter(vis, setter_name, param, fallible);
+        match self.check(error) {
+            Some(check) => {
+                let store = wrap(quote!(value));
+                quote! {
+                    #attrs
+                    ///
+                    /// # Errors
+                    ///
+                    /// Returns an error if the validator rejects the value.
+                    #vis #constness fn #setter_name(&mut self, value: #param) -> ::core::result::Result<(), #error> {
+                        let value = #value;
+                        #check
+                        self.#name = #store;
+                        ::core::result::Result::Ok(())
+                    }
+
+                    #with_setter
+                }
+            }
+            None => {
+                let store = wrap(value.clone());
+                quote! {
+                    #attrs
+                    #vis #constness fn #setter_name(&mut self, value: #param) {
+                        self.#name = #store;
+                    }
+
+                    #with_setter
+                }
+            }
+        }
+    }
+
+    /// Returns the getter `get_name` returning the value of the field if it is set, where `state` is
+    /// the type parameter of the state of a required field in the typestate mode.
+    fn getter(&self, state: Option<&Ident>) -> TokenStream {
+        let BuilderField { name, ty, vis, constness, .. } = self;
+        let getter = format_ident!("get_{}", name);
+        let doc = format!("Returns `{}` if it is set.", name);
+        let (output, value, bound) = match state {
+            Some(state) => (
+                quote!(#ty),
+                quote!(::using::typestate::FieldState::get(&self.#name)),
+                Some(quote!(where #state: ::using::typestate::FieldState<#ty>)),
+            ),
+            None if self.setter.strip_option => {
+                let inner = option_inner(ty).expect("checked when parsing the setter options");
+                let value = if self.has_default() {
+                    quote! {
+                        match &self.#name {
+                            ::core::option::Option::Some(::core::option::Option::Some(value)) => ::core::option::Option::Some(value),
+                            _ => ::core::option::Option::None,
+                        }
+                    }
+                } else {
+                    quote!(::core::option::Option::as_ref(&self.#name))
+                };
+                (quote!(#inner), value, None)
+            }
+            None if self.is_optional() && !self.has_default() => (quote!(#ty), quote!(::core::option::Option::Some(&self.#name)), None),
+            None => (quote!(#ty), quote!(::core::option::Option::as_ref(&self.#name)), None),
+        };
+        // trait methods cannot be called in a `const fn`
+        let constness = constness.as_ref().filter(|_| state.is_none());
+        quote! {
+            #[doc = #doc]
+            #vis #constness fn #getter(&self) -> ::core::option::Option<&#output> #bound {
+                #value
+            }
+        }
+    }
+
+    /// Returns the statement of `merge` overriding the value of the field in `self` with the value
+    /// in `other` if it is set. Items of `each` fields are added.
+    fn merge(&self) -> TokenStream {
+        let name = self.name;
+        if self.setter.each.is_some() {
+            quote!(::core::iter::Extend::extend(&mut self.#name, other.#name);)
+        } else {
+            quote! {
+                if ::core::option::Option::is_some(&other.#name) {
+                    self.#name = other.#name;
+                }
+            }
+        }
+    }
+
+    /// Returns the setters of the field taking `&mut self`, if the field is stored directly in the
+    /// builder.
+    fn mut_setters(&self, error: &Ident) -> syn::Result<TokenStream> {
+        let BuilderField { name, ty, setter_name, vis, constness, .. } = self;
+        let (setter, none_setter) = if self.has_default() {
+            let wrap = |value| quote!(::core::option::Option::Some(#value));
+            (self.mut_setter(error, wrap), self.setter.none_setter(vis, constness, name, setter_name, wrap))
+        } else {
+            (self.mut_setter(error, |value| value), self.setter.none_setter(vis, constness, name, setter_name, |value| value))
+        };
+        let each_setter = self.setter.each_setter(vis, name, ty)?;
+        Ok(quote! {
+            #setter
+            #none_setter
+            #each_setter
+        })
+    }
+}
+
+/// The struct or the variant of an enum a builder is generated for.
+struct Target {
+    /// The path constructing the struct or variant, e.g. `Name` or `Name::Variant`.
+    path: TokenStream,
+    /// The path in the documentation.
+    doc: String,
+    /// The name of the builder.
+    builder: Ident,
+    /// The name of the associated function of the struct or enum returning the builder.
+    builder_fn: Ident,
+    /// The name of the enum of the values of the fields, which is used by `set_field`.
+    value: Ident,
+}
+
+/// Implements `#[derive(UsingBuilder)]`.
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let targets = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => vec![ (Target {
+                path: quote!(#name),
+                doc: name.to_string(),
+                builder: format_ident!("{}Builder", name),
+                builder_fn: format_ident!("builder"),
+                value: format_ident!("{}FieldValue", name),
+            }, fields.named.iter().collect::<Vec<_>>()) ],
+            _ => return Err(Error::new_spanned(name, "`UsingBuilder` can only be derived for structs with named fields")),
+        },
+        Data::Enum(data) => data.variants.iter().map(|variant| {
+            let fields = match &variant.fields {
+                Fields::Named(fields) => fields.named.iter().collect(),
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(_) => return Err(Error::new_spanned(variant, "`UsingBuilder` can only be derived for enums whose variants have named fields")),
+            };
+            let variant = &variant.ident;
+            Ok((Target {
+                path: quote!(#name::#variant),
+                doc: format!("{}::{}", name, variant),
+                builder: format_ident!("{}{}Builder", name, variant),
+                builder_fn: format_ident!("{}_builder", snake_case(&variant.unraw().to_string())),
+                value: format_ident!("{}{}FieldValue", name, variant),
+            }, fields))
+        }).collect::<syn::Result<_>>()?,
+        Data::Union(_) => return Err(Error::new_spanned(name, "`UsingBuilder` can only be derived for structs and enums")),
+    };
+
+    let container = Options::parse(&input.attrs)?;
+    if container.typestate && container.deserialize {
+        return Err(Error::new_spanned(name, "`deserialize` cannot be combined with `typestate`"));
+    }
+    let setter_options = SetterOptions::parse_struct(&input.attrs)?;
+    targets.into_iter().map(|(target, fields)| {
+        let mut builder_fields = Vec::new();
+        // the fields without setters and their values
+        let mut skipped = Vec::new();
+        for field in fields {
+            let name = field.ident.as_ref().unwrap();
+            let mut options = FieldOptions::parse(&field.attrs)?;
+            if let Some(skip) = options.skip.take() {
+                let FieldOptions { default: None, validator: None, rename: None, vis: None, constness: false, .. } = options else {
+                    return Err(Error::new_spanned(name, "`skip` cannot be combined with other builder options"));
+                };
+                skipped.push((name, skip));
+                continue;
+            }
+            let setter = setter_options.clone().parse_field(&field.attrs)?;
+            if let (Some(validator), Some(_)) = (&options.validator, &setter.each) {
+                return Err(Error::new_spanned(validator, "`validator` cannot be combined with `each`"));
+            }
+            // deserializing the builder would bypass the validator
+            if let (Some(validator), true) = (&options.validator, container.deserialize) {
+                return Err(Error::new_spanned(validator, "`validator` cannot be combined with `deserialize`"));
+            }
+            let (param, value) = setter.param(&field.ty)?;
+            let constness = (options.constness || container.constness).then(<Token![const]>::default);
+            // trait methods like `Into::into` and `Extend::extend` cannot be called in a `const fn`
+            if constness.is_some() && (setter.into || setter.each.is_some()) {
+                return Err(Error::new_spanned(name, "`const` setters cannot be combined with `into` or `each`"));
+            }
+            let setter_name = options.rename.clone().unwrap_or_else(|| name.clone());
+            let vis = options.vis.clone().unwrap_or_else(|| input.vis.clone());
+            builder_fields.push(BuilderField { name, ty: &field.ty, setter_name, vis, constness, options, setter, param, value });
+        }
+        if container.typestate {
+            typestate(&input, &container, &target, &builder_fields, &skipped)
+        } else {
+            checked(&input, &container, &target, &builder_fields, &skipped)
+        }
+    }).collect()
+}
+
+/// Generates a builder that checks whether all required fields are set in `build`.
+fn checked(input: &DeriveInput, options: &Options, target: &Target, fields: &[BuilderField], skipped: &[(&Ident, TokenStream)]) -> syn::Result<TokenStream> {
+    let vis = &input.vis;
+    let name = &input.ident;
+    let Target { path, builder, builder_fn, value: value_enum, .. } = target;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let error = format_ident!("{}Error", builder);
+    let constness = options.constness.then(<Token![const]>::default);
+
+    let names: Vec<_> = fields.iter().map(|field| field.name).collect();
+    let storage = fields.iter().map(|field| {
+        let ty = field.ty;
+        if field.is_optional() { field.storage() } else { quote!(::core::option::Option<#ty>) }
+    });
+    let setters = fields.iter().map(|field| {
+        if field.is_optional() {
+            field.mut_setters(&error)
+        } else {
+            Ok(field.mut_setter(&error, |value| quote!(::core::option::Option::Some(#value))))
+        }
+    }).collect::<syn::Result<Vec<_>>>()?;
+    let getters = fields.iter().filter(|_| options.getters).map(|field| field.getter(None));
+    let merges = fields.iter().map(BuilderField::merge);
+    let required: Vec<_> = fields.iter().filter(|field| !field.is_optional()).map(|field| field.name).collect();
+    let required_names = required.iter().map(|name| name.unraw().to_string());
+    let required_count = required.len();
+    // the fields that can be set with `set_field`, i.e. all fields with setters of the visibility
+    // of the struct, and their values
+    let dynamic: Vec<_> = fields.iter().filter(|field| field.options.vis.is_none()).collect();
+    let value_variants = dynamic.iter().map(|field| {
+        let variant = field.variant();
+        let ty = if field.setter.strip_option {
+            option_inner(field.ty).expect("checked when parsing the setter options")
+        } else {
+            field.ty
+        };
+        let doc = format!("A value of `{}`.", field.name);
+        quote! {
+            #[doc = #doc]
+            #variant(#ty)
+        }
+    });
+    let set_arms = dynamic.iter().map(|field| {
+        let variant = field.variant();
+        let setter = &field.setter_name;
+        if field.options.validator.is_some() {
+            quote!(#value_enum::#variant(value) => self.#setter(value))
+        } else {
+            quote!(#value_enum::#variant(value) => ::core::result::Result::Ok(self.#setter(value)))
+        }
+    });
+    // a variant using the generics of the struct, which might only be used by skipped fields
+    let (marker_variant, marker_arm) = if generics.params.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(quote! {
+                #[doc(hidden)]
+                __Marker(::core::convert::Infallible, ::core::marker::PhantomData<fn() -> #name #ty_generics>),
+            }),
+            Some(quote!(#value_enum::__Marker(never, _) => match never { },)),
+        )
+    };
+    let value_doc = format!("A value of a field of [`{}`], which can be set with [`{}::set_field`].", target.doc, builder);
+    let defaults = fields.iter().map(|field| {
+        if field.is_optional() { field.default() } else { quote!(::core::option::Option::None) }
+    });
+    let error_enum = error_enum(vis, builder, fields, true);
+    let values = fields.iter().map(|field| {
+        let name = field.name;
+        if field.is_optional() {
+            field.value(&quote!(self))
+        } else {
+            let variant = field.missing();
+            quote! {
+                match self.#name {
+                    ::core::option::Option::Some(value) => value,
+                    ::core::option::Option::None => return ::core::result::Result::Err(#error::#variant),
+                }
+            }
+        }
+    });
+    let build = construct(path, &names, values, skipped);
+    // building only fails if a required field is missing
+    let conversion = if fields.iter().all(BuilderField::is_optional) {
+        let from = construct(path, &names, fields.iter().map(|field| field.value(&quote!(builder))), skipped);
+        quote! {
+            impl #impl_generics ::core::convert::From<#builder #ty_generics> for #name #ty_generics #where_clause {
+                #[allow(deprecated)]
+                fn from(builder: #builder #ty_generics) -> Self {
+                    #from
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::core::convert::TryFrom<#builder #ty_generics> for #name #ty_generics #where_clause {
+                type Error = #error;
+
+                fn try_from(builder: #builder #ty_generics) -> ::core::result::Result<Self, Self::Error> {
+                    #builder::build(builder)
+                }
+            }
+        }
+    };
+    let builder_doc = format!("A builder for [`{}`].", target.doc);
+    let builder_fn_doc = format!("Creates a [`{}`] with no fields set.", builder);
+    let new_doc = "Creates a builder with no fields set.";
+    // missing fields keep their values of `new`, i.e. they are not set or have their default
+    let (deserialize, skip) = if options.deserialize {
+        (Some(quote! {
+            #[derive(::using::__private::serde::Deserialize)]
+            #[serde(crate = "::using::__private::serde", default)]
+        }), Some(quote!(#[serde(skip)])))
+    } else {
+        (None, None)
+    };
+
+    Ok(quote! {
+        #[doc = #builder_doc]
+        #deserialize
+        #vis struct #builder #generics #where_clause {
+            #( #names: #storage, )*
+            #skip
+            __marker: ::core::marker::PhantomData<fn() -> #name #ty_generics>,
+        }
+
+        impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+            fn default() -> Self {
+                #builder::new()
+            }
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #[doc = #new_doc]
+            #vis #constness fn new() -> Self {
+                #builder {
+                    #( #names: #defaults, )*
+                    __marker: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #(#setters)*
+            #(#getters)*
+
+            /// Sets the fields that are set in `other`, so the values of `other` take precedence,
+            /// e.g. to layer a configuration. The items of collections with an `each` setter are
+            /// added.
+            #vis fn merge(&mut self, other: Self) {
+                #(#merges)*
+            }
+
+            /// Sets the field of `value` with its setter, e.g. for values provided at runtime by a
+            /// plugin or a script.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the validator of the field rejects the value.
+            #[allow(deprecated)]
+            #vis fn set_field(&mut self, value: #value_enum #ty_generics) -> ::core::result::Result<(), #error> {
+                match value {
+                    #( #set_arms, )*
+                    #marker_arm
+                }
+            }
+
+            /// Returns the names of the required fields that are not set, in the order of their
+            /// declaration, e.g. to report what is left to configure before calling `build`.
+            #vis fn missing_fields(&self) -> impl ::core::iter::Iterator<Item = &'static str> {
+                let fields: [(&'static str, bool); #required_count] = [
+                    #( (#required_names, ::core::option::Option::is_none(&self.#required)), )*
+                ];
+                ::core::iter::Iterator::filter_map(::core::iter::IntoIterator::into_iter(fields), |(name, missing)| missing.then_some(name))
+            }
+
+            /// Builds the object.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error naming the first required field that is not set.
+            #[allow(deprecated)]
+            #vis #constness fn build(self) -> ::core::result::Result<#name #ty_generics, #error> {
+                ::core::result::Result::Ok(#build)
+            }
+        }
+
+        #error_enum
+
+        #[doc = #value_doc]
+        #vis enum #value_enum #generics #where_clause {
+            #( #value_variants, )*
+            #marker_variant
+        }
+
+        impl #impl_generics ::using::UsingBuild for #builder #ty_generics #where_clause {
+            type Output = ::core::result::Result<#name #ty_generics, #error>;
+
+            fn build(self) -> Self::Output {
+                #builder::build(self)
+            }
+        }
+
+        #conversion
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = #builder_fn_doc]
+            #vis #constness fn #builder_fn() -> #builder #ty_generics {
+                #builder::new()
+            }
+        }
+    })
+}
+
+/// Generates a builder that tracks the set fields in its type, i.e. it has a type parameter for
+/// each required field, which is either `Unset` or `Set<Type>`, and `build` is only implemented if
+/// all required fields are set. Optional fields are stored directly and have `&mut self` setters.
+fn typestate(input: &DeriveInput, options: &Options, target: &Target, fields: &[BuilderField], skipped: &[(&Ident, TokenStream)]) -> syn::Result<TokenStream> {
+    let vis = &input.vis;
+    let name = &input.ident;
+    let Target { path, builder, builder_fn, .. } = target;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let args = generic_args(&input.generics);
+    let error = format_ident!("{}Error", builder);
+    let constness = options.constness.then(<Token![const]>::default);
+
+    let names: Vec<_> = fields.iter().map(|field| field.name).collect();
+    // the state of each required field, which is named by the index of the field, since names
+    // derived from the field names could collide (e.g. for `_x` and `x`)
+    let states: Vec<_> = fields.iter().enumerate().map(|(i, field)| {
+        (!field.is_optional()).then(|| format_ident!("__State{}", i))
+    }).collect();
+    let storage = fields.iter().zip(&states).map(|(field, state)| {
+        match state {
+            Some(state) => quote!(#state),
+            None => field.storage(),
+        }
+    });
+    let defaults = fields.iter().map(|field| {
+        if field.is_optional() { field.default() } else { quote!(::using::typestate::Unset) }
+    });
+    let set: Vec<_> = fields.iter().zip(&states)
+        .filter(|(_, state)| state.is_some())
+        .map(|(field, _)| {
+            let ty = field.ty;
+            quote!(::using::typestate::Set<#ty>)
+        })
+        .collect();
+    let values = fields.iter().map(|field| {
+        let name = field.name;
+        if field.is_optional() { field.value(&quote!(self)) } else { quote!(self.#name.0) }
+    });
+    let build = construct(path, &names, values, skipped);
+
+    // the generics of the builder: the generics of the struct and a state for each required field
+    let mut generics = input.generics.clone();
+    generics.params.extend(states.iter().flatten().map(|state| -> GenericParam {
+        syn::parse_quote!(#state = ::using::typestate::Unset)
+    }));
+    let (builder_impl_generics, builder_ty_generics, _) = generics.split_for_impl();
+
+    let setters = fields.iter().enumerate().map(|(i, field)| {
+        if field.is_optional() {
+            return field.mut_setters(&error);
+        }
+        let BuilderField { name, setter_name, vis, param, value, .. } = field;
+        let attrs = field.setter.attrs(name);
+        let check = field.check(&error);
+
+        // the states of the builder after the field is set
+        let set_states = fields.iter().zip(&states).enumerate().filter_map(|(j, (field, state))| {
+            let ty = field.ty;
+            let state = state.as_ref()?;
+            Some(if i == j { quote!(::using::typestate::Set<#ty>) } else { quote!(#state) })
+        });
+        let set_fields = names.iter().enumerate().map(|(j, name)| {
+            if i == j { quote!(#name: ::using::typestate::Set(value)) } else { quote!(#name: self.#name) }
+        });
+        let set_builder = quote! {
+            #builder {
+                #( #set_fields, )*
+                __marker: ::core::marker::PhantomData,
+            }
+        };
+        Ok(match check {
+            Some(check) => quote! {
+                #attrs
+                ///
+                /// # Errors
+                ///
+                /// Returns an error if the validator rejects the value.
+                #vis fn #setter_name(self, value: #param) -> ::core::result::Result<#builder <#( #args, )* #( #set_states ),*>, #error> {
+                    let value = #value;
+                    #check
+                    ::core::result::Result::Ok(#set_builder)
+                }
+            },
+            None => quote! {
+                #attrs
+                #vis fn #setter_name(self, value: #param) -> #builder <#( #args, )* #( #set_states ),*> {
+                    let value = #value;
+                    #set_builder
+                }
+            },
+        })
+    }).collect::<syn::Result<Vec<_>>>()?;
+    let getters = fields.iter().zip(&states).filter(|_| options.getters).map(|(field, state)| field.getter(state.as_ref()));
+
+    // the error is only needed for validators
+    let error_enum = fields.iter().any(|field| field.options.validator.is_some())
+        .then(|| error_enum(vis, builder, fields, false));
+    let builder_doc = format!("A builder for [`{}`], which tracks the set fields in its type.", target.doc);
+    let builder_fn_doc = format!("Creates a [`{}`] with no fields set.", builder);
+    let new_doc = "Creates a builder with no fields set.";
+
+    Ok(quote! {
+        #[doc = #builder_doc]
+        #vis struct #builder #generics #where_clause {
+            #( #names: #storage, )*
+            __marker: ::core::marker::PhantomData<fn() -> #name #ty_generics>,
+        }
+
+        impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+            fn default() -> Self {
+                #builder::new()
+            }
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            #[doc = #new_doc]
+            #vis #constness fn new() -> Self {
+                #builder {
+                    #( #names: #defaults, )*
+                    __marker: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl #builder_impl_generics #builder #builder_ty_generics #where_clause {
+            #(#setters)*
+            #(#getters)*
+        }
+
+        #error_enum
+
+        impl #impl_generics #builder <#( #args, )* #( #set ),*> #where_clause {
+            /// Builds the object.
+            #[allow(deprecated)]
+            #vis #constness fn build(self) -> #name #ty_generics {
+                #build
+            }
+        }
+
+        impl #impl_generics ::using::UsingBuild for #builder <#( #args, )* #( #set ),*> #where_clause {
+            type Output = #name #ty_generics;
+
+            fn build(self) -> Self::Output {
+                #builder::build(self)
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#builder <#( #args, )* #( #set ),*>> for #name #ty_generics #where_clause {
+            fn from(builder: #builder <#( #args, )* #( #set ),*>) -> Self {
+                #builder::build(builder)
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[doc = #builder_fn_doc]
+            #vis #constness fn #builder_fn() -> #builder #ty_generics {
+                #builder::new()
+            }
+        }
+    })
+}
+
+/// Returns the expression constructing the struct or variant `path` from the `values` of the
+/// fields `names` and the values of the `skipped` fields. The values are bound to variables named
+/// like the fields first, so the values of the skipped fields can use the other fields.
+fn construct(path: &TokenStream, names: &[&Ident], values: impl IntoIterator<Item = TokenStream>, skipped: &[(&Ident, TokenStream)]) -> TokenStream {
+    let values = values.into_iter();
+    let (skipped_names, skipped_values): (Vec<_>, Vec<_>) = skipped.iter().cloned().unzip();
+    quote! {
+        {
+            #( let #names = #values; )*
+            #( let #skipped_names = #skipped_values; )*
+            #path {
+                #( #names, )*
+                #( #skipped_names, )*
+            }
+        }
+    }
+}
+
+/// Generates the error of the builder `builder`, which contains a variant for each field with a
+/// validator and, if `checked` is `true`, for each required field.
+fn error_enum(vis: &Visibility, builder: &Ident, fields: &[BuilderField], checked: bool) -> TokenStream {
+    let error = format_ident!("{}Error", builder);
+    let error_doc = format!("The error returned by the methods of [`{}`].", builder);
+    let mut variants = Vec::new();
+    let mut messages = Vec::new();
+    for field in fields {
+        let name = field.name;
+        if checked && !field.is_optional() {
+            let variant = field.missing();
+            let doc = format!("The field `{}` is not set.", name);
+            let message = format!("field `{}` is not set", name);
+            variants.push(quote! {
+                #[doc = #doc]
+                #variant
+            });
+            messages.push(quote!(#error::#variant => f.write_str(#message)));
+        }
+        if field.options.validator.is_some() {
+            let variant = field.invalid();
+            let doc = format!("The validator of the field `{}` rejected a value with the contained message.", name);
+            let message = format!("invalid value for field `{}`: {{}}", name);
+            variants.push(quote! {
+                #[doc = #doc]
+                #variant(&'static str)
+            });
+            messages.push(quote!(#error::#variant(message) => ::core::write!(f, #message, message)));
+        }
+    }
+    quote! {
+        #[doc = #error_doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[allow(clippy::enum_variant_names)]
+        #vis enum #error {
+            #( #variants, )*
+        }
+
+        impl ::core::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match *self {
+                    #( #messages, )*
+                }
+            }
+        }
+
+        impl ::core::error::Error for #error { }
+    }
+}
+
+/// Returns the generic arguments corresponding to the generic parameters `generics`.
+fn generic_args(generics: &Generics) -> Vec<TokenStream> {
+    generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+        GenericParam::Type(param) => param.ident.to_token_stream(),
+        GenericParam::Const(param) => param.ident.to_token_stream(),
+    }).collect()
+}
+
+/// Converts an `UpperCamelCase` name to `snake_case`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<_> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        // a new word starts at an uppercase letter following a lowercase letter or a digit, or at
+        // the last uppercase letter of an acronym followed by a lowercase letter (e.g. `HTTPServer`)
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        if c.is_uppercase() && prev.is_some_and(|prev| !prev.is_uppercase() || next.is_some_and(|next| next.is_lowercase())) {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// Converts a `snake_case` name to `UpperCamelCase`.
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars))
+        })
+        .flatten()
+        .collect()
+}
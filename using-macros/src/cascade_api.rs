@@ -0,0 +1,116 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::parse::Parser;
+use syn::{Error, FnArg, ImplItem, ImplItemFn, ItemImpl, Pat, ReturnType};
+
+/// Implements `#[cascade_api]`.
+pub(crate) fn expand(attr: TokenStream, item: ItemImpl) -> syn::Result<TokenStream> {
+    let mut owned = false;
+    syn::meta::parser(|meta| {
+        if meta.path.is_ident("owned") {
+            owned = true;
+            Ok(())
+        } else {
+            Err(meta.error("unknown cascade_api option"))
+        }
+    }).parse2(attr)?;
+
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(Error::new_spanned(path, "`cascade_api` can only be used on inherent impl blocks"));
+    }
+
+    // the names of the items in the impl block, which are not generated again
+    let existing = item.items.iter().filter_map(|item| match item {
+        ImplItem::Fn(item) => Some(item.sig.ident.unraw().to_string()),
+        ImplItem::Const(item) => Some(item.ident.unraw().to_string()),
+        ImplItem::Type(item) => Some(item.ident.unraw().to_string()),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    let wrappers = item.items.iter().filter_map(|item| match item {
+        ImplItem::Fn(method) => wrappers(method, owned, &existing),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    let ItemImpl { generics, self_ty, .. } = &item;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #self_ty #where_clause {
+            #(#wrappers)*
+        }
+    })
+}
+
+/// Returns the wrappers of `method` if it is a setter, i.e. its name starts with `set_`, it takes
+/// `&mut self` and returns nothing, and it is neither `async` nor `unsafe`. Wrappers whose name is
+/// already used by an item in `existing` (e.g. a getter `fn name(&self)`) are skipped.
+fn wrappers(method: &ImplItemFn, owned: bool, existing: &[String]) -> Option<TokenStream> {
+    let sig = &method.sig;
+    let name = sig.ident.unraw().to_string();
+    let short = name.strip_prefix("set_").filter(|short| !short.is_empty())?;
+    let is_mut_self = matches!(sig.inputs.first(), Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_some());
+    if !is_mut_self || !matches!(sig.output, ReturnType::Default) || sig.asyncness.is_some() || sig.unsafety.is_some() {
+        return None;
+    }
+
+    // the parameters of the setter, where patterns are replaced with identifiers
+    let (args, types): (Vec<_>, Vec<_>) = sig.inputs.iter().skip(1).enumerate().map(|(i, arg)| match arg {
+        FnArg::Typed(arg) => {
+            let name = match &*arg.pat {
+                Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+                _ => format_ident!("arg{}", i),
+            };
+            (name, &*arg.ty)
+        }
+        FnArg::Receiver(_) => unreachable!("the receiver is always the first parameter"),
+    }).unzip();
+
+    let vis = &method.vis;
+    let setter = &sig.ident;
+    let cfgs = method.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+    let (generics, _, where_clause) = sig.generics.split_for_impl();
+    let fluent_doc = format!("Calls [`{0}`](Self::{0}) and returns `&mut self`, for method chaining.", setter);
+    let fluent = ident(short, existing).map(|fluent| quote! {
+        #(#cfgs)*
+        #[doc = #fluent_doc]
+        #vis fn #fluent #generics (&mut self, #( #args: #types ),*) -> &mut Self #where_clause {
+            self.#setter(#(#args),*);
+            self
+        }
+    });
+    let with = ident(&format!("with_{}", short), existing).filter(|_| owned).map(|with| {
+        let cfgs = method.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+        let with_doc = format!("Calls [`{0}`](Self::{0}) and returns `self`, for method chaining.", setter);
+        quote! {
+            #(#cfgs)*
+            #[doc = #with_doc]
+            #vis fn #with #generics (mut self, #( #args: #types ),*) -> Self #where_clause {
+                self.#setter(#(#args),*);
+                self
+            }
+        }
+    });
+
+    Some(quote! {
+        #fluent
+        #with
+    })
+}
+
+/// Returns the identifier of a generated method named `name`, which is raw if `name` is a keyword
+/// (e.g. `r#type` for `set_type`), or `None` if `name` is in `existing` or cannot be used as a
+/// method name (e.g. `self`).
+fn ident(name: &str, existing: &[String]) -> Option<Ident> {
+    if existing.iter().any(|existing| existing == name) {
+        None
+    } else if let Ok(ident) = syn::parse_str::<Ident>(name) {
+        Some(ident)
+    } else if matches!(name, "self" | "Self" | "super" | "crate" | "_") {
+        None
+    } else {
+        Some(Ident::new_raw(name, Span::call_site()))
+    }
+}
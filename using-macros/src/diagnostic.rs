@@ -0,0 +1,206 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{Error, Ident, LitStr, Token};
+
+/// Methods that only unwrap or convert a value, which are skipped when deriving a variable name from
+/// a target expression (e.g. `last` for `.last_mut().unwrap()`).
+const WRAPPERS: &[&str] = &[
+    "as_deref", "as_deref_mut", "as_mut", "as_ref", "borrow", "borrow_mut", "clone", "cloned",
+    "copied", "expect", "into", "to_owned", "unwrap", "unwrap_or_default",
+];
+
+/// An invalid use of a target expression (or of its name) reported by the declarative macros.
+pub(crate) enum Diagnostic {
+    /// A target expression in a position where it is not supported, e.g. an `if` condition:
+    /// `position "`if` conditions" . is_empty() ...`.
+    Position(LitStr, TokenStream),
+    /// A target expression followed by an operator: `compound (target.len()) +`.
+    Compound(TokenStream, TokenStream),
+    /// An assignment to the result of a method call: `assign (target.last_mut().unwrap()) =`.
+    Assign(TokenStream, TokenStream),
+    /// A dereferenced target expression: `deref * . last_mut().unwrap() ...`.
+    Deref(TokenStream),
+    /// A binding of a pattern that shadows a target named with `@`: `shadow name`.
+    Shadow(Ident),
+}
+
+impl Parse for Diagnostic {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let kind: Ident = input.parse()?;
+        match kind.to_string().as_str() {
+            "position" => Ok(Diagnostic::Position(input.parse()?, input.parse()?)),
+            "compound" => Ok(Diagnostic::Compound(parse_exp(input)?, input.parse()?)),
+            "assign" => Ok(Diagnostic::Assign(parse_exp(input)?, input.parse()?)),
+            "deref" => {
+                input.parse::<Token![*]>()?;
+                Ok(Diagnostic::Deref(input.parse()?))
+            }
+            "shadow" => Ok(Diagnostic::Shadow(input.call(Ident::parse_any)?)),
+            _ => Err(Error::new_spanned(kind, "unknown diagnostic")),
+        }
+    }
+}
+
+/// Parses a target expression in parentheses and removes the target, i.e. the first identifier.
+fn parse_exp(input: ParseStream) -> syn::Result<TokenStream> {
+    let content;
+    syn::parenthesized!(content in input);
+    let tokens = flatten(content.parse()?);
+    Ok(tokens.into_iter().skip(1).collect())
+}
+
+/// Implements the hidden `diagnostic!` macro.
+///
+/// The leading `.` of a target expression is passed by the declarative macros with their own span,
+/// so the errors point to the tokens following it.
+pub(crate) fn expand(diagnostic: Diagnostic) -> TokenStream {
+    let error = match diagnostic {
+        Diagnostic::Position(what, tokens) => {
+            let chain = chain(tokens);
+            let name = name(&chain);
+            Error::new_spanned(
+                chain.clone().into_iter().skip(1).collect::<TokenStream>(),
+                format!(
+                    "target expressions cannot be used in {}, write `let {} = {};` before it and use `{}` instead, or use `block!`, which has no positional restrictions",
+                    what.value(), name, render(&chain), name,
+                ),
+            )
+        }
+        Diagnostic::Compound(exp, op) => {
+            let chain = chain(exp);
+            let name = name(&chain);
+            let span: TokenStream = chain.clone().into_iter().skip(1).chain(op.clone()).collect();
+            Error::new_spanned(
+                span,
+                format!(
+                    "target expressions cannot be followed by `{}`, write `let {} = {};` before it and use `{}` instead",
+                    render(&op), name, render(&chain), name,
+                ),
+            )
+        }
+        Diagnostic::Assign(exp, op) => {
+            let chain = chain(exp);
+            let name = name(&chain);
+            let span: TokenStream = chain.clone().into_iter().skip(1).chain(op.clone()).collect();
+            Error::new_spanned(
+                span,
+                format!(
+                    "only fields can be assigned in target expressions, write `let {} = {};` and use `*{} {} ...` instead",
+                    name, render(&chain), name, render(&op),
+                ),
+            )
+        }
+        Diagnostic::Deref(tokens) => {
+            let chain = chain(tokens);
+            let name = name(&chain);
+            Error::new_spanned(
+                chain.clone().into_iter().skip(1).collect::<TokenStream>(),
+                format!(
+                    "target expressions cannot be dereferenced, write `let {} = {};` and use `*{}` instead",
+                    name, render(&chain), name,
+                ),
+            )
+        }
+        Diagnostic::Shadow(name) => Error::new_spanned(
+            &name,
+            format!(
+                "the pattern binds `{}`, which shadows the target, so the following target expressions and the result would refer to the new binding, use a different name",
+                name,
+            ),
+        ),
+    };
+    error.into_compile_error()
+}
+
+/// Removes invisible groups, which are introduced by declarative macros around fragments.
+fn flatten(tokens: TokenStream) -> TokenStream {
+    tokens.into_iter().flat_map(|token| match token {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => flatten(group.stream()),
+        token => TokenStream::from(token),
+    }).collect()
+}
+
+/// Returns the leading target expression of `tokens`, i.e. a sequence of field accesses, method
+/// calls (with optional generic arguments) and question mark operators.
+fn chain(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = flatten(tokens).into_iter().collect();
+    let mut end = 0;
+    loop {
+        match &tokens[end..] {
+            [ TokenTree::Punct(dot), TokenTree::Ident(_) | TokenTree::Literal(_), .. ] if dot.as_char() == '.' => {
+                end += 2;
+                if matches!(&tokens[end..], [ TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Punct(lt), .. ] if a.as_char() == ':' && b.as_char() == ':' && lt.as_char() == '<') {
+                    let mut depth = 0;
+                    for token in &tokens[end + 2..] {
+                        end += 1;
+                        match token {
+                            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                            TokenTree::Punct(p) if p.as_char() == '>' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => { }
+                        }
+                    }
+                    end += 2;
+                }
+                if matches!(tokens.get(end), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis) {
+                    end += 1;
+                }
+            }
+            [ TokenTree::Punct(question), .. ] if question.as_char() == '?' && end > 0 => end += 1,
+            _ => break,
+        }
+    }
+    tokens[..end].iter().cloned().collect()
+}
+
+/// Returns a variable name for the value of the target expression `chain`.
+fn name(chain: &TokenStream) -> String {
+    let tokens = chain.clone().into_iter().collect::<Vec<_>>();
+    let names = tokens.windows(2).filter_map(|pair| match pair {
+        [ TokenTree::Punct(dot), TokenTree::Ident(ident) ] if dot.as_char() == '.' => Some(ident.to_string()),
+        _ => None,
+    }).collect::<Vec<_>>();
+    let name = names.iter().rev().find(|name| !WRAPPERS.contains(&name.as_str())).map_or("value", |name| name.as_str());
+    let name = name.strip_prefix("get_").or_else(|| name.strip_prefix("to_")).unwrap_or(name);
+    let name = name.strip_suffix("_mut").unwrap_or(name);
+    if name.is_empty() { "value" } else { name }.to_string()
+}
+
+/// Renders a target expression like it is usually written, e.g. `.get(0).unwrap()`.
+fn render(tokens: &TokenStream) -> String {
+    let mut out = String::new();
+    let mut prev_word = false;
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                out.push_str(&group.stream().to_string());
+                out.push_str(close);
+                prev_word = false;
+            }
+            TokenTree::Punct(punct) => {
+                out.push(punct.as_char());
+                prev_word = false;
+            }
+            token => {
+                if prev_word {
+                    out.push(' ');
+                }
+                out.push_str(&token.to_string());
+                prev_word = true;
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,875 @@
+//! Procedural macros for the [`using`](https://docs.rs/using) crate.
+//!
+//! This crate should not be used directly: the macros are re-exported by the `using` crate. The
+//! derives (`Setters` and `UsingBuilder`) are compiled with the `derive` feature, the remaining
+//! macros with the `macros` feature.
+
+#[cfg(feature = "macros")]
+mod adapter;
+#[cfg(feature = "derive")]
+mod builder;
+#[cfg(feature = "macros")]
+mod cascade_api;
+#[cfg(feature = "macros")]
+mod diagnostic;
+#[cfg(feature = "macros")]
+mod rewrite;
+#[cfg(feature = "derive")]
+mod setters;
+
+#[cfg(any(feature = "derive", feature = "macros"))]
+use proc_macro::TokenStream;
+#[cfg(feature = "macros")]
+use proc_macro2::{Ident, Span};
+#[cfg(feature = "macros")]
+use quote::{quote, ToTokens};
+#[cfg(feature = "macros")]
+use syn::parse::{Parse, ParseStream, Parser};
+#[cfg(any(feature = "derive", feature = "macros"))]
+use syn::parse_macro_input;
+#[cfg(feature = "macros")]
+use syn::{braced, Block, Expr, ExprIf, ItemFn, ItemImpl, ItemStruct, Stmt, Token};
+#[cfg(feature = "derive")]
+use syn::DeriveInput;
+
+/// The input of [`block!`](block): `identifier @ expression => { ... }` or `expression => { ... }`.
+#[cfg(feature = "macros")]
+struct BlockInput {
+    id: Ident,
+    target: Expr,
+    body: proc_macro2::TokenStream,
+}
+
+#[cfg(feature = "macros")]
+impl Parse for BlockInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let id = if input.peek(syn::Ident) && input.peek2(Token![@]) {
+            let id = input.parse()?;
+            input.parse::<Token![@]>()?;
+            id
+        } else {
+            Ident::new("target", Span::mixed_site())
+        };
+        let target = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let content;
+        braced!(content in input);
+        let body = content.parse()?;
+        Ok(BlockInput { id, target, body })
+    }
+}
+
+/// A macro that provides method cascading for an object without positional restrictions.
+///
+/// # Usage
+///
+/// ```plain
+/// block!(expression => { ... })
+///
+/// block!(identifier @ expression => { ... })
+/// ```
+///
+/// Works like [`using`](https://docs.rs/using/latest/using/macro.using.html), but the block is
+/// parsed with `syn` instead of a declarative macro. Therefore, target expressions (expressions
+/// starting with a leading `.`) can be used in every expression position, e.g. in conditions,
+/// function and macro arguments, operands, closures and `return` expressions, and errors in the
+/// block point to the exact location in the source.
+///
+/// A `.` starts a target expression if it does not follow an expression, i.e. `a.b` is a field
+/// access, while `a + .b` and `f(.b)` contain target expressions. A `.` following a block
+/// statement (e.g. `if`, `match` or `{ ... }`) starts a target expression, while a `.` following
+/// such a block in an expression (e.g. `let x = match y { ... }.unwrap();`) does not. Target
+/// expressions in nested invocations of the macros of the `using` crate are left to these macros.
+///
+/// # Examples:
+///
+/// ```
+/// use using::block;
+///
+/// let vec = block!(Vec::new() => {
+///     .push(1);
+///     .push(.len() as i32 + 1);
+///     if .iter().sum::<i32>() < 10 {
+///         .extend([ 3, 4 ].map(|x| x * 2));
+///     }
+///     assert_eq!(.len(), 4);
+/// });
+/// assert_eq!(vec, [ 1, 2, 6, 8 ]);
+///
+/// // Generated code:
+/// //
+/// // let vec = {
+/// //     let mut target = Vec::new();
+/// //     target.push(1);
+/// //     target.push(target.len() as i32 + 1);
+/// //     if target.iter().sum::<i32>() < 10 {
+/// //         target.extend([ 3, 4 ].map(|x| x * 2));
+/// //     }
+/// //     assert_eq!(target.len(), 4);
+/// //     target
+/// // };
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro]
+pub fn block(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as BlockInput);
+    expand_block(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// A macro that returns the code generated by [`block!`](block) as a string.
+///
+/// # Usage
+///
+/// ```plain
+/// using_expand!(expression => { ... })
+///
+/// using_expand!(identifier @ expression => { ... })
+/// ```
+///
+/// Instead of evaluating the block, the macro evaluates to a `&'static str` containing the code
+/// that [`block!`](block) generates for the same input, which can also be used in constants. The
+/// string is created from the actual expansion and formatted with `prettyplease`, therefore the
+/// formatting might differ from the original source. Neither `expression` nor the block are
+/// evaluated or type checked, however, the block must be syntactically valid. Invocations of nested
+/// macros are not expanded.
+///
+/// For blocks that only use target expressions as statements or `let` initializers, the generated
+/// code is the same as the one of [`using`](https://docs.rs/using/latest/using/macro.using.html),
+/// apart from the checks that `using` adds (e.g. for trailing method calls returning `()`), so the
+/// macro helps to understand how the statements of a block are translated without external tools
+/// like `cargo expand`.
+///
+/// # Examples:
+///
+/// ```
+/// use using::using_expand;
+///
+/// const CODE: &str = using_expand!(vec @ Vec::new() => {
+///     .push(1);
+///     if .len() > 1 {
+///         .clear();
+///     }
+/// });
+/// assert_eq!(CODE, "\
+/// {
+///     #[allow(unused_mut)]
+///     let mut vec = Vec::new();
+///     vec.push(1);
+///     if vec.len() > 1 {
+///         vec.clear();
+///     }
+///     vec
+/// }");
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro]
+pub fn using_expand(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as BlockInput);
+    let expansion = match expand_block(input) {
+        Ok(expansion) => expansion,
+        Err(err) => return err.into_compile_error().into(),
+    };
+    // the block is formatted as the body of a function, whose signature is removed again
+    let file = syn::parse_quote!(fn expansion() #expansion);
+    let code = prettyplease::unparse(&file);
+    let body = code.split_once('{').map_or("", |(_, body)| body);
+    let code = format!("{{{}", body.trim_end());
+    quote!(#code).into()
+}
+
+/// Generates the code of [`block!`](block) and [`using_expand!`](using_expand).
+#[cfg(feature = "macros")]
+fn expand_block(input: BlockInput) -> syn::Result<proc_macro2::TokenStream> {
+    let BlockInput { id, target, body } = input;
+    let body = rewrite::targets(body, &id);
+    let stmts = Block::parse_within.parse2(body)?;
+
+    let trailing = match stmts.last() {
+        Some(Stmt::Expr(Expr::If(ExprIf { else_branch: None, .. }) | Expr::While(_) | Expr::ForLoop(_), None)) => false,
+        Some(Stmt::Expr(_, None)) => true,
+        Some(Stmt::Macro(stmt)) => stmt.semi_token.is_none(),
+        _ => false,
+    };
+    let result = if trailing { None } else { Some(&id) };
+
+    Ok(quote! {
+        {
+            #[allow(unused_mut)]
+            let mut #id = #target;
+            #(#stmts)*
+            #result
+        }
+    })
+}
+
+/// An attribute macro that provides method cascading for a receiver throughout a function body.
+///
+/// # Usage
+///
+/// ```plain
+/// #[using(expression)]
+/// fn name(...) { ... }
+/// ```
+///
+/// Within the body of the function, every placeholder `_` that is directly followed by a method
+/// call or field access (e.g. `_.push(1)` or `_.x`) is replaced with `(expression)`. In contrast
+/// to the leading dots of [`using`](https://docs.rs/using/latest/using/macro.using.html), the
+/// placeholders can be used in any expression position, e.g. in conditions, function arguments
+/// and macro invocations. The attribute also works on methods, where `expression` can refer to
+/// `self`.
+///
+/// Because the attributed function has to be valid Rust syntax before the attribute is applied,
+/// target expressions cannot start with a leading `.` and `_` is used instead. For the same reason,
+/// a placeholder cannot directly follow `return` or `break` (e.g. `return _.len()`), in which case
+/// the value has to be bound to a variable first.
+///
+/// Note that `expression` is evaluated for every placeholder, therefore it should be a place
+/// expression like `self.builder` or a cheap expression.
+///
+/// # Examples:
+///
+/// ```
+/// use using::attr::using;
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     values: Vec<i32>,
+///     len: usize,
+/// }
+///
+/// impl Counter {
+///     #[using(self.values)]
+///     fn fill(&mut self, n: i32) {
+///         _.clear();
+///         for i in 0..n {
+///             if !_.contains(&i) {
+///                 _.push(i);
+///             }
+///         }
+///         self.len = _.len();
+///     }
+/// }
+///
+/// let mut counter = Counter::default();
+/// counter.fill(3);
+/// assert_eq!(counter.values, [ 0, 1, 2 ]);
+/// assert_eq!(counter.len, 3);
+///
+/// // Generated code:
+/// //
+/// // fn fill(&mut self, n: i32) {
+/// //     (self.values).clear();
+/// //     for i in 0..n {
+/// //         if !(self.values).contains(&i) {
+/// //             (self.values).push(i);
+/// //         }
+/// //     }
+/// //     self.len = (self.values).len();
+/// // }
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro_attribute]
+pub fn using(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if attr.is_empty() {
+        return syn::Error::new(Span::call_site(), "expected a target expression, e.g. `#[using(self.builder)]`")
+            .into_compile_error()
+            .into();
+    }
+
+    let target = parse_macro_input!(attr as Expr).into_token_stream();
+    let ItemFn { attrs, vis, sig, block } = parse_macro_input!(item as ItemFn);
+    let block = rewrite::placeholders(block.into_token_stream(), &target);
+
+    quote! {
+        #(#attrs)*
+        #vis #sig #block
+    }.into()
+}
+
+/// An attribute macro for impl blocks that generates methods for method chaining for each setter
+/// taking `&mut self`.
+///
+/// # Usage
+///
+/// ```plain
+/// #[cascade_api]
+/// impl Name { ... }
+///
+/// #[cascade_api(owned)]
+/// impl Name { ... }
+/// ```
+///
+/// For each method `fn set_name(&mut self, ...)` in the impl block that does not return anything,
+/// a method `fn name(&mut self, ...) -> &mut Self` is generated, which calls `set_name` and returns
+/// `self`. With `owned`, an additional method `fn with_name(self, ...) -> Self` is generated.
+/// Methods that are `async` or `unsafe` are skipped, and so are generated methods whose name is
+/// already used in the impl block (e.g. by a getter `fn name(&self)`). Generated methods whose name
+/// is a keyword are raw identifiers (e.g. `r#type` for `set_type`). The generated methods have the
+/// same visibility, generics and `#[cfg]` attributes as the setters and are placed in a separate
+/// impl block, which is why the attribute can only be used on inherent impl blocks.
+///
+/// This way, a library can be written in the style designed for
+/// [`using`](https://docs.rs/using/latest/using/macro.using.html), while still serving users who
+/// prefer method chaining.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{attr::cascade_api, using};
+///
+/// #[derive(Default)]
+/// pub struct Request {
+///     url: String,
+///     retries: u32,
+/// }
+///
+/// #[cascade_api(owned)]
+/// impl Request {
+///     pub fn set_url(&mut self, url: impl Into<String>) {
+///         self.url = url.into();
+///     }
+///
+///     pub fn set_retries(&mut self, retries: u32) {
+///         self.retries = retries;
+///     }
+/// }
+///
+/// let request = using!(Request::default() => {
+///     .set_url("a");
+///     .set_retries(3);
+/// });
+///
+/// let mut chained = Request::default();
+/// chained.url("a").retries(3);
+/// assert_eq!((chained.url, chained.retries), (request.url, request.retries));
+///
+/// let owned = Request::default().with_url("b").with_retries(1);
+/// assert_eq!((owned.url.as_str(), owned.retries), ("b", 1));
+///
+/// // Generated code:
+/// //
+/// // impl Request {
+/// //     pub fn url(&mut self, url: impl Into<String>) -> &mut Self {
+/// //         self.set_url(url);
+/// //         self
+/// //     }
+/// //
+/// //     pub fn with_url(mut self, url: impl Into<String>) -> Self {
+/// //         self.set_url(url);
+/// //         self
+/// //     }
+/// //
+/// //     ...
+/// // }
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro_attribute]
+pub fn cascade_api(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemImpl);
+    cascade_api::expand(attr.into(), item).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// An attribute macro that turns a tuple struct into an adapter of a builder designed for method
+/// chaining, which provides setters taking `&mut self`.
+///
+/// # Usage
+///
+/// ```plain
+/// #[adapter(
+///     fn name(parameters...);
+///     ...
+/// )]
+/// struct Name(Builder);
+/// ```
+///
+/// Replaces the field of the struct with an `Option<Builder>` and generates a method
+/// `fn name(&mut self, parameters...)` for each listed method, which takes the builder out of the
+/// option, calls the method `fn name(self, parameters...) -> Self` of the builder and puts the
+/// result back. The listed methods have no receiver and no return type, and can have attributes,
+/// e.g. documentation, and generics. Additionally, the struct gets the methods `new` and
+/// `into_inner` for wrapping and unwrapping the builder, implements `From<Builder>`, and
+/// dereferences to the builder, so its other methods can be used directly.
+///
+/// This way, builders of other crates that consume `self` can be used in cascades of
+/// [`using`](https://docs.rs/using/latest/using/macro.using.html), including conditionals and
+/// loops, without `move` statements.
+///
+/// If a method of the builder panics, the adapter does not contain a builder anymore, therefore
+/// using it afterwards panics as well.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{attr::adapter, using};
+///
+/// #[adapter(
+///     fn name(name: String);
+///     fn stack_size(size: usize);
+/// )]
+/// pub struct ThreadBuilder(std::thread::Builder);
+///
+/// let large = true;
+/// let handle = using!(ThreadBuilder::new(std::thread::Builder::new()) => {
+///     .name("worker".to_string());
+///     if large {
+///         .stack_size(4 * 1024 * 1024);
+///     }
+///     .into_inner().spawn(|| std::thread::current().name().map(String::from)).unwrap()
+/// });
+/// assert_eq!(handle.join().unwrap().as_deref(), Some("worker"));
+///
+/// // Generated code:
+/// //
+/// // pub struct ThreadBuilder(Option<std::thread::Builder>);
+/// //
+/// // impl ThreadBuilder {
+/// //     pub fn name(&mut self, name: String) {
+/// //         let builder = Option::take(&mut self.0).expect("...");
+/// //         self.0 = Some(builder.name(name));
+/// //     }
+/// //
+/// //     ...
+/// // }
+/// ```
+#[cfg(feature = "macros")]
+#[proc_macro_attribute]
+pub fn adapter(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let methods = parse_macro_input!(attr as adapter::Methods);
+    let item = parse_macro_input!(item as ItemStruct);
+    adapter::expand(methods, item).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// A derive macro that generates a setter taking `&mut self` for each field of a struct.
+///
+/// # Usage
+///
+/// ```plain
+/// #[derive(Setters)]
+/// struct Name { ... }
+/// ```
+///
+/// For each field `name: Type`, a method `fn name(&mut self, value: Type)` is generated, which
+/// assigns `value` to the field. The setters have the same visibility as the struct. These are
+/// exactly the setters [`using`](https://docs.rs/using/latest/using/macro.using.html) is designed
+/// for, since they do not need to return `&mut Self` or `Self` to be used in a cascade.
+///
+/// With the attribute `#[setter(into)]` on a field, its setter takes `impl Into<Type>` instead of
+/// `Type` and converts the value, e.g. to pass a `&str` to a setter of a `String` field. With the
+/// attribute on the struct, this applies to all fields.
+///
+/// With the attribute `#[setter(strip_option)]` on a field of type `Option<T>`, its setter takes
+/// `T` and wraps it in `Some`, and an additional setter `fn name_none(&mut self)` sets the field to
+/// `None`.
+///
+/// With the attribute `#[setter(each = "item")]` on a collection field (e.g. `Vec<T>` or
+/// `HashMap<K, V>`), an additional setter `fn item(&mut self, item: T)` adds a single item to the
+/// collection using `Extend`. For maps, i.e. types whose name ends with `Map`, the setter takes a
+/// key and a value instead, e.g. `fn header(&mut self, key: K, value: V)`. The options can be
+/// combined, e.g. `#[setter(each = "header", into)]`, in which case `into` also applies to the
+/// items.
+///
+/// With the attribute `#[setter(with)]` on a field or on the struct, an additional setter
+/// `fn with_name(self, value: Type) -> Self` is generated for method chaining, so the type can be
+/// used both in cascades and in chains of method calls, e.g. `Name::default().with_x(1)`.
+///
+/// The doc comments of a field are appended to the documentation of its setter, and a
+/// `#[deprecated]` attribute on a field is copied to all of its setters.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{using, Setters};
+///
+/// #[derive(Default, Setters)]
+/// pub struct Config {
+///     #[setter(into)]
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let config = using!(Config::default() => {
+///     .host("localhost");
+///     .port(8080);
+/// });
+/// assert_eq!(config.host, "localhost");
+/// assert_eq!(config.port, 8080);
+///
+/// // Generated code:
+/// //
+/// // impl Config {
+/// //     pub fn host(&mut self, value: impl Into<String>) {
+/// //         self.host = value.into();
+/// //     }
+/// //
+/// //     pub fn port(&mut self, value: u16) {
+/// //         self.port = value;
+/// //     }
+/// // }
+/// ```
+#[cfg(feature = "derive")]
+#[proc_macro_derive(Setters, attributes(setter))]
+pub fn derive_setters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    setters::derive(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// A derive macro that generates a builder for a struct or for each variant of an enum, designed to
+/// be used with [`using`](https://docs.rs/using/latest/using/macro.using.html).
+///
+/// # Usage
+///
+/// ```plain
+/// #[derive(UsingBuilder)]
+/// struct Name { ... }
+///
+/// #[derive(UsingBuilder)]
+/// enum Name { Variant { ... }, ... }
+/// ```
+///
+/// Generates a struct `NameBuilder` with the same visibility and generics as the struct (including
+/// lifetimes, const parameters, defaults, bounds and the where clause), which contains an `Option`
+/// for each field of the struct, and an associated function `Name::builder` returning a
+/// `NameBuilder` with no fields set, like `NameBuilder::new()`. For each field `name: Type`, the
+/// builder has a setter `fn name(&mut self, value: Type)`. The method `build(self)` of the builder
+/// creates the struct from the set fields. If a field is not set, `build` returns an error of the
+/// generated enum `NameBuilderError`, which contains a variant `MissingField` for each required
+/// field `field` and implements `Error`. The builder also implements
+/// [`UsingBuild`](https://docs.rs/using/latest/using/trait.UsingBuild.html), so it can be used with
+/// [`using_build`](https://docs.rs/using/latest/using/macro.using_build.html), and the struct
+/// implements `TryFrom<NameBuilder>` using `build`, or `From<NameBuilder>` if all fields are
+/// optional (see below), in which case `build` cannot fail.
+///
+/// In contrast to builders designed for method chaining, the setters take `&mut self` and return
+/// nothing, so they can be used in cascades, including conditionals and loops.
+///
+/// The method `merge(&mut self, other: NameBuilder)` sets the fields that are set in `other`, so
+/// configurations can be layered, e.g. defaults, a file and command line arguments with one call
+/// per layer. The items of `each` fields are added, while the other fields are only overridden if
+/// they are set in `other`, including fields with a default.
+///
+/// The method `missing_fields(&self)` returns an iterator over the names of the required fields
+/// that are not set, e.g. for interactive tools or to report everything that is left to configure
+/// at once instead of only the first missing field returned by `build`.
+///
+/// The method `set_field(&mut self, value: NameFieldValue)` sets a field chosen at runtime, e.g. by
+/// a plugin system or a scripting layer. The enum `NameFieldValue` contains a variant `Field(Type)`
+/// for each field `field` with setters of the visibility of the struct, where `Type` is the type of
+/// the setter (e.g. `T` for `strip_option` fields), so the variant determines the field. The value
+/// is passed to the setter of the field, so validators are applied.
+///
+/// With the attribute `#[builder(default = expression)]` on a field, `build` uses `expression` if
+/// the field is not set instead of returning an error. `#[builder(default)]` uses
+/// `Default::default()` instead.
+///
+/// With the attribute `#[builder(skip = expression)]` on a field, the field has no setters and is
+/// not stored in the builder. Instead, `build` initializes it with `expression`, which can use the
+/// values of the other fields by their names, e.g. `#[builder(skip = format!("{}:{}", host, port))]`
+/// for a computed field or `#[builder(skip = Vec::with_capacity(capacity))]` for an internal cache.
+/// `#[builder(skip)]` uses `Default::default()` instead. `skip` cannot be combined with other
+/// builder options.
+///
+/// With the attribute `#[builder(validator = path)]` on a field, the setter of the field calls the
+/// function `path` with a reference to the new value. The function has the signature
+/// `fn(&Type) -> Result<(), &'static str>`. If it returns an error, the setter does not change
+/// the field and returns an error of the variant `InvalidField` containing the message. Otherwise,
+/// it returns `Ok(())`. These setters can be used with `?` in a cascade, e.g. `.port(443)?;`, or
+/// with [`using_errors`](https://docs.rs/using/latest/using/macro.using_errors.html).
+///
+/// With the attributes `#[builder(rename = "name")]` and `#[builder(vis = "visibility")]` on a
+/// field, the setters of the field are named `name` instead of the name of the field, and have the
+/// visibility `visibility` instead of the visibility of the struct, e.g.
+/// `#[builder(rename = "with_host", vis = "pub(crate)")]`. The additional setter of
+/// `strip_option` is renamed accordingly, e.g. `with_host_none`, and the additional setters of
+/// `strip_option` and `each` have the same visibility.
+///
+/// With the attribute `#[builder(const)]` on a field, the setters of the field taking `&mut self`
+/// are `const fn`, so the builder can be configured in `const` contexts, e.g. for static
+/// configuration tables. This requires that the old value of the field can be dropped in a `const`
+/// context (e.g. `u16` or `&'static str`, but not `String`) and that the validator is a
+/// `const fn`, and it cannot be combined with `into` or `each`. With `#[builder(const)]` on the
+/// struct, all setters taking `&mut self` are `const fn`, as well as `NameBuilder::new`,
+/// `Name::builder` and `build`, where the defaults must be constant expressions.
+///
+/// With the attribute `#[builder(getters)]` on the struct, the builder has a getter
+/// `fn get_name(&self) -> Option<&Type>` for each field, which returns the value of the field if it
+/// is set, so a cascade can branch on the fields that are already set, e.g.
+/// `if .get_port().is_none() { .port(80); }` in
+/// [`block`](macro@block). Since Rust does not support overloading, the getters cannot have the
+/// same names as the setters. The getters of fields with a default return `None` until the field
+/// is set, since the default is only used by `build`, while the getters of `strip_option` fields
+/// return the contained value, i.e. `Option<&T>` instead of `Option<&Option<T>>`.
+///
+/// With the attribute `#[builder(deserialize)]` on the struct, the builder implements
+/// `Deserialize` of the `serde` crate (requires the `serde` feature of `using`), where every field
+/// can be omitted and keeps its initial value, i.e. it is not set or empty. This allows
+/// loading a partial configuration from a file and overriding fields in a cascade before calling
+/// `build`. The fields are deserialized with their names, and `deserialize` cannot be combined
+/// with `typestate` or validators, since deserializing the fields would bypass them.
+///
+/// For an enum, a builder `NameVariantBuilder` with an associated function
+/// `Name::variant_builder` (the name of the variant in `snake_case`) is generated for each variant
+/// as described above, which builds the variant. The variants must have named fields or no fields.
+/// The error enums are called `NameVariantBuilderError` accordingly.
+///
+/// With the attribute `#[builder(typestate)]` on the struct, the builder tracks in its type which
+/// fields are set, so a missing field is a compile-time error instead of a runtime error. In this
+/// mode, `NameBuilder` has a type parameter for each field, which is either
+/// [`Unset`](https://docs.rs/using/latest/using/typestate/struct.Unset.html) or
+/// [`Set<Type>`](https://docs.rs/using/latest/using/typestate/struct.Set.html), the setters take
+/// `self` and return the builder with the field set, and `build(self)` returns the struct directly,
+/// but only exists if all fields are set. Instead of `TryFrom`, the struct implements `From` for
+/// the builder with all fields set. The error enum is only generated if a field has a
+/// validator, and only contains the `InvalidField` variants. There is no `merge` or `set_field`,
+/// since builders with different fields set have different types, and no `missing_fields`, since
+/// the missing fields are already known at compile time. Since the setters change the type of the
+/// builder, they take `self` instead of `&mut self` and are used with `move` statements in
+/// [`using`](https://docs.rs/using/latest/using/macro.using.html), e.g. `move .port(443);`. As
+/// `move` statements are only supported at the top level of the block, required fields cannot be
+/// set in conditionals or loops.
+///
+/// The setters support the attributes `#[setter(into)]`, `#[setter(strip_option)]`,
+/// `#[setter(each = "item")]` and `#[setter(with)]` like the setters of
+/// [`Setters`](derive@Setters), where the setters `with_name` of fields with a validator return a
+/// `Result`. Fields with `strip_option` or `each` are optional like fields with
+/// `#[builder(default)]`, i.e. they are `None` or empty if they are not set. In the typestate
+/// mode, optional fields are not tracked in the type and their setters take `&mut self`, so they
+/// can also be used in conditionals and loops, while `with` and `const` have no effect on the
+/// setters of required fields, which already take `self`.
+///
+/// As for [`Setters`](derive@Setters), the doc comments and `#[deprecated]` attributes of the fields
+/// are copied to their setters.
+///
+/// # Examples:
+///
+/// ```
+/// use using::{using, UsingBuilder};
+///
+/// #[derive(UsingBuilder)]
+/// pub struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let tls = true;
+/// let server = using!(Server::builder() => {
+///     .host("localhost".to_string());
+///     if tls {
+///         .port(443);
+///     } else {
+///         .port(80);
+///     }
+///     .build()
+/// });
+/// let server = server.unwrap();
+/// assert_eq!(server.host, "localhost");
+/// assert_eq!(server.port, 443);
+///
+/// let server = using!(Server::builder() => {
+///     .host("localhost".to_string());
+///     .build()
+/// });
+/// assert_eq!(server.err(), Some(ServerBuilderError::MissingPort));
+///
+/// // Generated code:
+/// //
+/// // pub struct ServerBuilder {
+/// //     host: Option<String>,
+/// //     port: Option<u16>,
+/// // }
+/// //
+/// // impl ServerBuilder {
+/// //     pub fn host(&mut self, value: String) {
+/// //         self.host = Some(value);
+/// //     }
+/// //
+/// //     pub fn port(&mut self, value: u16) {
+/// //         self.port = Some(value);
+/// //     }
+/// //
+/// //     pub fn build(self) -> Result<Server, ServerBuilderError> {
+/// //         Ok(Server {
+/// //             host: match self.host {
+/// //                 Some(value) => value,
+/// //                 None => return Err(ServerBuilderError::MissingHost),
+/// //             },
+/// //             port: match self.port {
+/// //                 Some(value) => value,
+/// //                 None => return Err(ServerBuilderError::MissingPort),
+/// //             },
+/// //         })
+/// //     }
+/// // }
+/// //
+/// // pub enum ServerBuilderError {
+/// //     MissingHost,
+/// //     MissingPort,
+/// // }
+/// //
+/// // impl Server {
+/// //     pub fn builder() -> ServerBuilder {
+/// //         ServerBuilder { host: None, port: None }
+/// //     }
+/// // }
+/// ```
+///
+/// Layering configurations with `merge`:
+///
+/// ```
+/// use using::{using, using_build, UsingBuilder};
+///
+/// #[derive(UsingBuilder)]
+/// pub struct Server {
+///     host: String,
+///     port: u16,
+///     #[setter(strip_option)]
+///     proxy: Option<String>,
+///     #[builder(default = 4)]
+///     threads: u8,
+/// }
+///
+/// let file = using!(Server::builder() => {
+///     .host("example.com".to_string());
+///     .port(8080);
+///     .threads(8);
+/// });
+/// let args = using!(Server::builder() => {
+///     .port(443);
+/// });
+/// let server = using_build!(Server::builder() => {
+///     .proxy("localhost".to_string());
+///     .merge(file);
+///     .merge(args);
+/// });
+/// let server = server.unwrap();
+/// assert_eq!(server.host, "example.com");
+/// assert_eq!(server.port, 443);
+/// assert_eq!(server.proxy.as_deref(), Some("localhost"));
+/// assert_eq!(server.threads, 8);
+/// ```
+///
+/// With `#[builder(typestate)]`:
+///
+/// ```
+/// use using::{using, UsingBuilder};
+///
+/// #[derive(UsingBuilder)]
+/// #[builder(typestate)]
+/// pub struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let server = using!(Server::builder() => {
+///     move .host("localhost".to_string());
+///     move .port(443);
+///     .build()
+/// });
+/// assert_eq!(server.host, "localhost");
+/// assert_eq!(server.port, 443);
+/// ```
+///
+/// Calling `build` without setting all fields does not compile:
+///
+/// ```compile_fail
+/// use using::{using, UsingBuilder};
+///
+/// #[derive(UsingBuilder)]
+/// #[builder(typestate)]
+/// pub struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let server = using!(Server::builder() => {
+///     move .host("localhost".to_string());
+///     .build()
+/// });
+/// ```
+///
+/// With `#[builder(const)]`:
+///
+/// ```
+/// use using::UsingBuilder;
+///
+/// #[derive(UsingBuilder)]
+/// #[builder(const)]
+/// pub struct Route {
+///     path: &'static str,
+///     #[builder(default = 200)]
+///     status: u16,
+/// }
+///
+/// const fn route(path: &'static str, status: Option<u16>) -> Route {
+///     let mut route = Route::builder();
+///     route.path(path);
+///     if let Some(status) = status {
+///         route.status(status);
+///     }
+///     match route.build() {
+///         Ok(route) => route,
+///         Err(_) => panic!("the path is not set"),
+///     }
+/// }
+///
+/// const ROUTES: [Route; 2] = [ route("/", None), route("/old", Some(301)) ];
+/// assert_eq!(ROUTES[0].status, 200);
+/// assert_eq!(ROUTES[1].status, 301);
+/// ```
+///
+/// With `#[builder(deserialize)]`:
+///
+/// ```
+/// use using::{using_build, UsingBuilder};
+///
+/// #[derive(UsingBuilder)]
+/// #[builder(deserialize)]
+/// pub struct Server {
+///     host: String,
+///     port: u16,
+///     #[builder(default = 4)]
+///     threads: u8,
+/// }
+///
+/// let file: ServerBuilder = serde_json::from_str(r#"{ "host": "example.com", "port": 8080 }"#).unwrap();
+/// let server = using_build!(file => {
+///     .port(443);
+/// });
+/// let server = server.unwrap();
+/// assert_eq!(server.host, "example.com");
+/// assert_eq!(server.port, 443);
+/// assert_eq!(server.threads, 4);
+/// ```
+///
+/// For enums:
+///
+/// ```
+/// use using::{using_build, UsingBuilder};
+///
+/// #[derive(Debug, PartialEq, UsingBuilder)]
+/// pub enum Shape {
+///     Circle { radius: f64 },
+///     Rect { width: f64, height: f64 },
+/// }
+///
+/// let shape = using_build!(Shape::rect_builder() => {
+///     .width(2.0);
+///     .height(1.0);
+/// });
+/// assert_eq!(shape, Ok(Shape::Rect { width: 2.0, height: 1.0 }));
+/// ```
+#[cfg(feature = "derive")]
+#[proc_macro_derive(UsingBuilder, attributes(builder, setter))]
+pub fn derive_using_builder(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    builder::derive(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Reports an invalid use of a target expression in the declarative macros of the `using` crate,
+/// pointing to the target expression and suggesting a `let` binding.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[proc_macro]
+pub fn diagnostic(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as diagnostic::Diagnostic);
+    diagnostic::expand(input).into()
+}
@@ -0,0 +1,187 @@
+use proc_macro2::{Delimiter, Group, Ident, Spacing, TokenStream, TokenTree};
+
+/// Replaces each placeholder `_` that is directly followed by a `.` (e.g. `_.push(1)`) with the
+/// target in parentheses, including placeholders inside nested groups and macro invocations.
+pub(crate) fn placeholders(tokens: TokenStream, target: &TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut out = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "_" && is_field_access(tokens.peek()) => {
+                let mut group = Group::new(Delimiter::Parenthesis, target.clone());
+                group.set_span(ident.span());
+                out.push(TokenTree::Group(group));
+            }
+            TokenTree::Group(group) => {
+                let mut rewritten = Group::new(group.delimiter(), placeholders(group.stream(), target));
+                rewritten.set_span(group.span());
+                out.push(TokenTree::Group(rewritten));
+            }
+            token => out.push(token),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// Returns `true` if `token` is a `.` introducing a method call or field access, i.e. it is not
+/// the start of a range operator like `..` or `..=`.
+fn is_field_access(token: Option<&TokenTree>) -> bool {
+    match token {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == '.' && punct.spacing() == Spacing::Alone,
+        _ => false,
+    }
+}
+
+/// Keywords that start a block expression or an item, which are followed by a header and a block
+/// (e.g. `if condition { ... }`).
+const BLOCK_KEYWORDS: &[&str] = &[
+    "async", "else", "enum", "fn", "for", "if", "impl", "loop", "match", "mod", "struct", "trait",
+    "unsafe", "while",
+];
+
+/// Keywords that can be directly followed by an expression.
+const EXPR_KEYWORDS: &[&str] = &[
+    "break", "in", "let", "move", "mut", "return", "static", "yield",
+];
+
+/// The state of [`targets`] after the previous token.
+struct State {
+    /// Whether the previous token ends an expression, i.e. whether a following `.` is a method
+    /// call or field access instead of the start of a target expression.
+    ends_expr: bool,
+    /// Whether the previous token ends a statement.
+    ends_stmt: bool,
+    /// While in the header of a block expression or item, whether it is a statement.
+    header: Option<bool>,
+    /// Whether the last block expression was a statement, which is inherited by `else`.
+    last_header: bool,
+}
+
+/// Prefixes each target expression, i.e. each `.` that is not preceded by an expression (e.g.
+/// `.push(1)`), with `target`, including target expressions inside nested groups and macro
+/// invocations. Invocations of macros of this crate are left untouched, because they have their
+/// own targets.
+pub(crate) fn targets(tokens: TokenStream, target: &Ident) -> TokenStream {
+    let mut state = State {
+        ends_expr: false,
+        ends_stmt: true,
+        header: None,
+        last_header: true,
+    };
+    let mut tokens = tokens.into_iter().peekable();
+    let mut out = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                if BLOCK_KEYWORDS.contains(&name.as_str()) {
+                    if state.header.is_none() {
+                        state.header = Some(if name == "else" { state.last_header } else { state.ends_stmt });
+                    }
+                    state.ends_expr = false;
+                } else {
+                    state.ends_expr = !EXPR_KEYWORDS.contains(&name.as_str());
+                }
+                state.ends_stmt = false;
+                out.push(TokenTree::Ident(ident));
+            }
+            TokenTree::Literal(literal) => {
+                state.ends_expr = true;
+                state.ends_stmt = false;
+                out.push(TokenTree::Literal(literal));
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '.' && punct.spacing() == Spacing::Joint => {
+                // range operators like `..` and `..=`
+                let mut spacing = punct.spacing();
+                out.push(TokenTree::Punct(punct));
+                while spacing == Spacing::Joint {
+                    match tokens.next_if(|next| matches!(next, TokenTree::Punct(p) if matches!(p.as_char(), '.' | '='))) {
+                        Some(TokenTree::Punct(next)) => {
+                            spacing = next.spacing();
+                            out.push(TokenTree::Punct(next));
+                        }
+                        _ => break,
+                    }
+                }
+                state.ends_expr = false;
+                state.ends_stmt = false;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '.' && !state.ends_expr => {
+                let mut name = target.clone();
+                name.set_span(target.span().located_at(punct.span()));
+                out.push(TokenTree::Ident(name));
+                out.push(TokenTree::Punct(punct));
+                state.ends_stmt = false;
+            }
+            TokenTree::Punct(punct) => {
+                match punct.as_char() {
+                    // attributes do not change the state
+                    '#' => { }
+                    ';' => {
+                        state.ends_expr = false;
+                        state.ends_stmt = true;
+                        state.header = None;
+                    }
+                    c => {
+                        state.ends_expr = c == '?';
+                        state.ends_stmt = false;
+                    }
+                }
+                out.push(TokenTree::Punct(punct));
+            }
+            TokenTree::Group(group) => {
+                let is_attribute = matches!(out.last(), Some(TokenTree::Punct(p)) if p.as_char() == '#');
+                let stream = if is_attribute || is_nested_macro(&out) {
+                    group.stream()
+                } else {
+                    targets(group.stream(), target)
+                };
+
+                if is_attribute {
+                    // attributes do not change the state
+                } else if group.delimiter() != Delimiter::Brace {
+                    state.ends_expr = true;
+                    state.ends_stmt = false;
+                } else if let Some(stmt) = state.header.take() {
+                    // the block of a block expression or item
+                    state.last_header = stmt;
+                    state.ends_expr = !stmt;
+                    state.ends_stmt = stmt;
+                } else if state.ends_expr && matches!(out.last(), Some(TokenTree::Ident(_))) {
+                    // a struct expression
+                    state.ends_stmt = false;
+                } else {
+                    // a block expression
+                    state.ends_expr = !state.ends_stmt;
+                }
+
+                let mut rewritten = Group::new(group.delimiter(), stream);
+                rewritten.set_span(group.span());
+                out.push(TokenTree::Group(rewritten));
+            }
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// The macros of the `using` crate with their own target, whose target expressions are not
+/// rewritten. `using_pipe!` makes the same decision with the `@call` arms of `using_pipe_impl!`,
+/// which must list the same macros (checked by `tests/nested_macros.rs`).
+const NESTED_MACROS: &[&str] = &[
+    "using", "using_default", "using_new", "using_in", "using_all", "using_clone", "using_lock",
+    "using_cell", "using_tls", "using_global", "using_ptr", "using_cow", "using_some", "using_take",
+    "using_ok", "using_each", "using_with", "using_do", "using_keep", "using_pipe", "using_lazy",
+    "using_build", "using_validated", "using_errors", "using_catch", "async_using", "cascade",
+    "with", "using_dbg", "using_record", "assert_using", "using_map", "using_set", "using_vec",
+    "using_string", "block", "using_expand", "using_fn",
+];
+
+/// Returns `true` if `out` ends with the name of a macro of this crate followed by `!`.
+fn is_nested_macro(out: &[TokenTree]) -> bool {
+    match out {
+        [.., TokenTree::Ident(name), TokenTree::Punct(bang)] if bang.as_char() == '!' => {
+            NESTED_MACROS.contains(&name.to_string().as_str())
+        }
+        _ => false,
+    }
+}
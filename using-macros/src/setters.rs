@@ -0,0 +1,263 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr, PathArguments, Token, Type, Visibility};
+
+/// The options of the `#[setter(...)]` attributes of a struct or a field.
+#[derive(Clone, Default)]
+pub(crate) struct SetterOptions {
+    /// Whether the setter takes `impl Into<Type>` instead of `Type` (`#[setter(into)]`).
+    pub(crate) into: bool,
+    /// Whether the setter of an `Option<T>` field takes `T` and wraps it in `Some`
+    /// (`#[setter(strip_option)]`).
+    pub(crate) strip_option: bool,
+    /// Whether a consuming setter `with_name` is generated in addition to the setter taking
+    /// `&mut self` (`#[setter(with)]`).
+    with: bool,
+    /// The name of the setter adding a single item to a collection field (`#[setter(each = "name")]`).
+    pub(crate) each: Option<Ident>,
+    /// The doc comments of the field, which are copied to its setter.
+    docs: Vec<Attribute>,
+    /// The `#[deprecated]` attribute of the field, which is copied to all of its setters.
+    deprecated: Option<Attribute>,
+}
+
+impl SetterOptions {
+    /// Parses the `#[setter(...)]` attributes of a struct.
+    pub(crate) fn parse_struct(attrs: &[Attribute]) -> syn::Result<Self> {
+        let options = SetterOptions::default().parse(attrs)?;
+        match &options.each {
+            Some(each) => Err(Error::new_spanned(each, "`each` can only be used on fields")),
+            None => Ok(options),
+        }
+    }
+
+    /// Parses the attributes of a field, which extend the options `self` of the struct, and keeps
+    /// its doc comments and its `#[deprecated]` attribute.
+    pub(crate) fn parse_field(self, attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = self.parse(attrs)?;
+        options.docs = attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect();
+        options.deprecated = attrs.iter().find(|attr| attr.path().is_ident("deprecated")).cloned();
+        Ok(options)
+    }
+
+    /// Parses the `#[setter(...)]` attributes in `attrs`, which extend the options `self`, e.g. the
+    /// options of the struct for a field.
+    pub(crate) fn parse(mut self, attrs: &[Attribute]) -> syn::Result<Self> {
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("setter")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("into") {
+                    self.into = true;
+                    Ok(())
+                } else if meta.path.is_ident("strip_option") {
+                    self.strip_option = true;
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    self.with = true;
+                    Ok(())
+                } else if meta.path.is_ident("each") {
+                    self.each = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown setter option"))
+                }
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the type of the parameter `value` of a setter for a field of type `ty`, and the
+    /// expression converting `value` to `ty`.
+    pub(crate) fn param(&self, ty: &Type) -> syn::Result<(TokenStream, TokenStream)> {
+        if let (true, Some(each)) = (self.strip_option, &self.each) {
+            return Err(Error::new_spanned(each, "`each` cannot be combined with `strip_option`"));
+        }
+        let inner = if self.strip_option {
+            option_inner(ty).ok_or_else(|| Error::new_spanned(ty, "`strip_option` can only be used for fields of type `Option<T>`"))?
+        } else {
+            ty
+        };
+        let (param, value) = if self.into {
+            (quote!(impl ::core::convert::Into<#inner>), quote!(::core::convert::Into::into(value)))
+        } else {
+            (quote!(#inner), quote!(value))
+        };
+        if self.strip_option {
+            Ok((param, quote!(::core::option::Option::Some(#value))))
+        } else {
+            Ok((param, value))
+        }
+    }
+
+    /// Returns the attributes of the setter of the field `name`: a doc comment followed by the doc
+    /// comments of the field, and the `#[deprecated]` attribute of the field.
+    pub(crate) fn attrs(&self, name: &Ident) -> TokenStream {
+        let doc = format!("Sets `{}`.", name);
+        let docs = &self.docs;
+        let separator = (!docs.is_empty()).then(|| quote!(#[doc = ""]));
+        let deprecated = &self.deprecated;
+        quote! {
+            #[doc = #doc]
+            #separator
+            #(#docs)*
+            #deprecated
+        }
+    }
+
+    /// Returns the setter `setter_none` setting the field `name` to `wrap(None)` if `strip_option` is
+    /// set, where `setter` is the name of the setter of the field and `constness` makes it a
+    /// `const fn`.
+    pub(crate) fn none_setter(&self, vis: &Visibility, constness: &Option<Token![const]>, name: &Ident, setter: &Ident, wrap: impl FnOnce(TokenStream) -> TokenStream) -> Option<TokenStream> {
+        if !self.strip_option {
+            return None;
+        }
+        let store = wrap(quote!(::core::option::Option::None));
+        let setter = format_ident!("{}_none", setter);
+        let doc = format!("Sets `{}` to `None`.", name);
+        let deprecated = &self.deprecated;
+        Some(quote! {
+            #[doc = #doc]
+            #deprecated
+            #vis #constness fn #setter(&mut self) {
+                self.#name = #store;
+            }
+        })
+    }
+
+    /// Returns the setter `with_setter` taking and returning `self` if `with` is set, which calls the
+    /// setter `setter` taking `&mut self` with a parameter of type `param`. If `error` is given, the
+    /// setter `setter` returns a `Result` with this error type.
+    pub(crate) fn with_setter(&self, vis: &Visibility, setter: &Ident, param: &TokenStream, error: Option<&Ident>) -> Option<TokenStream> {
+        if !self.with {
+            return None;
+        }
+        let with = format_ident!("with_{}", setter);
+        let doc = format!("Calls [`{0}`](Self::{0}) and returns `self`, for method chaining.", setter);
+        let errors_doc = format!("Returns the error of [`{0}`](Self::{0}).", setter);
+        let deprecated = &self.deprecated;
+        // the setter `setter` is deprecated as well
+        let allow = deprecated.as_ref().map(|_| quote!(#[allow(deprecated)]));
+        Some(match error {
+            Some(error) => quote! {
+                #[doc = #doc]
+                #deprecated
+                ///
+                /// # Errors
+                ///
+                #[doc = #errors_doc]
+                #allow
+                #vis fn #with(mut self, value: #param) -> ::core::result::Result<Self, #error> {
+                    self.#setter(value)?;
+                    ::core::result::Result::Ok(self)
+                }
+            },
+            None => quote! {
+                #[doc = #doc]
+                #deprecated
+                #allow
+                #vis fn #with(mut self, value: #param) -> Self {
+                    self.#setter(value);
+                    self
+                }
+            },
+        })
+    }
+
+    /// Returns the setter adding a single item to the collection field `name` of type `ty` if `each`
+    /// is set. For maps (i.e. types whose name ends with `Map`), the setter takes a key and a value.
+    pub(crate) fn each_setter(&self, vis: &Visibility, name: &Ident, ty: &Type) -> syn::Result<Option<TokenStream>> {
+        let Some(setter) = &self.each else { return Ok(None) };
+        let error = || Error::new_spanned(ty, "`each` can only be used for collections with type parameters, e.g. `Vec<T>`");
+        let Type::Path(path) = ty else { return Err(error()) };
+        let segment = path.path.segments.last().ok_or_else(error)?;
+        let PathArguments::AngleBracketed(args) = &segment.arguments else { return Err(error()) };
+        let mut types = args.args.iter().filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        });
+        let param = |ty| if self.into { quote!(impl ::core::convert::Into<#ty>) } else { quote!(#ty) };
+        let convert = |value| if self.into { quote!(::core::convert::Into::into(#value)) } else { quote!(#value) };
+        let deprecated = &self.deprecated;
+
+        Ok(Some(if segment.ident.to_string().ends_with("Map") {
+            let (key_ty, value_ty) = types.next().zip(types.next()).ok_or_else(error)?;
+            let (key_param, value_param) = (param(key_ty), param(value_ty));
+            let (key, value) = (convert(quote!(key)), convert(quote!(value)));
+            let doc = format!("Inserts an entry into `{}`.", name);
+            quote! {
+                #[doc = #doc]
+                #deprecated
+                #vis fn #setter(&mut self, key: #key_param, value: #value_param) {
+                    ::core::iter::Extend::extend(&mut self.#name, ::core::iter::once((#key, #value)));
+                }
+            }
+        } else {
+            let item_param = param(types.next().ok_or_else(error)?);
+            let item = convert(quote!(item));
+            let doc = format!("Adds an item to `{}`.", name);
+            quote! {
+                #[doc = #doc]
+                #deprecated
+                #vis fn #setter(&mut self, item: #item_param) {
+                    ::core::iter::Extend::extend(&mut self.#name, ::core::iter::once(#item));
+                }
+            }
+        }))
+    }
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+pub(crate) fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Implements `#[derive(Setters)]`.
+pub(crate) fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "`Setters` can only be derived for structs with named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "`Setters` can only be derived for structs")),
+    };
+
+    let vis = &input.vis;
+    let options = SetterOptions::parse_struct(&input.attrs)?;
+    let setters = fields.iter().map(|field| {
+        let name = field.ident.as_ref().unwrap();
+        let options = options.clone().parse_field(&field.attrs)?;
+        let (param, value) = options.param(&field.ty)?;
+        let none_setter = options.none_setter(vis, &None, name, name, |value| value);
+        let each_setter = options.each_setter(vis, name, &field.ty)?;
+        let with_setter = options.with_setter(vis, name, &param, None);
+        let attrs = options.attrs(name);
+        Ok(quote! {
+            #attrs
+            #vis fn #name(&mut self, value: #param) {
+                self.#name = #value;
+            }
+
+            #with_setter
+            #none_setter
+            #each_setter
+        })
+    }).collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#setters)*
+        }
+    })
+}
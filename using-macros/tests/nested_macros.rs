@@ -0,0 +1,27 @@
+//! `block!` and `using_pipe!` must agree on which macros have their own target, so the list of
+//! `NESTED_MACROS` in `src/rewrite.rs` is compared with the `@call` arms of `using_pipe_impl!`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+fn read(path: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+#[test]
+fn nested_macros() {
+    let rewrite = read("src/rewrite.rs");
+    let list = rewrite.split("const NESTED_MACROS: &[&str] = &[").nth(1).unwrap();
+    let list = &list[..list.find("];").unwrap()];
+    let nested: BTreeSet<_> = list.split(',').map(|name| name.trim().trim_matches('"')).filter(|name| !name.is_empty()).collect();
+
+    let lib = read("../src/lib.rs");
+    let pipe: BTreeSet<_> = lib.lines().filter_map(|line| {
+        let name = line.trim().strip_prefix("(@call ")?.split(' ').next()?;
+        (name != "$m:ident").then_some(name)
+    }).collect();
+
+    assert!(nested.contains("using_pipe"));
+    assert_eq!(nested, pipe);
+}
@@ -0,0 +1,9 @@
+//! The diagnostics of the declarative macros reported by `diagnostic!`, which point to the
+//! offending tokens and suggest a fix, and the spans of errors in the generated statements. Run
+//! with `TRYBUILD=overwrite` to update the expected errors.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
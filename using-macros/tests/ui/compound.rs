@@ -0,0 +1,8 @@
+use using::using;
+
+fn main() {
+    let _ = using!(Vec::<i32>::new() => {
+        .push(1);
+        .len() + 1
+    });
+}
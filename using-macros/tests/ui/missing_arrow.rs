@@ -0,0 +1,7 @@
+use using::using;
+
+fn main() {
+    let _ = using!(Vec::<i32>::new() {
+        .push(1);
+    });
+}
@@ -0,0 +1,9 @@
+use using::using;
+
+fn main() {
+    let _ = using!(Vec::<i32>::new() => {
+        if .is_empty() {
+            .push(1);
+        }
+    });
+}
@@ -0,0 +1,9 @@
+use using::using;
+
+fn main() {
+    let _ = using!(vec @ Vec::<i32>::new() => {
+        for mut vec in [vec![1]] {
+            .push(2);
+        }
+    });
+}
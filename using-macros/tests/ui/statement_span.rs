@@ -0,0 +1,19 @@
+use using::using;
+
+#[derive(Default)]
+struct Counter {
+    value: i32,
+}
+
+impl Counter {
+    fn add(&mut self, value: i32) {
+        self.value += value;
+    }
+}
+
+fn main() {
+    let _ = using!(Counter::default() => {
+        .add(1);
+        .add("2");
+    });
+}